@@ -1,11 +1,13 @@
-use std::path::PathBuf;
+use std::{cmp::Reverse, collections::VecDeque, fmt::Display};
 
 use crate::{
     ast::{self},
-    net_ops::NetOps,
+    documentation::{Documentation, DocumentationFinder},
     schema::{self, Type},
+    span::Span,
     tokenizer::Token,
-    util::Error,
+    util::fuzzy_match_scored,
+    validator::{Diagnostic, Validator},
 };
 
 #[derive(Debug, Clone)]
@@ -13,70 +15,350 @@ pub struct SuggestionElem {
     pub name: String,
     pub kind: String,
     pub fuzzy_match_positions: Vec<usize>,
+    pub is_deprecated: bool,
+    // How well the name matches the typed prefix, see `rank_suggestion_elems`.
+    pub score: i32,
+    // Inserted instead of the bare name, unless the name is already followed by its arguments, value or
+    // selection.
+    pub snippet: Option<Snippet>,
 }
 
+/**
+ * Text completing a suggestion with its arguments, value or selection, eg.: `user(id: ) {  }`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub text: String,
+    // Offset of the first empty spot to fill.
+    placeholder: Option<usize>,
+}
+
+impl Snippet {
+    pub fn new(text: &str) -> Snippet {
+        Snippet {
+            text: text.to_string(),
+            placeholder: None,
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    pub fn placeholder(&mut self) {
+        self.placeholder.get_or_insert(self.text.len());
+    }
+
+    // Where the cursor goes within the text: the first placeholder, or the end.
+    pub fn cursor(&self) -> usize {
+        self.placeholder.unwrap_or(self.text.len())
+    }
+}
+
+impl SuggestionElem {
+    // None when the name does not fuzzy match the prefix.
+    pub fn matching(
+        name: &str,
+        kind: String,
+        prefix: &str,
+        is_deprecated: bool,
+    ) -> Option<SuggestionElem> {
+        fuzzy_match_scored(name, prefix).map(|fuzzy_match| SuggestionElem {
+            name: name.to_string(),
+            kind,
+            fuzzy_match_positions: fuzzy_match.positions,
+            is_deprecated,
+            score: fuzzy_match.score,
+            snippet: None,
+        })
+    }
+}
+
+/**
+ * Best matches first, deprecated items below the live ones. Equal scores keep their order, so with no
+ * prefix typed the schema order stays.
+ */
+pub fn rank_suggestion_elems(mut elems: Vec<SuggestionElem>) -> Vec<SuggestionElem> {
+    elems.sort_by_key(|elem| (elem.is_deprecated, Reverse(elem.score)));
+    elems
+}
+
+// How many accepted suggestions are remembered.
+const RECENT_SUGGESTIONS_LIMIT: usize = 20;
+// Score bonus of the most recently accepted name, older ones get proportionally less. Less than what
+// matching the start of the name is worth, so it mostly breaks ties.
+const RECENT_SUGGESTION_BONUS: i32 = 20;
+
 #[derive(Debug, Clone)]
 pub struct Suggestion {
     pub elems: Vec<SuggestionElem>,
     pub token: Option<Token>,
 }
 
-type AnalyzerResult = Result<Option<Suggestion>, Error>;
+/**
+ * Names of recently accepted suggestions, ranked higher the next time they match.
+ */
+pub struct RecentSuggestions {
+    // Most recent first.
+    names: VecDeque<String>,
+}
+
+impl RecentSuggestions {
+    pub fn new() -> RecentSuggestions {
+        RecentSuggestions {
+            names: VecDeque::new(),
+        }
+    }
+
+    pub fn accept(&mut self, name: &str) {
+        self.names.retain(|recent_name| recent_name != name);
+        self.names.push_front(name.to_string());
+        self.names.truncate(RECENT_SUGGESTIONS_LIMIT);
+    }
+
+    pub fn boost(&self, mut suggestion: Suggestion) -> Suggestion {
+        for elem in &mut suggestion.elems {
+            if let Some(age) = self.names.iter().position(|name| name == &elem.name) {
+                elem.score += RECENT_SUGGESTION_BONUS * (RECENT_SUGGESTIONS_LIMIT - age) as i32
+                    / RECENT_SUGGESTIONS_LIMIT as i32;
+            }
+        }
+        suggestion.elems = rank_suggestion_elems(suggestion.elems);
+
+        suggestion
+    }
+}
+
+/**
+ * The document at the cursor does not match the schema, eg.: an unknown field or argument.
+ */
+#[derive(Debug)]
+pub struct AnalyzerError {
+    // Where the offending part of the document starts.
+    pub span: Span,
+    pub message: String,
+}
+
+impl AnalyzerError {
+    fn new(span: Span, message: impl Into<String>) -> AnalyzerError {
+        AnalyzerError {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for AnalyzerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+type AnalyzerResult = Result<Option<Suggestion>, AnalyzerError>;
 
 pub struct Analyzer {
     schema: schema::Schema,
 }
 
 impl Analyzer {
-    pub fn new(
-        net_ops: &NetOps,
-        schema_cache_file_path: &PathBuf,
-        reload_schema: bool,
-    ) -> Analyzer {
-        Analyzer {
-            schema: schema::Schema::new(&net_ops, schema_cache_file_path, reload_schema),
+    pub fn new(schema: schema::Schema) -> Analyzer {
+        Analyzer { schema }
+    }
+
+    pub fn analyze(&self, document: &ast::Document, pos: usize) -> AnalyzerResult {
+        for fragment in &document.fragments {
+            if fragment.range_inclusive().contains(&pos) {
+                return self.find_pos_in_fragment_definition(document, fragment, pos);
+            }
+        }
+
+        match document.operation_at(pos) {
+            Some(root) => self.find_pos_in_root(document, root, pos),
+            None => Ok(None),
         }
     }
 
-    pub fn analyze(&self, root: ast::Root, pos: usize) -> AnalyzerResult {
-        self.find_pos_in_root(&root, pos)
+    pub fn documentation(&self, document: &ast::Document, pos: usize) -> Option<Documentation> {
+        DocumentationFinder::find(&self.schema, document, pos)
     }
 
-    fn find_pos_in_root(&self, root: &ast::Root, pos: usize) -> AnalyzerResult {
-        match root {
-            ast::Root::Query(query) => self.find_pos_in_query(query, pos),
-            ast::Root::Mutation(mutation) => self.find_pos_in_mutation(mutation, pos),
-        }
+    pub fn validate(&self, document: &ast::Document) -> Vec<Diagnostic> {
+        Validator::validate(&self.schema, document)
     }
 
-    fn find_pos_in_query(&self, query: &ast::Query, pos: usize) -> AnalyzerResult {
-        let query_scope = self
-            .schema
-            .type_definition(&self.schema.query_root_name)
-            .ok_or("Query is not found in the schema".to_string())?;
+    pub fn schema(&self) -> &schema::Schema {
+        &self.schema
+    }
+
+    fn find_pos_in_root(
+        &self,
+        document: &ast::Document,
+        root: &ast::Root,
+        pos: usize,
+    ) -> AnalyzerResult {
+        if let Some(variable_definitions) = root.variable_definitions() {
+            if variable_definitions.range_exclusive().contains(&pos) {
+                return self.find_pos_in_variable_definitions(variable_definitions, pos);
+            }
+        }
+
+        for directive in root.directives() {
+            if directive.range_inclusive().contains(&pos) {
+                return self.find_pos_in_directive(directive, pos, root.directive_location());
+            }
+        }
+
+        let (root_name, operation_name) = match root {
+            ast::Root::Query(_) => (Some(&self.schema.query_root_name), "Query"),
+            ast::Root::Mutation(_) => (self.schema.mutation_root_name.as_ref(), "Mutation"),
+            ast::Root::Subscription(_) => {
+                (self.schema.subscription_root_name.as_ref(), "Subscription")
+            }
+        };
+        let root_scope = root_name
+            .and_then(|root_name| self.schema.type_definition(root_name))
+            .ok_or_else(|| {
+                AnalyzerError::new(
+                    root.span(),
+                    format!("{} is not found in the schema", operation_name),
+                )
+            })?;
 
-        if !query.field_list.range_exclusive().contains(&pos) {
+        if !root.field_list().range_exclusive().contains(&pos) {
             return Ok(None);
         }
 
-        self.find_pos_in_field_list(&query.field_list, pos, query_scope)
+        self.find_pos_in_field_list(document, root.field_list(), pos, root_scope)
     }
 
-    fn find_pos_in_mutation(&self, mutation: &ast::Mutation, pos: usize) -> AnalyzerResult {
-        let mutation_scope = self
-            .schema
-            .type_definition(&self.schema.mutation_root_name)
-            .ok_or("Mutation is not found in the schema".to_string())?;
+    fn find_pos_in_fragment_definition(
+        &self,
+        document: &ast::Document,
+        fragment: &ast::FragmentDefinition,
+        pos: usize,
+    ) -> AnalyzerResult {
+        if fragment.type_condition.range_inclusive().contains(&pos) {
+            trace!("Suggestion on a fragment type condition");
+            return Ok(Some(Suggestion {
+                elems: self
+                    .schema
+                    .composite_type_names(&fragment.type_condition.original),
+                token: Some(fragment.type_condition.clone()),
+            }));
+        }
 
-        if !mutation.field_list.range_exclusive().contains(&pos) {
-            return Ok(None);
+        for directive in &fragment.directives {
+            if directive.range_inclusive().contains(&pos) {
+                return self.find_pos_in_directive(
+                    directive,
+                    pos,
+                    ast::DirectiveLocation::FragmentDefinition,
+                );
+            }
         }
 
-        self.find_pos_in_field_list(&mutation.field_list, pos, mutation_scope)
+        if fragment.field_list.range_exclusive().contains(&pos) {
+            let type_name = &fragment.type_condition.original;
+            let scope = self.schema.type_definition(type_name).ok_or_else(|| {
+                AnalyzerError::new(
+                    fragment.type_condition.span,
+                    format!("Missing fragment type: {}", type_name),
+                )
+            })?;
+
+            return self.find_pos_in_field_list(document, &fragment.field_list, pos, scope);
+        }
+
+        Ok(None)
+    }
+
+    fn find_pos_in_variable_definitions(
+        &self,
+        variable_definitions: &ast::VariableDefinitionList,
+        pos: usize,
+    ) -> AnalyzerResult {
+        for definition in &variable_definitions.definitions {
+            if !definition.range_inclusive().contains(&pos) {
+                continue;
+            }
+
+            if definition.variable.name.range_inclusive().contains(&pos) {
+                // Variable names are free form.
+                return Ok(None);
+            }
+
+            let type_name = definition.var_type.named_type();
+            if type_name.range_inclusive().contains(&pos) {
+                trace!("Suggestion on a variable type");
+                return Ok(Some(Suggestion {
+                    elems: self.schema.input_type_names(&type_name.original),
+                    token: Some(type_name.clone()),
+                }));
+            }
+
+            if let Some(default_value) = &definition.default_value {
+                if default_value.range_inclusive().contains(&pos) {
+                    let var_type = self.type_class_of_type_ref(&definition.var_type)?;
+                    return self.find_pos_in_arglist_value(default_value, &var_type, pos);
+                }
+            }
+
+            for directive in &definition.directives {
+                if directive.range_inclusive().contains(&pos) {
+                    return self.find_pos_in_directive(
+                        directive,
+                        pos,
+                        ast::DirectiveLocation::VariableDefinition,
+                    );
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn type_class_of_type_ref(
+        &self,
+        type_ref: &ast::TypeRef,
+    ) -> Result<schema::TypeClass, AnalyzerError> {
+        match type_ref {
+            ast::TypeRef::Named(token) => {
+                let type_definition =
+                    self.schema
+                        .type_definition(&token.original)
+                        .ok_or_else(|| {
+                            AnalyzerError::new(
+                                token.span,
+                                format!("Type {} not found", token.original),
+                            )
+                        })?;
+
+                match type_definition {
+                    Type::Scalar(_) => {
+                        Ok(schema::TypeClass::Scalar(token.original.as_str().into()))
+                    }
+                    Type::Enum(_) => Ok(schema::TypeClass::Enum(token.original.as_str().into())),
+                    Type::InputObject(_) => {
+                        Ok(schema::TypeClass::Input(token.original.as_str().into()))
+                    }
+                    _ => Err(AnalyzerError::new(
+                        token.span,
+                        format!("Type {} is not an input type", token.original),
+                    )),
+                }
+            }
+            ast::TypeRef::List(list) => Ok(schema::TypeClass::List(Box::new(
+                self.type_class_of_type_ref(&list.inner)?,
+            ))),
+            ast::TypeRef::NonNull(non_null) => Ok(schema::TypeClass::NonNull(Box::new(
+                self.type_class_of_type_ref(&non_null.inner)?,
+            ))),
+        }
     }
 
     fn find_pos_in_field_list(
         &self,
+        document: &ast::Document,
         field_list: &ast::FieldList,
         pos: usize,
         scope: &schema::Type,
@@ -85,67 +367,161 @@ impl Analyzer {
 
         for field in &field_list.fields {
             if field.range_inclusive().contains(&pos) {
-                return self.find_pos_in_field(field, pos, scope);
+                return self.find_pos_in_field(document, field_list, field, pos, scope);
             }
         }
 
         // In query but not on fields. -> can offer fields.
-        match scope {
-            &schema::Type::Object(ref inner_type) | &schema::Type::Interface(ref inner_type) => {
-                trace!("Suggestion on all fields of a field list");
-                Ok(Some(Suggestion {
-                    elems: inner_type.field_names(""),
-                    token: None,
-                }))
-            }
-            _ => Ok(None),
+        if !scope.is_composite_type() {
+            return Ok(None);
         }
+        trace!("Suggestion on all fields of a field list");
+        Ok(Some(Suggestion {
+            elems: self
+                .schema
+                .field_names(scope, "", &field_list.unaliased_field_names(None)),
+            token: None,
+        }))
     }
 
     fn find_pos_in_field(
         &self,
+        document: &ast::Document,
+        field_list: &ast::FieldList,
         field: &ast::Field,
         pos: usize,
         scope: &schema::Type,
     ) -> AnalyzerResult {
-        match &field {
-            &ast::Field::Concrete(field) => self.find_pos_in_concrete_field(field, pos, scope),
-            &ast::Field::Union(field) => self.find_pos_in_union_field(field, pos, scope),
+        match field {
+            ast::Field::Concrete(field) => {
+                self.find_pos_in_concrete_field(document, field_list, field, pos, scope)
+            }
+            ast::Field::Union(field) => self.find_pos_in_union_field(document, field, pos, scope),
+            ast::Field::FragmentSpread(field) => {
+                self.find_pos_in_fragment_spread(document, field, pos, scope)
+            }
         }
     }
 
+    fn find_pos_in_fragment_spread(
+        &self,
+        document: &ast::Document,
+        field: &ast::FragmentSpread,
+        pos: usize,
+        scope: &schema::Type,
+    ) -> AnalyzerResult {
+        for directive in &field.directives {
+            if directive.range_inclusive().contains(&pos) {
+                return self.find_pos_in_directive(
+                    directive,
+                    pos,
+                    ast::DirectiveLocation::FragmentSpread,
+                );
+            }
+        }
+
+        if !field.name.range_inclusive().contains(&pos) {
+            return Ok(None);
+        }
+
+        if let Some(fragment) = document.fragment(&field.name.original) {
+            if !self
+                .schema
+                .type_condition_applies(scope, &fragment.type_condition.original)
+            {
+                return Err(AnalyzerError::new(
+                    field.name.span,
+                    format!(
+                        "Fragment {} on {} cannot be spread in {}",
+                        fragment.name.original,
+                        fragment.type_condition.original,
+                        scope.name()
+                    ),
+                ));
+            }
+        }
+
+        trace!("Suggestion on a fragment spread name");
+        let elems = document
+            .fragments
+            .iter()
+            .filter(|fragment| {
+                self.schema
+                    .type_condition_applies(scope, &fragment.type_condition.original)
+            })
+            .filter_map(|fragment| {
+                SuggestionElem::matching(
+                    &fragment.name.original,
+                    format!("Fragment<{}>", fragment.type_condition.original),
+                    &field.name.original,
+                    false,
+                )
+            })
+            .collect();
+
+        Ok(Some(Suggestion {
+            elems: rank_suggestion_elems(elems),
+            token: Some(field.name.clone()),
+        }))
+    }
+
     fn find_pos_in_union_field(
         &self,
+        document: &ast::Document,
         field: &ast::UnionField,
         pos: usize,
         scope: &schema::Type,
     ) -> AnalyzerResult {
         if field.type_name.range_inclusive().contains(&pos) {
             // Autocomplete for the union type.
-            let elems = match &scope {
-                &schema::Type::Union(union_type) => {
-                    union_type.possible_type_names(&field.type_name.original)
-                }
-                &schema::Type::Object(inner_type) | &schema::Type::Interface(inner_type) => {
-                    inner_type.possible_type_names(&field.type_name.original)
-                }
-                _ => return Err("Expected union type. Likely not a valid union scope.".into()),
-            };
+            if !scope.is_composite_type() {
+                return Err(AnalyzerError::new(
+                    field.type_name.span,
+                    "Expected union type. Likely not a valid union scope.",
+                ));
+            }
             trace!("Suggestion on a union type");
             return Ok(Some(Suggestion {
-                elems,
+                elems: self
+                    .schema
+                    .possible_type_condition_names(scope, &field.type_name.original),
                 token: Some(field.type_name.clone()),
             }));
         }
 
+        for directive in &field.directives {
+            if directive.range_inclusive().contains(&pos) {
+                return self.find_pos_in_directive(
+                    directive,
+                    pos,
+                    ast::DirectiveLocation::InlineFragment,
+                );
+            }
+        }
+
         if field.field_list.range_exclusive().contains(&pos) {
             let union_type_name = &field.type_name.original;
             let inner_scope = self
                 .schema
                 .type_definition(union_type_name)
-                .ok_or(format!("Missing union type: {}", union_type_name))?;
+                .ok_or_else(|| {
+                    AnalyzerError::new(
+                        field.type_name.span,
+                        format!("Missing union type: {}", union_type_name),
+                    )
+                })?;
+            if !self.schema.type_condition_applies(scope, union_type_name) {
+                return Err(AnalyzerError::new(
+                    field.type_name.span,
+                    format!(
+                        "Inline fragment on {} cannot be spread in {}",
+                        union_type_name,
+                        scope.name()
+                    ),
+                ));
+            }
 
-            return self.find_pos_in_field_list(&field.field_list, pos, inner_scope);
+            return self.find_pos_in_field_list(document, &field.field_list, pos, inner_scope);
         }
 
         Ok(None)
@@ -153,48 +529,121 @@ impl Analyzer {
 
     fn find_pos_in_concrete_field(
         &self,
+        document: &ast::Document,
+        field_list: &ast::FieldList,
         field: &ast::ConcreteField,
         pos: usize,
         scope: &schema::Type,
     ) -> AnalyzerResult {
+        if let Some(alias) = &field.alias {
+            if alias.range_inclusive().contains(&pos) {
+                // Aliases are free form.
+                return Ok(None);
+            }
+        }
+
         // On field.
         if field.name.range_inclusive().contains(&pos) {
             // On the field name.
             trace!("Suggestion on a concrete field name");
-            match scope {
-                &schema::Type::Object(ref inner_type)
-                | &schema::Type::Interface(ref inner_type) => {
-                    return Ok(Some(Suggestion {
-                        elems: inner_type.field_names(&field.name.original),
-                        token: Some(field.name.clone()),
-                    }));
-                }
-                _ => return Err("Keyword found in a non object/interface scope".into()),
+            if !scope.is_composite_type() {
+                return Err(AnalyzerError::new(
+                    field.name.span,
+                    "Keyword found in a non composite scope",
+                ));
             }
+
+            // Aliased fields can select the same field again.
+            let present = if field.alias.is_some() {
+                vec![]
+            } else {
+                field_list.unaliased_field_names(Some(field))
+            };
+
+            return Ok(Some(Suggestion {
+                elems: self
+                    .schema
+                    .field_names(scope, &field.name.original, &present),
+                token: Some(field.name.clone()),
+            }));
         }
 
         if let Some(arglist) = &field.arglist {
             if arglist.range_exclusive().contains(&pos) {
-                return scope
-                    .field(&field.name.original)
-                    .ok_or(format!("Invalid field {}", field.name.original).into())
+                return self
+                    .schema
+                    .field_definition(scope, &field.name.original)
+                    .ok_or_else(|| {
+                        AnalyzerError::new(
+                            field.name.span,
+                            format!("Invalid field {}", field.name.original),
+                        )
+                    })
                     .and_then(|field_def| self.find_pos_in_arglist(arglist, pos, &field_def.args));
             }
         }
 
+        for directive in &field.directives {
+            if directive.range_inclusive().contains(&pos) {
+                return self.find_pos_in_directive(directive, pos, ast::DirectiveLocation::Field);
+            }
+        }
+
         if let Some(field_list) = &field.field_list {
             if field_list.range_exclusive().contains(&pos) {
                 return self
                     .schema
                     .field_type(scope, &field.name.original)
+                    .map_err(|err| AnalyzerError::new(field.name.span, err.to_string()))
                     .and_then(|subfield_type_definition| {
-                        self.find_pos_in_field_list(field_list, pos, subfield_type_definition)
+                        self.find_pos_in_field_list(
+                            document,
+                            field_list,
+                            pos,
+                            subfield_type_definition,
+                        )
                     });
             }
         }
 
         // Between the gaps (not on key [or key's fieldset]) but on the key+fieldset frame.
-        return Ok(None);
+        Ok(None)
+    }
+
+    fn find_pos_in_directive(
+        &self,
+        directive: &ast::Directive,
+        pos: usize,
+        location: ast::DirectiveLocation,
+    ) -> AnalyzerResult {
+        if directive.name.range_inclusive().contains(&pos) {
+            trace!("Suggestion on a directive name");
+            return Ok(Some(Suggestion {
+                elems: self
+                    .schema
+                    .directive_names(&directive.name.original, location.as_str()),
+                token: Some(directive.name.clone()),
+            }));
+        }
+
+        if let Some(arglist) = &directive.arglist {
+            if arglist.range_exclusive().contains(&pos) {
+                return self
+                    .schema
+                    .directive(&directive.name.original)
+                    .ok_or_else(|| {
+                        AnalyzerError::new(
+                            directive.name.span,
+                            format!("Unknown directive @{}", directive.name.original),
+                        )
+                    })
+                    .and_then(|directive_definition| {
+                        self.find_pos_in_arglist(arglist, pos, &directive_definition.args)
+                    });
+            }
+        }
+
+        Ok(None)
     }
 
     fn find_pos_in_arglist(
@@ -207,11 +656,11 @@ impl Analyzer {
 
         // Inside arglist.
         for arg in &arglist.params {
-            if pos > arg.end_pos {
+            if pos > arg.end_span.offset {
                 continue;
             }
 
-            if pos < arg.start_pos {
+            if pos < arg.span.offset {
                 break;
             }
 
@@ -219,16 +668,22 @@ impl Analyzer {
                 // On arg key.
                 trace!("Suggestion on arglist key: {}", arg.key.original);
                 return Ok(Some(Suggestion {
-                    elems: scope.arg_names(&arg.key.original),
+                    elems: self.with_arg_snippets(
+                        scope.arg_names(&arg.key.original, &arglist.keys(Some(arg))),
+                        scope,
+                    ),
                     token: Some(arg.key.clone()),
                 }));
             } else if arg.value.range_inclusive().contains(&pos) {
                 // We are in the <arg-name>: ______ scope.
                 //                           ^^^^^^
                 // Get the current arg definition.
-                let current_arg = scope
-                    .arg(&arg.key.original)
-                    .ok_or(format!("Invalid arg name {}", &arg.key.original))?;
+                let current_arg = scope.arg(&arg.key.original).ok_or_else(|| {
+                    AnalyzerError::new(
+                        arg.key.span,
+                        format!("Invalid arg name {}", arg.key.original),
+                    )
+                })?;
                 let current_arg_type = &current_arg.arg_type;
 
                 return self.find_pos_in_arglist_value(&arg.value, current_arg_type, pos);
@@ -240,24 +695,51 @@ impl Analyzer {
         // In arglist -> offer key.
         trace!("Suggestion on all arglist fields");
         Ok(Some(Suggestion {
-            elems: scope.arg_names(&String::new()),
+            elems: self.with_arg_snippets(scope.arg_names("", &arglist.keys(None)), scope),
             token: None,
         }))
     }
 
+    fn with_arg_snippets(
+        &self,
+        mut elems: Vec<SuggestionElem>,
+        scope: &schema::ArgList,
+    ) -> Vec<SuggestionElem> {
+        for elem in &mut elems {
+            elem.snippet = scope
+                .arg(&elem.name)
+                .map(|arg| self.schema.arg_snippet(arg));
+        }
+
+        elems
+    }
+
     fn find_pos_in_arglist_value(
         &self,
         value: &ast::ParamValue,
         scope: &schema::TypeClass,
         pos: usize,
     ) -> AnalyzerResult {
+        // Unwrap non-null, and the list type when a single value is given (input coercion).
+        let scope = match scope.skip_non_null() {
+            schema::TypeClass::List(inner) if !matches!(value, ast::ParamValue::List(_)) => {
+                inner.skip_non_null()
+            }
+            scope => scope,
+        };
+
         match &value {
-            crate::ast::ParamValue::Simple(token) => match scope {
-                schema::TypeClass::Enum(enum_type_name) => {
+            crate::ast::ParamValue::Enum(token) => {
+                if let schema::TypeClass::Enum(enum_type_name) = scope {
                     return self
                         .schema
                         .type_definition(enum_type_name)
-                        .ok_or(format!("Enum type {} not found", enum_type_name).into())
+                        .ok_or_else(|| {
+                            AnalyzerError::new(
+                                token.span,
+                                format!("Enum type {} not found", enum_type_name),
+                            )
+                        })
                         .and_then(|ref enum_type| match enum_type {
                             &schema::Type::Enum(inner_type) => {
                                 trace!("Suggestion on enum arglist value");
@@ -266,36 +748,40 @@ impl Analyzer {
                                     token: Some(token.clone()),
                                 }))
                             }
-                            _ => Err("Expected enum type".into()),
-                        })
+                            _ => Err(AnalyzerError::new(token.span, "Expected enum type")),
+                        });
                 }
-                _ => {}
-            },
+            }
             crate::ast::ParamValue::Object(object_arglist) => {
                 // Get the type name of current arg value.
                 let value_type_name = match scope.skip_non_null() {
                     schema::TypeClass::Input(name) => name,
                     _ => {
-                        return Err(
-                            format!("Exected input type for arg value. Got: {:?}", scope).into(),
-                        )
+                        return Err(AnalyzerError::new(
+                            object_arglist.span,
+                            format!("Exected input type for arg value. Got: {:?}", scope),
+                        ))
                     }
                 };
                 // Get the schema type definition of the arg value's type.
                 let value_type = self
                     .schema
                     .type_definition(value_type_name)
-                    .ok_or(format!("Type {} not found.", &value_type_name))?;
+                    .ok_or_else(|| {
+                        AnalyzerError::new(
+                            object_arglist.span,
+                            format!("Type {} not found.", value_type_name),
+                        )
+                    })?;
 
                 // Get the inner args of that type.
                 let value_args = match value_type {
                     Type::InputObject(input_object) => &input_object.args,
                     _ => {
-                        return Err(format!(
-                            "Type {} is expected to be an input object",
-                            &value_type_name
-                        )
-                        .into())
+                        return Err(AnalyzerError::new(
+                            object_arglist.span,
+                            format!("Type {} is expected to be an input object", value_type_name),
+                        ))
                     }
                 };
 
@@ -311,11 +797,10 @@ impl Analyzer {
                         let inner_scope = match &scope.skip_non_null() {
                             schema::TypeClass::List(inner_type_class) => inner_type_class,
                             _ => {
-                                return Err(format!(
-                                    "Exected list type for arg value. Got: {:?}",
-                                    scope
-                                )
-                                .into())
+                                return Err(AnalyzerError::new(
+                                    list.span,
+                                    format!("Exected list type for arg value. Got: {:?}", scope),
+                                ))
                             }
                         };
 
@@ -323,12 +808,25 @@ impl Analyzer {
                     }
                 }
             }
-            crate::ast::ParamValue::Missing(_pos) => match &scope {
-                schema::TypeClass::Enum(enum_type_name) => {
+            crate::ast::ParamValue::Int(_)
+            | crate::ast::ParamValue::Float(_)
+            | crate::ast::ParamValue::String(_)
+            | crate::ast::ParamValue::BlockString(_)
+            | crate::ast::ParamValue::Boolean(_)
+            | crate::ast::ParamValue::Null(_)
+            | crate::ast::ParamValue::Magic(_)
+            | crate::ast::ParamValue::Variable(_) => {}
+            crate::ast::ParamValue::Missing((span, _)) => {
+                if let schema::TypeClass::Enum(enum_type_name) = scope {
                     return self
                         .schema
                         .type_definition(enum_type_name)
-                        .ok_or(format!("Enum type {} not found", enum_type_name).into())
+                        .ok_or_else(|| {
+                            AnalyzerError::new(
+                                *span,
+                                format!("Enum type {} not found", enum_type_name),
+                            )
+                        })
                         .and_then(|ref enum_type| match enum_type {
                             &schema::Type::Enum(inner_type) => {
                                 trace!("Suggestion on all enum options of an arglist value");
@@ -337,13 +835,195 @@ impl Analyzer {
                                     token: None,
                                 }))
                             }
-                            _ => Err("Expected enum type".into()),
-                        })
+                            _ => Err(AnalyzerError::new(*span, "Expected enum type")),
+                        });
                 }
-                _ => {}
-            },
+            }
         }
 
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{parser::Parser, schema::fixture, span::Span, tokenizer::Tokenizer};
+
+    use super::{Analyzer, RecentSuggestions, Suggestion};
+
+    // Suggestion at the `|` marker.
+    fn suggestion(raw_with_cursor: &str) -> Suggestion {
+        let pos = raw_with_cursor.find('|').unwrap();
+        let raw = raw_with_cursor.replacen('|', "", 1);
+        let (document, _) = Parser::new(Tokenizer::tokenize(&raw, false)).parse();
+
+        let analyzer = Analyzer {
+            schema: fixture::schema(),
+        };
+        analyzer.analyze(&document, pos).unwrap().unwrap()
+    }
+
+    fn names(suggestion: Suggestion) -> Vec<String> {
+        suggestion.elems.into_iter().map(|elem| elem.name).collect()
+    }
+
+    // Suggested names at the `|` marker.
+    fn suggestion_names(raw_with_cursor: &str) -> Vec<String> {
+        names(suggestion(raw_with_cursor))
+    }
+
+    #[test]
+    fn test_suggest_fields_not_yet_selected() {
+        assert_eq!(
+            vec!["role", "posts", "__typename", "username"],
+            suggestion_names("{ user(id: 1) { id name | } }")
+        );
+        assert_eq!(
+            vec!["id", "name", "role", "posts", "__typename", "username"],
+            suggestion_names("{ user(id: 1) { id name other: | } }")
+        );
+        assert_eq!(
+            vec!["name", "__typename", "username"],
+            suggestion_names("{ user(id: 1) { na| id } }")
+        );
+        assert_eq!(
+            vec!["__typename", "username"],
+            suggestion_names("{ user(id: 1) { name na| } }")
+        );
+    }
+
+    #[test]
+    fn test_suggest_meta_fields() {
+        assert_eq!(
+            vec!["__typename"],
+            suggestion_names("{ search(term: \"a\") { | } }")
+        );
+        assert_eq!(
+            vec!["__type", "__schema", "__typename"],
+            suggestion_names("{ __| }")
+        );
+        assert_eq!(vec!["__typename"], suggestion_names("mutation { __| }"));
+        assert_eq!(
+            vec!["queryType", "__typename"],
+            suggestion_names("{ __schema { | } }")
+        );
+    }
+
+    #[test]
+    fn test_suggest_possible_type_conditions() {
+        assert_eq!(
+            vec!["User", "Post", "Node", "SearchResult"],
+            suggestion_names("{ search(term: \"a\") { ... on | } }")
+        );
+        assert_eq!(
+            vec!["User", "Node", "SearchResult"],
+            suggestion_names("{ user(id: 1) { ... on | } }")
+        );
+    }
+
+    #[test]
+    fn test_suggestions_are_ranked() {
+        assert_eq!(
+            vec!["name", "__typename", "username"],
+            suggestion_names("{ user(id: 1) { nm| } }")
+        );
+        assert_eq!(
+            vec!["MEMBER", "ADMIN"],
+            suggestion_names("{ users(filter: { role: M| }) { id } }")
+        );
+    }
+
+    #[test]
+    fn test_recent_suggestions_go_first() {
+        let mut recent_suggestions = RecentSuggestions::new();
+        recent_suggestions.accept("posts");
+        recent_suggestions.accept("role");
+
+        assert_eq!(
+            vec!["role", "posts", "id", "name", "__typename", "username"],
+            names(recent_suggestions.boost(suggestion("{ user(id: 1) { | } }")))
+        );
+        // Does not outrank much better matches.
+        recent_suggestions.accept("ADMIN");
+        assert_eq!(
+            vec!["MEMBER", "ADMIN"],
+            names(recent_suggestions.boost(suggestion("{ users(filter: { role: M| }) { id } }")))
+        );
+    }
+
+    // Snippet of the named suggestion, with `|` at the cursor.
+    fn snippet(raw_with_cursor: &str, name: &str) -> Option<String> {
+        suggestion(raw_with_cursor)
+            .elems
+            .into_iter()
+            .find(|elem| elem.name == name)
+            .unwrap()
+            .snippet
+            .map(|snippet| {
+                let mut text = snippet.text.clone();
+                text.insert(snippet.cursor(), '|');
+                text
+            })
+    }
+
+    #[test]
+    fn test_snippets() {
+        assert_eq!(
+            Some("user(id: |) {  }".to_string()),
+            snippet("{ | }", "user")
+        );
+        assert_eq!(
+            Some("posts { | }".to_string()),
+            snippet("{ user(id: 1) { | } }", "posts")
+        );
+        assert_eq!(None, snippet("{ user(id: 1) { | } }", "name"));
+        assert_eq!(
+            Some("createUser(input: { name: | }) {  }".to_string()),
+            snippet("mutation { cre| }", "createUser")
+        );
+        assert_eq!(
+            Some("filter: { | }".to_string()),
+            snippet("{ users(|) { id } }", "filter")
+        );
+        assert_eq!(
+            Some("tags: [|]".to_string()),
+            snippet("mutation { createUser(input: { | }) { id } }", "tags")
+        );
+    }
+
+    #[test]
+    fn test_deprecated_suggestions_go_last() {
+        assert_eq!(
+            vec!["ADMIN", "MEMBER", "GUEST"],
+            suggestion_names("{ users(filter: { role: | }) { id } }")
+        );
+    }
+
+    #[test]
+    fn test_suggest_args_not_yet_given() {
+        assert_eq!(
+            vec!["filter", "offset"],
+            suggestion_names("{ users(first: 1, |) { id } }")
+        );
+        assert_eq!(
+            vec!["first"],
+            suggestion_names("{ users(filter: {}, fir|) { id } }")
+        );
+    }
+
+    #[test]
+    fn test_error_span() {
+        let raw = "{\n  users(bogus: ) { id }\n}";
+        let (document, _) = Parser::new(Tokenizer::tokenize(raw, false)).parse();
+        let analyzer = Analyzer {
+            schema: fixture::schema(),
+        };
+
+        let err = analyzer
+            .analyze(&document, raw.find(": ").unwrap() + 2)
+            .unwrap_err();
+
+        assert_eq!(Span::new(10, 10, 2, 9), err.span);
+        assert_eq!("Invalid arg name bogus at line 2, col 9", err.to_string());
+    }
+}
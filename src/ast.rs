@@ -1,39 +1,351 @@
-use std::ops::{Range, RangeInclusive};
+use std::{
+    fmt,
+    ops::{Range, RangeInclusive},
+};
 
-use crate::tokenizer::Token;
+use crate::{
+    span::Span,
+    tokenizer::{Token, TokenKind},
+};
+
+pub struct Document {
+    pub operations: Vec<Root>,
+    pub fragments: Vec<FragmentDefinition>,
+}
+
+impl Document {
+    pub fn operation_at(&self, pos: usize) -> Option<&Root> {
+        self.operations
+            .iter()
+            .find(|operation| operation.range_inclusive().contains(&pos))
+    }
+
+    pub fn fragment(&self, name: &str) -> Option<&FragmentDefinition> {
+        self.fragments
+            .iter()
+            .find(|fragment| fragment.name.original == name)
+    }
+
+    // Every directive of the document together with the location it is applied on.
+    pub fn directives(&self) -> Vec<(&Directive, DirectiveLocation)> {
+        let mut directives = vec![];
+
+        for root in &self.operations {
+            for directive in root.directives() {
+                directives.push((directive, root.directive_location()));
+            }
+            if let Some(variable_definitions) = root.variable_definitions() {
+                for definition in &variable_definitions.definitions {
+                    for directive in &definition.directives {
+                        directives.push((directive, DirectiveLocation::VariableDefinition));
+                    }
+                }
+            }
+            root.field_list().collect_directives(&mut directives);
+        }
+
+        for fragment in &self.fragments {
+            for directive in &fragment.directives {
+                directives.push((directive, DirectiveLocation::FragmentDefinition));
+            }
+            fragment.field_list.collect_directives(&mut directives);
+        }
+
+        directives
+    }
+
+    pub fn alias_tokens(&self) -> Vec<&Token> {
+        let mut tokens = vec![];
+
+        for root in &self.operations {
+            root.field_list().collect_alias_tokens(&mut tokens);
+        }
+
+        for fragment in &self.fragments {
+            fragment.field_list.collect_alias_tokens(&mut tokens);
+        }
+
+        tokens
+    }
+}
 
 pub enum Root {
     Query(Query),
     Mutation(Mutation),
+    Subscription(Subscription),
+}
+
+impl Root {
+    pub fn name(&self) -> Option<&Token> {
+        match self {
+            Root::Query(query) => query.name.as_ref(),
+            Root::Mutation(mutation) => mutation.name.as_ref(),
+            Root::Subscription(subscription) => subscription.name.as_ref(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Root::Query(query) => query.span,
+            Root::Mutation(mutation) => mutation.span,
+            Root::Subscription(subscription) => subscription.span,
+        }
+    }
+
+    pub fn range_inclusive(&self) -> RangeInclusive<usize> {
+        self.span().offset..=self.field_list().end_span.offset
+    }
+
+    pub fn variable_definitions(&self) -> &Option<VariableDefinitionList> {
+        match self {
+            Root::Query(query) => &query.variable_definitions,
+            Root::Mutation(mutation) => &mutation.variable_definitions,
+            Root::Subscription(subscription) => &subscription.variable_definitions,
+        }
+    }
+
+    pub fn directives(&self) -> &Vec<Directive> {
+        match self {
+            Root::Query(query) => &query.directives,
+            Root::Mutation(mutation) => &mutation.directives,
+            Root::Subscription(subscription) => &subscription.directives,
+        }
+    }
+
+    pub fn field_list(&self) -> &FieldList {
+        match self {
+            Root::Query(query) => &query.field_list,
+            Root::Mutation(mutation) => &mutation.field_list,
+            Root::Subscription(subscription) => &subscription.field_list,
+        }
+    }
+
+    pub fn is_subscription(&self) -> bool {
+        matches!(self, Root::Subscription(_))
+    }
+
+    pub fn directive_location(&self) -> DirectiveLocation {
+        match self {
+            Root::Query(_) => DirectiveLocation::Query,
+            Root::Mutation(_) => DirectiveLocation::Mutation,
+            Root::Subscription(_) => DirectiveLocation::Subscription,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Query {
+    pub span: Span,
+    pub name: Option<Token>,
+    pub variable_definitions: Option<VariableDefinitionList>,
+    pub directives: Vec<Directive>,
     pub field_list: FieldList,
 }
 
 pub struct Mutation {
+    pub span: Span,
+    pub name: Option<Token>,
+    pub variable_definitions: Option<VariableDefinitionList>,
+    pub directives: Vec<Directive>,
+    pub field_list: FieldList,
+}
+
+pub struct Subscription {
+    pub span: Span,
+    pub name: Option<Token>,
+    pub variable_definitions: Option<VariableDefinitionList>,
+    pub directives: Vec<Directive>,
     pub field_list: FieldList,
 }
 
+#[derive(Debug)]
+pub struct VariableDefinitionList {
+    pub span: Span,
+    pub end_span: Span,
+    pub definitions: Vec<VariableDefinition>,
+}
+
+impl VariableDefinitionList {
+    pub fn range_exclusive(&self) -> Range<usize> {
+        self.span.offset..self.end_span.offset
+    }
+}
+
+#[derive(Debug)]
+pub struct VariableDefinition {
+    pub span: Span,
+    pub end_span: Span,
+    pub variable: Variable,
+    pub var_type: TypeRef,
+    pub default_value: Option<ParamValue>,
+    pub directives: Vec<Directive>,
+}
+
+impl VariableDefinition {
+    pub fn range_inclusive(&self) -> RangeInclusive<usize> {
+        self.span.offset..=self.end_span.offset
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Variable {
+    pub span: Span,
+    pub end_span: Span,
+    // The name without the `$` sigil.
+    pub name: Token,
+}
+
+#[derive(Debug)]
+pub enum TypeRef {
+    Named(Token),
+    List(ListTypeRef),
+    NonNull(NonNullTypeRef),
+}
+
+impl TypeRef {
+    #[allow(unused)]
+    pub fn span(&self) -> Span {
+        match self {
+            TypeRef::Named(token) => token.span,
+            TypeRef::List(list) => list.span,
+            TypeRef::NonNull(non_null) => non_null.inner.span(),
+        }
+    }
+
+    pub fn end_span(&self) -> Span {
+        match self {
+            TypeRef::Named(token) => token.end_span(),
+            TypeRef::List(list) => list.end_span,
+            TypeRef::NonNull(non_null) => non_null.end_span,
+        }
+    }
+
+    // The innermost type name, eg.: `ID` for `[ID!]!`.
+    pub fn named_type(&self) -> &Token {
+        match self {
+            TypeRef::Named(token) => token,
+            TypeRef::List(list) => list.inner.named_type(),
+            TypeRef::NonNull(non_null) => non_null.inner.named_type(),
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeRef::Named(token) => write!(f, "{}", token.original),
+            TypeRef::List(list) => write!(f, "[{}]", list.inner),
+            TypeRef::NonNull(non_null) => write!(f, "{}!", non_null.inner),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ListTypeRef {
+    pub span: Span,
+    pub end_span: Span,
+    pub inner: Box<TypeRef>,
+}
+
+#[derive(Debug)]
+pub struct NonNullTypeRef {
+    pub end_span: Span,
+    pub inner: Box<TypeRef>,
+}
+
+#[derive(Debug)]
+pub struct FragmentDefinition {
+    pub span: Span,
+    pub end_span: Span,
+    pub name: Token,
+    pub type_condition: Token,
+    pub directives: Vec<Directive>,
+    pub field_list: FieldList,
+}
+
+impl FragmentDefinition {
+    pub fn range_inclusive(&self) -> RangeInclusive<usize> {
+        self.span.offset..=self.end_span.offset
+    }
+}
+
 #[derive(Debug)]
 pub struct FieldList {
-    pub start_pos: usize,
-    pub end_pos: usize,
+    pub span: Span,
+    pub end_span: Span,
     pub fields: Vec<Field>,
 }
 
 impl FieldList {
-    pub fn new_empty(pos: usize) -> FieldList {
+    pub fn new_empty(span: Span) -> FieldList {
         FieldList {
-            start_pos: pos,
-            end_pos: pos,
+            span,
+            end_span: span,
             fields: vec![],
         }
     }
 
     pub fn range_exclusive(&self) -> Range<usize> {
-        self.start_pos..self.end_pos
+        self.span.offset..self.end_span.offset
+    }
+
+    // Fields selected without an alias (other than `except`). Selecting them again is redundant.
+    pub fn unaliased_field_names(&self, except: Option<&ConcreteField>) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter_map(|field| match field {
+                Field::Concrete(field)
+                    if field.alias.is_none()
+                        && !except.is_some_and(|except| std::ptr::eq(except, field)) =>
+                {
+                    Some(field.name.original.as_str())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn collect_directives<'a>(&'a self, directives: &mut Vec<(&'a Directive, DirectiveLocation)>) {
+        for field in &self.fields {
+            match field {
+                Field::Concrete(field) => {
+                    for directive in &field.directives {
+                        directives.push((directive, DirectiveLocation::Field));
+                    }
+                    if let Some(field_list) = &field.field_list {
+                        field_list.collect_directives(directives);
+                    }
+                }
+                Field::Union(field) => {
+                    for directive in &field.directives {
+                        directives.push((directive, DirectiveLocation::InlineFragment));
+                    }
+                    field.field_list.collect_directives(directives);
+                }
+                Field::FragmentSpread(field) => {
+                    for directive in &field.directives {
+                        directives.push((directive, DirectiveLocation::FragmentSpread));
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_alias_tokens<'a>(&'a self, tokens: &mut Vec<&'a Token>) {
+        for field in &self.fields {
+            match field {
+                Field::Concrete(field) => {
+                    if let Some(alias) = &field.alias {
+                        tokens.push(alias);
+                    }
+                    if let Some(field_list) = &field.field_list {
+                        field_list.collect_alias_tokens(tokens);
+                    }
+                }
+                Field::Union(field) => field.field_list.collect_alias_tokens(tokens),
+                Field::FragmentSpread(_) => {}
+            }
+        }
     }
 }
 
@@ -41,13 +353,15 @@ impl FieldList {
 pub enum Field {
     Concrete(ConcreteField),
     Union(UnionField),
+    FragmentSpread(FragmentSpread),
 }
 
 impl Field {
     pub fn range_inclusive(&self) -> RangeInclusive<usize> {
         match self {
-            Field::Concrete(field) => field.start_pos..=field.end_pos,
-            Field::Union(field) => field.start_pos..=field.end_pos,
+            Field::Concrete(field) => field.span.offset..=field.end_span.offset,
+            Field::Union(field) => field.span.offset..=field.end_span.offset,
+            Field::FragmentSpread(field) => field.span.offset..=field.end_span.offset,
         }
     }
 
@@ -66,71 +380,166 @@ impl Field {
             _ => panic!("Expected to be a concrete field"),
         }
     }
+
+    #[cfg(test)]
+    pub fn as_fragment_spread(&self) -> &FragmentSpread {
+        match &self {
+            Field::FragmentSpread(field) => field,
+            _ => panic!("Expected to be a fragment spread"),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct ConcreteField {
-    pub start_pos: usize,
-    pub end_pos: usize,
+    pub span: Span,
+    pub end_span: Span,
+    pub alias: Option<Token>,
     pub name: Token,
     pub arglist: Option<ArgList>,
+    pub directives: Vec<Directive>,
     pub field_list: Option<FieldList>,
 }
 
 #[derive(Debug)]
 pub struct UnionField {
-    pub start_pos: usize,
-    pub end_pos: usize,
+    pub span: Span,
+    pub end_span: Span,
     pub type_name: Token,
+    pub directives: Vec<Directive>,
     pub field_list: FieldList,
 }
 
+#[derive(Debug)]
+pub struct FragmentSpread {
+    pub span: Span,
+    pub end_span: Span,
+    pub name: Token,
+    pub directives: Vec<Directive>,
+}
+
+#[derive(Debug)]
+pub struct Directive {
+    pub span: Span,
+    pub end_span: Span,
+    // The name without the `@` sigil.
+    pub name: Token,
+    pub arglist: Option<ArgList>,
+}
+
+impl Directive {
+    pub fn range_inclusive(&self) -> RangeInclusive<usize> {
+        self.span.offset..=self.end_span.offset
+    }
+}
+
+// Executable directive locations as listed in the introspection `__DirectiveLocation` enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveLocation {
+    Query,
+    Mutation,
+    Subscription,
+    Field,
+    FragmentDefinition,
+    FragmentSpread,
+    InlineFragment,
+    VariableDefinition,
+}
+
+impl DirectiveLocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectiveLocation::Query => "QUERY",
+            DirectiveLocation::Mutation => "MUTATION",
+            DirectiveLocation::Subscription => "SUBSCRIPTION",
+            DirectiveLocation::Field => "FIELD",
+            DirectiveLocation::FragmentDefinition => "FRAGMENT_DEFINITION",
+            DirectiveLocation::FragmentSpread => "FRAGMENT_SPREAD",
+            DirectiveLocation::InlineFragment => "INLINE_FRAGMENT",
+            DirectiveLocation::VariableDefinition => "VARIABLE_DEFINITION",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ArgList {
-    pub start_pos: usize,
-    pub end_pos: usize,
+    pub span: Span,
+    pub end_span: Span,
     pub params: Vec<ParamKeyValuePair>,
 }
 
 impl ArgList {
     pub fn range_exclusive(&self) -> Range<usize> {
-        self.start_pos..self.end_pos
+        self.span.offset..self.end_span.offset
+    }
+
+    // Keys already given (other than `except`).
+    pub fn keys(&self, except: Option<&ParamKeyValuePair>) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|param| !except.is_some_and(|except| std::ptr::eq(except, *param)))
+            .map(|param| param.key.original.as_str())
+            .collect()
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParamKeyValuePair {
-    pub start_pos: usize,
-    pub end_pos: usize,
+    pub span: Span,
+    pub end_span: Span,
     pub key: Token,
     pub value: ParamValue,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParamValue {
-    Simple(Token),
+    Int(Token),
+    Float(Token),
+    String(Token),
+    BlockString(Token),
+    Boolean(Token),
+    Null(Token),
+    Enum(Token),
+    Magic(Token),
     List(ListParamValue),
     Object(ArgList),
+    Variable(Variable),
     // For error correction reasons a placeholder type.
-    Missing((usize, usize)), // (start_pos, end_pos)
+    Missing((Span, Span)), // (span, end_span)
 }
 
 impl ParamValue {
-    pub fn start_pos(&self) -> usize {
+    pub fn span(&self) -> Span {
         match self {
-            ParamValue::Simple(token) => token.pos,
-            ParamValue::Missing((start_pos, _)) => *start_pos,
-            ParamValue::List(list) => list.start_pos,
-            ParamValue::Object(object) => object.start_pos,
+            ParamValue::Int(token)
+            | ParamValue::Float(token)
+            | ParamValue::String(token)
+            | ParamValue::BlockString(token)
+            | ParamValue::Boolean(token)
+            | ParamValue::Null(token)
+            | ParamValue::Enum(token)
+            | ParamValue::Magic(token) => token.span,
+            ParamValue::Missing((span, _)) => *span,
+            ParamValue::List(list) => list.span,
+            ParamValue::Object(object) => object.span,
+            ParamValue::Variable(variable) => variable.span,
         }
     }
 
-    pub fn end_pos(&self) -> usize {
+    pub fn end_span(&self) -> Span {
         match self {
-            ParamValue::Simple(token) => token.end_pos(),
-            ParamValue::Missing((_, end_pos)) => *end_pos,
-            ParamValue::List(list) => list.end_pos,
-            ParamValue::Object(object) => object.end_pos,
+            ParamValue::Int(token)
+            | ParamValue::Float(token)
+            | ParamValue::String(token)
+            | ParamValue::BlockString(token)
+            | ParamValue::Boolean(token)
+            | ParamValue::Null(token)
+            | ParamValue::Enum(token)
+            | ParamValue::Magic(token) => token.end_span(),
+            ParamValue::Missing((_, end_span)) => *end_span,
+            ParamValue::List(list) => list.end_span,
+            ParamValue::Object(object) => object.end_span,
+            ParamValue::Variable(variable) => variable.end_span,
         }
     }
 
@@ -150,22 +559,52 @@ impl ParamValue {
         }
     }
 
+    pub fn as_scalar(&self) -> &Token {
+        match &self {
+            ParamValue::Int(token)
+            | ParamValue::Float(token)
+            | ParamValue::String(token)
+            | ParamValue::BlockString(token)
+            | ParamValue::Boolean(token)
+            | ParamValue::Null(token)
+            | ParamValue::Enum(token)
+            | ParamValue::Magic(token) => token,
+            _ => panic!("Param value expected to be a scalar type"),
+        }
+    }
+
+    // The unescaped content of string and block string values.
+    #[allow(unused)]
+    pub fn string_value(&self) -> Option<&str> {
+        match &self {
+            ParamValue::String(Token {
+                kind: TokenKind::Str(value),
+                ..
+            })
+            | ParamValue::BlockString(Token {
+                kind: TokenKind::BlockStr(value),
+                ..
+            }) => Some(value),
+            _ => None,
+        }
+    }
+
     #[allow(unused)]
-    pub fn as_simple(&self) -> &Token {
+    pub fn boolean_value(&self) -> Option<bool> {
         match &self {
-            ParamValue::Simple(token) => token,
-            _ => panic!("Param value expected to be a simple type"),
+            ParamValue::Boolean(token) => Some(token.original == "true"),
+            _ => None,
         }
     }
 
     pub fn range_inclusive(&self) -> RangeInclusive<usize> {
-        self.start_pos()..=self.end_pos()
+        self.span().offset..=self.end_span().offset
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ListParamValue {
-    pub start_pos: usize,
-    pub end_pos: usize,
+    pub span: Span,
+    pub end_span: Span,
     pub elems: Vec<ParamValue>,
 }
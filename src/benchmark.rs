@@ -0,0 +1,111 @@
+// Keystroke latency on small and large synthetic schemas. Ignored by default, run them with:
+// `cargo test --release benchmark -- --ignored --nocapture`
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    analyzer::Analyzer, complexity::Complexity, config::ComplexityConfig, parser::Parser,
+    schema::Schema, sdl::SdlDocument, tokenizer::Tokenizer,
+};
+
+const FIELDS_PER_TYPE: usize = 20;
+const ITERATIONS: u32 = 200;
+
+// `type_count` object types with `FIELDS_PER_TYPE` fields each, every field leading to another type, plus an
+// enum and an input type for every object type.
+fn synthetic_schema(type_count: usize) -> Schema {
+    let mut source = String::from("type Query {\n");
+    for i in 0..type_count {
+        source.push_str(&format!(
+            "  type{}(first: Int, filter: Filter{}): [Type{}]\n",
+            i, i, i
+        ));
+    }
+    source.push_str("}\n");
+
+    for i in 0..type_count {
+        source.push_str(&format!(
+            "type Type{} {{\n  id: ID!\n  name: String\n  status: Status{}\n",
+            i, i
+        ));
+        for j in 0..FIELDS_PER_TYPE {
+            source.push_str(&format!(
+                "  field{}(first: Int): Type{}\n",
+                j,
+                (i + j + 1) % type_count
+            ));
+        }
+        source.push_str("}\n");
+        source.push_str(&format!("enum Status{} {{ ACTIVE INACTIVE }}\n", i));
+        source.push_str(&format!(
+            "input Filter{} {{ name: String status: Status{} }}\n",
+            i, i
+        ));
+    }
+
+    let mut document = SdlDocument::default();
+    document.add_source(&source).unwrap();
+    Schema::from_json_value(&document.into_introspection().unwrap()).unwrap()
+}
+
+// What the editor does on every keystroke, see `Editor::refresh_screen`.
+fn keystroke(analyzer: &Analyzer, config: &ComplexityConfig, raw: &str, pos: usize) {
+    let (document, _) = Parser::new(Tokenizer::tokenize(raw, false)).parse();
+
+    black_box(analyzer.analyze(&document, pos).unwrap());
+    black_box(analyzer.documentation(&document, pos));
+    black_box(analyzer.validate(&document));
+    black_box(Complexity::estimate(
+        analyzer.schema(),
+        &document,
+        &document.operations[0],
+        config,
+    ));
+}
+
+fn mean_keystroke_latency(type_count: usize) -> Duration {
+    let analyzer = Analyzer::new(synthetic_schema(type_count));
+    let config = ComplexityConfig::default();
+    // Types near the end of the schema, those were the slowest to look up.
+    let last = type_count - 1;
+    let raw = format!(
+        r#"query {{
+            type{}(first: 5, filter: {{ name: "a", status: ACTIVE }}) {{
+                id name status
+                field3 {{ id field7(first: 2) {{ name field1 {{ na }} }} }}
+            }}
+        }}"#,
+        last
+    );
+    let pos = raw.find("na }").unwrap() + 2;
+
+    // Warms up the allocator and the caches.
+    keystroke(&analyzer, &config, &raw, pos);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        keystroke(&analyzer, &config, &raw, pos);
+    }
+    start.elapsed() / ITERATIONS
+}
+
+#[test]
+#[ignore]
+fn benchmark_keystroke_latency() {
+    let small = mean_keystroke_latency(10);
+    let large = mean_keystroke_latency(5_000);
+
+    println!("keystroke latency with 10 types: {:?}", small);
+    println!("keystroke latency with 5000 types: {:?}", large);
+
+    // Lookups are indexed, the schema size should barely matter.
+    assert!(
+        large < small * 3,
+        "{:?} with the large schema, {:?} with the small one",
+        large,
+        small
+    );
+}
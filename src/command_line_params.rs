@@ -3,7 +3,7 @@ use std::{
     path::PathBuf,
 };
 
-use crate::config::Config;
+use crate::{config::Config, schema::SchemaSource};
 
 #[derive(clap::Parser)]
 #[command(version, about, long_about = None)]
@@ -19,6 +19,29 @@ pub struct CommandLineParams {
 
     #[arg(short, long, value_name = "RELOAD_SCHEMA", default_value_t = false)]
     pub reload_schema: bool,
+
+    // Overrides `schema_file` of the config.
+    #[arg(long, value_name = "SCHEMA_FILE")]
+    pub schema_file: Option<String>,
+
+    #[arg(long, value_name = "MIGRATE_COMMENTS", default_value_t = false)]
+    pub migrate_comments: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Print the schema as SDL and exit
+    PrintSchema,
+    /// Print the changes between two schemas, exits with 1 when any of them is breaking
+    CompareSchemas {
+        /// SDL file / folder or introspection JSON of the old schema
+        old: String,
+        /// SDL file / folder or introspection JSON of the new schema, the configured schema by default
+        new: Option<String>,
+    },
 }
 
 impl CommandLineParams {
@@ -27,6 +50,16 @@ impl CommandLineParams {
         serde_json::from_reader(file).unwrap()
     }
 
+    pub fn schema_source(&self, config: &Config) -> SchemaSource {
+        match self.schema_file.as_ref().or(config.schema_file.as_ref()) {
+            Some(schema_file) => SchemaSource::Sdl(PathBuf::from(schema_file)),
+            None => SchemaSource::Introspection {
+                cache_file_path: PathBuf::from(&config.schema_cache),
+                reload: self.reload_schema,
+            },
+        }
+    }
+
     pub fn source_folder(&self) -> PathBuf {
         if let Some(ref source_folder) = self.source_folder {
             fs::canonicalize(PathBuf::from(source_folder)).expect("Failed obtaining absolute path")
@@ -0,0 +1,276 @@
+use std::fmt::Display;
+
+use crate::{
+    ast::{self, Field, ParamValue},
+    config::ComplexityConfig,
+    schema::{self, Schema, Type},
+};
+
+/**
+ * Estimated cost of an operation. Every selected field costs the product of the list sizes above it, the
+ * list sizes come from `first` / `last` / `limit` style arguments or the configured default.
+ * Fragments are expanded, all branches of inline fragments are counted.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Complexity {
+    pub depth: usize,
+    pub field_count: usize,
+    pub cost: u64,
+}
+
+impl Complexity {
+    pub fn estimate(
+        schema: &Schema,
+        document: &ast::Document,
+        root: &ast::Root,
+        config: &ComplexityConfig,
+    ) -> Complexity {
+        let root_name = match root {
+            ast::Root::Query(_) => Some(&schema.query_root_name),
+            ast::Root::Mutation(_) => schema.mutation_root_name.as_ref(),
+            ast::Root::Subscription(_) => schema.subscription_root_name.as_ref(),
+        };
+        let Some(scope) = root_name.and_then(|root_name| schema.type_definition(root_name)) else {
+            return Complexity::default();
+        };
+
+        let mut estimator = ComplexityEstimator {
+            schema,
+            document,
+            config,
+            fragment_path: vec![],
+        };
+        estimator.estimate_field_list(root.field_list(), scope, 1, 1)
+    }
+
+    // Why the operation needs a confirmation before executing it, if it does.
+    pub fn budget_violation(&self, config: &ComplexityConfig) -> Option<String> {
+        if let Some(max_cost) = config.max_cost {
+            if self.cost > max_cost {
+                return Some(format!(
+                    "Estimated cost {} is over the budget of {}",
+                    self.cost, max_cost
+                ));
+            }
+        }
+        if let Some(max_depth) = config.max_depth {
+            if self.depth > max_depth {
+                return Some(format!(
+                    "Depth {} is over the limit of {}",
+                    self.depth, max_depth
+                ));
+            }
+        }
+
+        None
+    }
+
+    fn merge(&mut self, other: Complexity) {
+        self.depth = self.depth.max(other.depth);
+        self.field_count += other.field_count;
+        self.cost = self.cost.saturating_add(other.cost);
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "depth {} | {} fields | cost ~{}",
+            self.depth, self.field_count, self.cost
+        )
+    }
+}
+
+struct ComplexityEstimator<'a> {
+    schema: &'a Schema,
+    document: &'a ast::Document,
+    config: &'a ComplexityConfig,
+    // Fragments being expanded, to stop on (invalid) cyclic spreads.
+    fragment_path: Vec<&'a str>,
+}
+
+impl<'a> ComplexityEstimator<'a> {
+    fn estimate_field_list(
+        &mut self,
+        field_list: &'a ast::FieldList,
+        scope: &'a Type,
+        depth: usize,
+        multiplier: u64,
+    ) -> Complexity {
+        let mut complexity = Complexity::default();
+
+        for field in &field_list.fields {
+            match field {
+                Field::Concrete(field) => {
+                    complexity.merge(self.estimate_concrete_field(field, scope, depth, multiplier))
+                }
+                Field::Union(field) => {
+                    let inner_scope = self
+                        .schema
+                        .type_definition(&field.type_name.original)
+                        .unwrap_or(scope);
+                    complexity.merge(self.estimate_field_list(
+                        &field.field_list,
+                        inner_scope,
+                        depth,
+                        multiplier,
+                    ));
+                }
+                Field::FragmentSpread(field) => {
+                    let name = field.name.original.as_str();
+                    let Some(fragment) = self.document.fragment(name) else {
+                        continue;
+                    };
+                    if self.fragment_path.contains(&name) {
+                        continue;
+                    }
+                    let inner_scope = self
+                        .schema
+                        .type_definition(&fragment.type_condition.original)
+                        .unwrap_or(scope);
+
+                    self.fragment_path.push(name);
+                    complexity.merge(self.estimate_field_list(
+                        &fragment.field_list,
+                        inner_scope,
+                        depth,
+                        multiplier,
+                    ));
+                    self.fragment_path.pop();
+                }
+            }
+        }
+
+        complexity
+    }
+
+    fn estimate_concrete_field(
+        &mut self,
+        field: &'a ast::ConcreteField,
+        scope: &'a Type,
+        depth: usize,
+        multiplier: u64,
+    ) -> Complexity {
+        let mut complexity = Complexity {
+            depth,
+            field_count: 1,
+            cost: multiplier,
+        };
+
+        let Some(field_definition) = self.schema.field_definition(scope, &field.name.original)
+        else {
+            return complexity;
+        };
+        let (Some(field_list), Some(field_type)) = (
+            &field.field_list,
+            field_definition
+                .field_type
+                .underlying_type_name()
+                .and_then(|type_name| self.schema.type_definition(type_name)),
+        ) else {
+            return complexity;
+        };
+
+        complexity.merge(self.estimate_field_list(
+            field_list,
+            field_type,
+            depth + 1,
+            multiplier.saturating_mul(self.list_size(field, field_definition)),
+        ));
+        complexity
+    }
+
+    // How many times the selection of the field is resolved.
+    fn list_size(&self, field: &ast::ConcreteField, field_definition: &schema::Field) -> u64 {
+        let size_arg = field.arglist.as_ref().and_then(|arglist| {
+            arglist
+                .params
+                .iter()
+                .find(|param| self.config.multiplier_args.contains(&param.key.original))
+        });
+
+        match size_arg.map(|param| &param.value) {
+            Some(ParamValue::Int(token)) => token
+                .original
+                .parse()
+                .unwrap_or(self.config.default_list_size),
+            // Variables and magic values are only known at execution.
+            Some(_) => self.config.default_list_size,
+            None if field_definition.field_type.is_list() => self.config.default_list_size,
+            None => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{config::ComplexityConfig, parser::Parser, schema::fixture, tokenizer::Tokenizer};
+
+    use super::Complexity;
+
+    fn estimate(raw: &str) -> Complexity {
+        let (document, _) = Parser::new(Tokenizer::tokenize(raw, false)).parse();
+
+        Complexity::estimate(
+            &fixture::schema(),
+            &document,
+            &document.operations[0],
+            &ComplexityConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_estimate() {
+        // 1 user + 2 fields + 1 posts + 5 * 2 post fields.
+        assert_eq!(
+            Complexity {
+                depth: 3,
+                field_count: 6,
+                cost: 14,
+            },
+            estimate("{ user(id: 1) { id name posts(first: 5) { id title } } }")
+        );
+        // Lists without a size argument are of the default size.
+        assert_eq!(
+            Complexity {
+                depth: 3,
+                field_count: 3,
+                cost: 111,
+            },
+            estimate("{ users { posts { id } } }")
+        );
+    }
+
+    #[test]
+    fn test_estimate_expands_fragments() {
+        assert_eq!(
+            Complexity {
+                depth: 3,
+                field_count: 5,
+                cost: 41,
+            },
+            estimate(
+                r#"{ search(term: "a") { ... on User { id } ...PostFields } }
+                fragment PostFields on Post { id author { name } }"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_budget_violation() {
+        let config = ComplexityConfig {
+            max_cost: Some(100),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some("Estimated cost 111 is over the budget of 100".to_string()),
+            estimate("{ users { posts { id } } }").budget_violation(&config)
+        );
+        assert_eq!(
+            None,
+            estimate("{ users(first: 2) { id } }").budget_violation(&config)
+        );
+    }
+}
@@ -4,7 +4,37 @@ use serde_json::Value;
 #[derive(Deserialize)]
 pub struct Config {
     pub url: String,
+    // Defaults to `url` with the `ws` / `wss` scheme.
+    pub subscription_url: Option<String>,
     pub headers: Vec<[String; 2]>,
     pub schema_cache: String,
+    // SDL file or folder, read instead of introspecting `url` when set.
+    pub schema_file: Option<String>,
     pub variables: Option<Value>,
+    #[serde(default)]
+    pub complexity: ComplexityConfig,
+}
+
+// Query cost estimation, see `complexity::Complexity`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ComplexityConfig {
+    // Arguments whose value is the size of the returned list (eg.: `first: 50`).
+    pub multiplier_args: Vec<String>,
+    // Assumed size of lists without such an argument.
+    pub default_list_size: u64,
+    // Executing queries over these asks for a confirmation.
+    pub max_depth: Option<usize>,
+    pub max_cost: Option<u64>,
+}
+
+impl Default for ComplexityConfig {
+    fn default() -> ComplexityConfig {
+        ComplexityConfig {
+            multiplier_args: vec!["first".to_string(), "last".to_string(), "limit".to_string()],
+            default_list_size: 10,
+            max_depth: None,
+            max_cost: None,
+        }
+    }
 }
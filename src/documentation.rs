@@ -0,0 +1,356 @@
+use crate::{
+    ast::{self, Field, ParamValue},
+    schema::{self, Schema, Type, TypeClass},
+    schema_explorer::Location,
+    tokenizer::Token,
+};
+
+/**
+ * Schema documentation of the field, argument, enum value, directive or type under the cursor.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub struct Documentation {
+    // Eg.: `posts(first: Int = 10): [Post]`.
+    pub signature: String,
+    // Where the item is defined: the type of a field or enum value, the field of an argument.
+    pub parent: Option<String>,
+    pub description: Option<String>,
+    pub deprecation_reason: Option<String>,
+    // Where the schema explorer opens for the item.
+    pub location: Option<Location>,
+}
+
+pub struct DocumentationFinder<'a> {
+    schema: &'a Schema,
+    document: &'a ast::Document,
+    pos: usize,
+}
+
+impl<'a> DocumentationFinder<'a> {
+    pub fn find(
+        schema: &'a Schema,
+        document: &'a ast::Document,
+        pos: usize,
+    ) -> Option<Documentation> {
+        let finder = DocumentationFinder {
+            schema,
+            document,
+            pos,
+        };
+
+        for fragment in &document.fragments {
+            if fragment.range_inclusive().contains(&pos) {
+                return finder.find_in_fragment_definition(fragment);
+            }
+        }
+
+        document
+            .operation_at(pos)
+            .and_then(|root| finder.find_in_root(root))
+    }
+
+    fn find_in_root(&self, root: &ast::Root) -> Option<Documentation> {
+        if let Some(variable_definitions) = root.variable_definitions() {
+            for definition in &variable_definitions.definitions {
+                let type_name = definition.var_type.named_type();
+                if self.is_on(type_name) {
+                    return self.type_documentation(&type_name.original);
+                }
+                if let Some(documentation) = self.find_in_directives(&definition.directives) {
+                    return Some(documentation);
+                }
+            }
+        }
+
+        if let Some(documentation) = self.find_in_directives(root.directives()) {
+            return Some(documentation);
+        }
+
+        let root_name = match root {
+            ast::Root::Query(_) => Some(&self.schema.query_root_name),
+            ast::Root::Mutation(_) => self.schema.mutation_root_name.as_ref(),
+            ast::Root::Subscription(_) => self.schema.subscription_root_name.as_ref(),
+        }?;
+        let scope = self.schema.type_definition(root_name)?;
+
+        self.find_in_field_list(root.field_list(), scope)
+    }
+
+    fn find_in_fragment_definition(
+        &self,
+        fragment: &ast::FragmentDefinition,
+    ) -> Option<Documentation> {
+        if self.is_on(&fragment.type_condition) {
+            return self.type_documentation(&fragment.type_condition.original);
+        }
+        if let Some(documentation) = self.find_in_directives(&fragment.directives) {
+            return Some(documentation);
+        }
+
+        let scope = self
+            .schema
+            .type_definition(&fragment.type_condition.original)?;
+        self.find_in_field_list(&fragment.field_list, scope)
+    }
+
+    fn find_in_field_list(
+        &self,
+        field_list: &ast::FieldList,
+        scope: &Type,
+    ) -> Option<Documentation> {
+        let field = field_list
+            .fields
+            .iter()
+            .find(|field| field.range_inclusive().contains(&self.pos))?;
+
+        match field {
+            Field::Concrete(field) => self.find_in_concrete_field(field, scope),
+            Field::Union(field) => {
+                if self.is_on(&field.type_name) {
+                    return self.type_documentation(&field.type_name.original);
+                }
+                if let Some(documentation) = self.find_in_directives(&field.directives) {
+                    return Some(documentation);
+                }
+
+                let inner_scope = self.schema.type_definition(&field.type_name.original)?;
+                self.find_in_field_list(&field.field_list, inner_scope)
+            }
+            Field::FragmentSpread(field) => {
+                if self.is_on(&field.name) {
+                    let fragment = self.document.fragment(&field.name.original)?;
+                    return self.type_documentation(&fragment.type_condition.original);
+                }
+
+                self.find_in_directives(&field.directives)
+            }
+        }
+    }
+
+    fn find_in_concrete_field(
+        &self,
+        field: &ast::ConcreteField,
+        scope: &Type,
+    ) -> Option<Documentation> {
+        if let Some(documentation) = self.find_in_directives(&field.directives) {
+            return Some(documentation);
+        }
+
+        let field_definition = self.schema.field_definition(scope, &field.name.original)?;
+
+        if self.is_on(&field.name) {
+            return Some(Documentation {
+                signature: field_definition.signature(),
+                parent: Some(scope.name().to_string()),
+                description: field_definition.description.clone(),
+                deprecation_reason: field_definition.deprecation_reason.clone(),
+                location: Some(field_location(scope, field_definition)),
+            });
+        }
+
+        if let Some(arglist) = &field.arglist {
+            if arglist.range_exclusive().contains(&self.pos) {
+                return self.find_in_arglist(
+                    arglist,
+                    &field_definition.args,
+                    format!("{}.{}", scope.name(), field_definition.name),
+                    Some(field_location(scope, field_definition)),
+                );
+            }
+        }
+
+        let field_list = field.field_list.as_ref()?;
+        let field_type = self
+            .schema
+            .type_definition(field_definition.field_type.underlying_type_name()?)?;
+        self.find_in_field_list(field_list, field_type)
+    }
+
+    fn find_in_directives(&self, directives: &[ast::Directive]) -> Option<Documentation> {
+        let directive = directives
+            .iter()
+            .find(|directive| directive.range_inclusive().contains(&self.pos))?;
+        let directive_definition = self.schema.directive(&directive.name.original)?;
+
+        if self.is_on(&directive.name) {
+            return Some(Documentation {
+                signature: directive_definition.signature(),
+                parent: None,
+                description: directive_definition.description.clone(),
+                deprecation_reason: None,
+                location: None,
+            });
+        }
+
+        let arglist = directive.arglist.as_ref()?;
+        self.find_in_arglist(
+            arglist,
+            &directive_definition.args,
+            format!("@{}", directive_definition.name),
+            None,
+        )
+    }
+
+    // Arguments, and input object fields when `parent` is the input type.
+    fn find_in_arglist(
+        &self,
+        arglist: &ast::ArgList,
+        definitions: &schema::ArgList,
+        parent: String,
+        location: Option<Location>,
+    ) -> Option<Documentation> {
+        let param = arglist
+            .params
+            .iter()
+            .find(|param| (param.span.offset..=param.end_span.offset).contains(&self.pos))?;
+        let arg = definitions.arg(&param.key.original)?;
+
+        if self.is_on(&param.key) {
+            return Some(Documentation {
+                signature: arg.signature(),
+                parent: Some(parent),
+                description: arg.description.clone(),
+                deprecation_reason: arg.deprecation_reason.clone(),
+                location,
+            });
+        }
+
+        self.find_in_value(&param.value, &arg.arg_type)
+    }
+
+    fn find_in_value(&self, value: &ParamValue, type_class: &TypeClass) -> Option<Documentation> {
+        if !value.range_inclusive().contains(&self.pos) {
+            return None;
+        }
+
+        match (type_class, value) {
+            (TypeClass::NonNull(inner), _) => self.find_in_value(value, inner),
+            (TypeClass::List(inner), ParamValue::List(list)) => list
+                .elems
+                .iter()
+                .find_map(|elem| self.find_in_value(elem, inner)),
+            (TypeClass::List(inner), _) => self.find_in_value(value, inner),
+            (TypeClass::Enum(enum_name), ParamValue::Enum(token)) => {
+                let Some(Type::Enum(enum_type)) = self.schema.type_definition(enum_name) else {
+                    return None;
+                };
+                let enum_value = enum_type.value(&token.original)?;
+
+                Some(Documentation {
+                    signature: enum_value.name.to_string(),
+                    parent: Some(enum_name.to_string()),
+                    description: enum_value.description.clone(),
+                    deprecation_reason: enum_value.deprecation_reason.clone(),
+                    location: Some(Location::Type(enum_name.to_string())),
+                })
+            }
+            (TypeClass::Input(input_name), ParamValue::Object(object)) => {
+                let Some(Type::InputObject(input_type)) = self.schema.type_definition(input_name)
+                else {
+                    return None;
+                };
+
+                self.find_in_arglist(
+                    object,
+                    &input_type.args,
+                    input_name.to_string(),
+                    Some(Location::Type(input_name.to_string())),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn type_documentation(&self, type_name: &str) -> Option<Documentation> {
+        let ty = self.schema.type_definition(type_name)?;
+
+        Some(Documentation {
+            signature: format!("{} {}", ty.keyword(), ty.name()),
+            parent: None,
+            description: ty.description().cloned(),
+            deprecation_reason: None,
+            location: Some(Location::Type(ty.name().to_string())),
+        })
+    }
+
+    fn is_on(&self, token: &Token) -> bool {
+        token.range_inclusive().contains(&self.pos)
+    }
+}
+
+fn field_location(scope: &Type, field_definition: &schema::Field) -> Location {
+    Location::Field {
+        type_name: scope.name().to_string(),
+        field_name: field_definition.name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parser::Parser, schema::fixture, schema_explorer::Location, tokenizer::Tokenizer};
+
+    use super::{Documentation, DocumentationFinder};
+
+    // Documentation at the `|` marker.
+    fn documentation_at(raw_with_cursor: &str) -> Option<Documentation> {
+        let pos = raw_with_cursor.find('|').unwrap();
+        let raw = raw_with_cursor.replacen('|', "", 1);
+        let (document, _) = Parser::new(Tokenizer::tokenize(&raw, false)).parse();
+
+        DocumentationFinder::find(&fixture::schema(), &document, pos)
+    }
+
+    #[test]
+    fn test_field_documentation() {
+        assert_eq!(
+            Some(Documentation {
+                signature: "posts(first: Int = 10): [Post]".to_string(),
+                parent: Some("User".to_string()),
+                description: Some("Most recent first.".to_string()),
+                deprecation_reason: None,
+                location: Some(Location::Field {
+                    type_name: "User".to_string(),
+                    field_name: "posts".to_string(),
+                }),
+            }),
+            documentation_at("{ user(id: 1) { po|sts { id } } }")
+        );
+    }
+
+    #[test]
+    fn test_argument_documentation() {
+        let documentation =
+            documentation_at("{ user(id: 1) { posts(fi|rst: 2) { id } } }").unwrap();
+
+        assert_eq!("first: Int = 10", documentation.signature);
+        assert_eq!(Some("User.posts".to_string()), documentation.parent);
+        assert_eq!(
+            Some(Location::Field {
+                type_name: "User".to_string(),
+                field_name: "posts".to_string(),
+            }),
+            documentation.location
+        );
+    }
+
+    #[test]
+    fn test_enum_value_and_type_documentation() {
+        let documentation = documentation_at("{ users(filter: { role: ADM|IN }) { id } }").unwrap();
+        assert_eq!("ADMIN", documentation.signature);
+        assert_eq!(Some("Role".to_string()), documentation.parent);
+
+        let documentation =
+            documentation_at("{ search(term: \"a\") { ... on Us|er { id } } }").unwrap();
+        assert_eq!("type User", documentation.signature);
+        assert_eq!(
+            Some("A registered user.".to_string()),
+            documentation.description
+        );
+    }
+
+    #[test]
+    fn test_no_documentation_outside_of_schema_items() {
+        assert_eq!(None, documentation_at("{ user(id: 1) { unknown| } }"));
+        assert_eq!(None, documentation_at("{ user(id: |1) { id } }"));
+    }
+}
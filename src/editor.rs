@@ -1,11 +1,15 @@
-use std::path::PathBuf;
 use std::{cell::RefCell, rc::Rc};
 
-use crate::analyzer::{Analyzer, Suggestion};
-use crate::editor_printer::EditorPrinter;
-use crate::net_ops::NetOps;
-use crate::parser;
+use crate::analyzer::{Analyzer, RecentSuggestions, Suggestion};
+use crate::ast;
+use crate::complexity::Complexity;
+use crate::config::ComplexityConfig;
+use crate::editor_printer::{EditorPrinter, Frame, Status};
+use crate::parser::{self, ParseError};
+use crate::schema::Schema;
+use crate::schema_explorer::Location;
 use crate::tokenizer::{Token, TokenKind, Tokenizer};
+use crate::util::Error;
 use crate::{stdin_reader::KeyboardInput, text::Text};
 
 #[derive(Debug, PartialEq)]
@@ -14,26 +18,36 @@ enum State {
     SuggestionSelect,
 }
 
+pub struct ExecutableOperation {
+    pub name: Option<String>,
+    pub is_subscription: bool,
+    // Why executing needs a confirmation, see `ComplexityConfig`.
+    pub budget_violation: Option<String>,
+}
+
 pub struct Editor {
     content: Rc<RefCell<Text>>,
     analyzer: Analyzer,
     state: State,
     previous_suggestion: Option<Suggestion>,
+    recent_suggestions: RecentSuggestions,
+    complexity_config: ComplexityConfig,
     printer: EditorPrinter,
 }
 
 impl Editor {
     pub fn new(
         content: Rc<RefCell<Text>>,
-        net_ops: &NetOps,
-        schema_cache_file_path: &PathBuf,
-        reload_schema: bool,
+        schema: Schema,
+        complexity_config: ComplexityConfig,
     ) -> Editor {
         Editor {
             content,
-            analyzer: Analyzer::new(&net_ops, schema_cache_file_path, reload_schema),
+            analyzer: Analyzer::new(schema),
             state: State::Edit,
             previous_suggestion: None,
+            recent_suggestions: RecentSuggestions::new(),
+            complexity_config,
             printer: EditorPrinter::new(),
         }
     }
@@ -51,10 +65,7 @@ impl Editor {
             KeyboardInput::VisibleChar(code) => {
                 if self.state == State::SuggestionSelect {
                     if code >= b'0' && code <= b'9' {
-                        self.content.borrow_mut().apply_suggestion(
-                            self.previous_suggestion.as_ref().unwrap(),
-                            (code - b'0') as usize,
-                        );
+                        self.apply_suggestion((code - b'0') as usize);
                     }
                     self.state = State::Edit;
                 } else {
@@ -73,11 +84,7 @@ impl Editor {
             KeyboardInput::Backspace => self.content.borrow_mut().backspace(),
             KeyboardInput::Tab => self.content.borrow_mut().insert_tab(),
             KeyboardInput::CtrlSlash => self.content.borrow_mut().toggle_comment(),
-            KeyboardInput::AltDigit(digit) => {
-                self.content
-                    .borrow_mut()
-                    .apply_suggestion(self.previous_suggestion.as_ref().unwrap(), digit as usize);
-            }
+            KeyboardInput::AltDigit(digit) => self.apply_suggestion(digit as usize),
             KeyboardInput::CtrlW => self.content.borrow_mut().delete_word(),
             _ => {
                 warn!("Unrecognized editor input: {:?}", input);
@@ -87,10 +94,159 @@ impl Editor {
         self.refresh_screen();
     }
 
+    fn apply_suggestion(&mut self, idx: usize) {
+        let Some(suggestion) = self.previous_suggestion.as_ref() else {
+            return;
+        };
+        if let Some(elem) = suggestion.elems.get(idx) {
+            self.recent_suggestions.accept(&elem.name);
+        }
+
+        self.content.borrow_mut().apply_suggestion(suggestion, idx);
+    }
+
     pub fn refresh_screen(&mut self) {
         let tokens = self.build_tokens();
+
+        let mut suggestions = None;
+        let mut definition_error = None;
+
+        // The analyzer works on the partial document even when there are syntax errors.
+        let (document, parse_errors) = Editor::parse_tokens(tokens.clone());
+        let alias_tokens = document
+            .alias_tokens()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        match self.analyzer.analyze(
+            &document,
+            self.content.borrow().new_line_adjusted_cursor_position(),
+        ) {
+            Ok(ok) => {
+                let ok = ok.map(|suggestion| self.recent_suggestions.boost(suggestion));
+                self.previous_suggestion = ok.clone();
+                suggestions = ok;
+            }
+            Err(err) => definition_error = Some(err.to_string()),
+        };
+
+        let documentation = self.analyzer.documentation(
+            &document,
+            self.content.borrow().new_line_adjusted_cursor_position(),
+        );
+
+        // Validating the partial document would mostly repeat the syntax errors.
+        let diagnostics = if parse_errors.is_empty() {
+            self.analyzer.validate(&document)
+        } else {
+            vec![]
+        };
+
+        let complexity = if parse_errors.is_empty() {
+            Editor::executable_root(
+                &document,
+                self.content.borrow().new_line_adjusted_cursor_position(),
+            )
+            .map(|root| {
+                Complexity::estimate(
+                    self.analyzer.schema(),
+                    &document,
+                    root,
+                    &self.complexity_config,
+                )
+            })
+        } else {
+            None
+        };
+
+        self.printer.print(&Frame {
+            tokens,
+            alias_tokens,
+            cursor: &self.content.borrow().cursor,
+            suggestions,
+            parse_errors,
+            definition_error,
+            diagnostics,
+            documentation,
+            suggestion_selection_mode: self.state == State::SuggestionSelect,
+            status: Status {
+                file_name: &self.content.borrow().file_path,
+                is_file_saved: self.content.borrow().is_file_saved,
+                complexity,
+                is_over_budget: complexity.is_some_and(|complexity| {
+                    complexity
+                        .budget_violation(&self.complexity_config)
+                        .is_some()
+                }),
+            },
+        });
+    }
+
+    /**
+     * The operation to execute: the one under the cursor, or the only one in the document.
+     */
+    pub fn operation_at_cursor(&self) -> Result<ExecutableOperation, Error> {
+        let (document, parse_errors) = Editor::parse_tokens(self.build_tokens());
+        if let Some(parse_error) = parse_errors.first() {
+            return Err(parse_error.to_string().into());
+        }
+        let pos = self.content.borrow().new_line_adjusted_cursor_position();
+
+        let operation = Editor::executable_root(&document, pos)
+            .ok_or("Move the cursor into the operation to execute")?;
+
+        let name = match operation.name() {
+            Some(name) => Some(name.original.clone()),
+            None if document.operations.len() == 1 => None,
+            None => {
+                return Err(
+                    "Operations must be named when the document contains more than one".into(),
+                )
+            }
+        };
+
+        Ok(ExecutableOperation {
+            name,
+            is_subscription: operation.is_subscription(),
+            budget_violation: Complexity::estimate(
+                self.analyzer.schema(),
+                &document,
+                operation,
+                &self.complexity_config,
+            )
+            .budget_violation(&self.complexity_config),
+        })
+    }
+
+    // The operation under the cursor, or the only one in the document.
+    fn executable_root(document: &ast::Document, pos: usize) -> Option<&ast::Root> {
+        document
+            .operation_at(pos)
+            .or(match document.operations.as_slice() {
+                [operation] => Some(operation),
+                _ => None,
+            })
+    }
+
+    pub fn schema(&self) -> &Schema {
+        self.analyzer.schema()
+    }
+
+    // Where the schema explorer opens for the item under the cursor.
+    pub fn schema_location_at_cursor(&self) -> Option<Location> {
+        let (document, _) = Editor::parse_tokens(self.build_tokens());
+
+        self.analyzer
+            .documentation(
+                &document,
+                self.content.borrow().new_line_adjusted_cursor_position(),
+            )
+            .and_then(|documentation| documentation.location)
+    }
+
+    fn parse_tokens(tokens: Vec<Token>) -> (ast::Document, Vec<ParseError>) {
         let tokens_without_whitespace = tokens
-            .clone()
             .into_iter()
             .filter(|token| match token.kind {
                 TokenKind::Whitespace(_) => false,
@@ -100,35 +256,7 @@ impl Editor {
             })
             .collect::<Vec<_>>();
 
-        let mut parse_error = None;
-        let mut suggestions = None;
-        let mut definition_error = None;
-        match parser::Parser::new(tokens_without_whitespace).parse() {
-            Ok(root) => {
-                match self.analyzer.analyze(
-                    root,
-                    self.content.borrow().new_line_adjusted_cursor_position(),
-                ) {
-                    Ok(ok) => {
-                        self.previous_suggestion = ok.clone();
-                        suggestions = ok;
-                    }
-                    Err(err) => definition_error = Some(err.to_string()),
-                };
-            }
-            Err(err) => parse_error = Some(err),
-        }
-
-        self.printer.print(
-            tokens,
-            &self.content.borrow().cursor,
-            suggestions,
-            parse_error,
-            definition_error,
-            self.state == State::SuggestionSelect,
-            &self.content.borrow().file_path,
-            self.content.borrow().is_file_saved,
-        );
+        parser::Parser::new(tokens_without_whitespace).parse()
     }
 
     fn build_tokens(&self) -> Vec<Token> {
@@ -5,13 +5,51 @@ use std::{
 
 use crate::{
     analyzer::{Suggestion, SuggestionElem},
+    complexity::Complexity,
+    documentation::Documentation,
     parser::ParseError,
     terminal_handler::TerminalHandler,
     tokenizer::{self, Token, TokenKind},
     util::{trim_coloured_string_list, CoordUsize},
+    validator::{Diagnostic, Severity},
 };
 
 const POPUP_BAR_WIDTH_DIVIDER: usize = 2;
+const COLOR_ALIAS: u8 = 36;
+// Strike-through.
+const COLOR_DEPRECATED_SUGGESTION: usize = 9;
+const COLOR_DEPRECATED_SUGGESTION_MATCH: usize = 90;
+const COLOR_WARNING: u8 = 33;
+const BACKGROUND_ERROR: u8 = 52;
+const BACKGROUND_WARNING: u8 = 58;
+const COLOR_DOCUMENTATION_SIGNATURE: usize = 97;
+const COLOR_DOCUMENTATION_PARENT: usize = 90;
+const COLOR_DOCUMENTATION_DEPRECATION: usize = 93;
+const COLOR_DOCUMENTATION_TEXT: usize = 39;
+
+// What the title bar shows about the file and the operation to execute.
+pub struct Status<'a> {
+    pub file_name: &'a Option<PathBuf>,
+    pub is_file_saved: bool,
+    pub complexity: Option<Complexity>,
+    pub is_over_budget: bool,
+}
+
+/**
+ * Everything one redraw of the editor shows: the text, the analysis results around it and the title bar.
+ */
+pub struct Frame<'a> {
+    pub tokens: Vec<Token>,
+    pub alias_tokens: Vec<Token>,
+    pub cursor: &'a CoordUsize,
+    pub suggestions: Option<Suggestion>,
+    pub parse_errors: Vec<ParseError>,
+    pub definition_error: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub documentation: Option<Documentation>,
+    pub suggestion_selection_mode: bool,
+    pub status: Status<'a>,
+}
 
 pub struct EditorPrinter {
     terminal_dimension: (usize, usize),
@@ -28,45 +66,62 @@ impl EditorPrinter {
         }
     }
 
-    pub fn print(
-        &mut self,
-        tokens: Vec<Token>,
-        cursor: &CoordUsize,
-        suggestions: Option<Suggestion>,
-        parse_error: Option<ParseError>,
-        definition_error: Option<String>,
-        suggestion_selection_mode: bool,
-        file_name: &Option<PathBuf>,
-        is_file_saved: bool,
-    ) {
+    pub fn print(&mut self, frame: &Frame) {
         let mut buf: String = String::new();
         TerminalHandler::append_hide_cursor(&mut buf);
         TerminalHandler::append_clear_screen(&mut buf);
 
         self.print_tokens(
             &mut buf,
-            tokens,
-            cursor.y,
-            &parse_error.as_ref().and_then(|pe| pe.token.as_ref()),
+            &frame.tokens,
+            &frame.alias_tokens,
+            frame.cursor.y,
+            &frame
+                .parse_errors
+                .iter()
+                .filter_map(|pe| pe.token.as_ref().map(|token| token.pos()))
+                .chain(frame.diagnostics.iter().filter_map(|diagnostic| {
+                    (diagnostic.severity == Severity::Error).then_some(diagnostic.span.offset)
+                }))
+                .collect::<Vec<_>>(),
+            &frame
+                .diagnostics
+                .iter()
+                .filter_map(|diagnostic| {
+                    (diagnostic.severity == Severity::Warning).then_some(diagnostic.span.offset)
+                })
+                .collect::<Vec<_>>(),
         );
 
-        if let Some(suggestions) = suggestions {
+        if let Some(suggestions) = &frame.suggestions {
             self.print_analyzer_result_suggestions(
                 &mut buf,
                 suggestions,
-                suggestion_selection_mode,
+                frame.suggestion_selection_mode,
             );
         }
 
-        if let Some(parse_error) = parse_error {
-            self.print_analyzer_result_parse_error(&mut buf, parse_error);
-        } else if let Some(definition_error) = definition_error {
-            self.print_analyzer_result_definition_error(&mut buf, definition_error);
+        let error_message_height = if !frame.parse_errors.is_empty() {
+            self.print_analyzer_result_parse_error(&mut buf, &frame.parse_errors)
+        } else if let Some(definition_error) = &frame.definition_error {
+            self.print_analyzer_result_definition_error(&mut buf, definition_error)
+        } else if !frame.diagnostics.is_empty() {
+            self.print_validation_errors(&mut buf, &frame.diagnostics)
+        } else {
+            0
+        };
+
+        if let Some(documentation) = &frame.documentation {
+            self.print_documentation(&mut buf, documentation, error_message_height);
         }
 
-        self.print_title_bar(&mut buf, file_name, is_file_saved);
+        self.print_title_bar(&mut buf, &frame.status);
 
-        TerminalHandler::append_cursor_location(&mut buf, cursor.x, cursor.y - self.vscroll);
+        TerminalHandler::append_cursor_location(
+            &mut buf,
+            frame.cursor.x,
+            frame.cursor.y - self.vscroll,
+        );
         TerminalHandler::append_show_cursor(&mut buf);
 
         io::stdout()
@@ -83,14 +138,16 @@ impl EditorPrinter {
     fn print_tokens(
         &mut self,
         buf: &mut String,
-        tokens: Vec<Token>,
+        tokens: &[Token],
+        alias_tokens: &[Token],
         cursor_y: usize,
-        parse_error_token: &Option<&Token>,
+        error_positions: &[usize],
+        warning_positions: &[usize],
     ) {
         TerminalHandler::append_cursor_location(buf, 0, 0);
 
         self.resolve_vscroll(cursor_y);
-        let output = self.colorize(tokens, parse_error_token);
+        let output = self.colorize(tokens, alias_tokens, error_positions, warning_positions);
 
         let lines = output.lines().collect::<Vec<_>>();
 
@@ -117,7 +174,7 @@ impl EditorPrinter {
     fn print_analyzer_result_suggestions(
         &self,
         buf: &mut String,
-        suggestions: Suggestion,
+        suggestions: &Suggestion,
         suggestion_selection_mode: bool,
     ) {
         let popup_width = self.terminal_dimension.0 / POPUP_BAR_WIDTH_DIVIDER;
@@ -161,45 +218,63 @@ impl EditorPrinter {
         line_elems: &mut Vec<(String, Option<usize>)>,
         suggestion: &SuggestionElem,
     ) {
+        // Deprecated ones are struck-through with dimmed matches.
+        let (color, match_color) = if suggestion.is_deprecated {
+            (
+                COLOR_DEPRECATED_SUGGESTION,
+                COLOR_DEPRECATED_SUGGESTION_MATCH,
+            )
+        } else {
+            (32, 97)
+        };
+
         let mut printed_pos = 0usize;
         for fuzzy_pos in &suggestion.fuzzy_match_positions {
             if *fuzzy_pos > 0 && *fuzzy_pos > printed_pos {
-                line_elems.push((suggestion.name[printed_pos..*fuzzy_pos].into(), Some(32)));
+                line_elems.push((suggestion.name[printed_pos..*fuzzy_pos].into(), Some(color)));
             }
-            line_elems.push((suggestion.name[*fuzzy_pos..=*fuzzy_pos].into(), Some(97)));
+            line_elems.push((
+                suggestion.name[*fuzzy_pos..=*fuzzy_pos].into(),
+                Some(match_color),
+            ));
             printed_pos = *fuzzy_pos + 1;
         }
 
         if printed_pos < suggestion.name.len() {
-            line_elems.push((suggestion.name[printed_pos..].into(), Some(32)));
+            line_elems.push((suggestion.name[printed_pos..].into(), Some(color)));
         }
     }
 
     fn chop_string(s: String, width: usize) -> Vec<String> {
-        let mut i = 0usize;
-        let mut lines = vec![];
-
-        while i < s.len() {
-            let line_width = (s.len() - i).min(width);
-
-            lines.push(format!("{: <width$}", &s[0..line_width], width = width));
-
-            i += width;
-        }
-
-        lines
+        s.chars()
+            .collect::<Vec<_>>()
+            .chunks(width)
+            .map(|chunk| {
+                format!(
+                    "{: <width$}",
+                    chunk.iter().collect::<String>(),
+                    width = width
+                )
+            })
+            .collect()
     }
 
-    fn print_analyzer_result_parse_error(&self, buf: &mut String, error: ParseError) {
-        let err_lines = EditorPrinter::chop_string(error.message, self.terminal_width());
-        let token_lines = EditorPrinter::chop_string(
-            format!("At token: {:?}", error.token),
-            self.terminal_width(),
-        );
+    // Shows the first error, the rest are only highlighted in the editor.
+    fn print_analyzer_result_parse_error(&self, buf: &mut String, errors: &[ParseError]) -> usize {
+        let other_errors_len = errors.len() - 1;
+        let error = &errors[0];
+
+        let err_lines = EditorPrinter::chop_string(error.message.clone(), self.terminal_width());
+        let token_lines =
+            EditorPrinter::chop_string(format!("At {}", error.span), self.terminal_width());
         let scope_lines =
             EditorPrinter::chop_string(format!("Scope: {:?}", error.scope), self.terminal_width());
-        let title_lines =
-            EditorPrinter::chop_string("PARSE ERROR".to_string(), self.terminal_width());
+        let title = if other_errors_len > 0 {
+            format!("PARSE ERROR (and {} more)", other_errors_len)
+        } else {
+            "PARSE ERROR".to_string()
+        };
+        let title_lines = EditorPrinter::chop_string(title, self.terminal_width());
 
         let lines = title_lines
             .into_iter()
@@ -208,11 +283,41 @@ impl EditorPrinter {
             .chain(err_lines.into_iter())
             .collect();
 
-        self.print_error_message(buf, lines);
+        self.print_error_message(buf, lines, BACKGROUND_ERROR)
     }
 
-    fn print_analyzer_result_definition_error(&self, buf: &mut String, error: String) {
-        let mut lines = EditorPrinter::chop_string(error, self.terminal_width());
+    // Shows the first error (or warning when there are no errors), the rest are only highlighted.
+    fn print_validation_errors(&self, buf: &mut String, diagnostics: &[Diagnostic]) -> usize {
+        let other_diagnostics_len = diagnostics.len() - 1;
+        let first_index = diagnostics
+            .iter()
+            .position(|diagnostic| diagnostic.severity == Severity::Error)
+            .unwrap_or(0);
+        let diagnostic = &diagnostics[first_index];
+
+        let (title, background) = match diagnostic.severity {
+            Severity::Error => ("VALIDATION ERROR", BACKGROUND_ERROR),
+            Severity::Warning => ("VALIDATION WARNING", BACKGROUND_WARNING),
+        };
+        let title = if other_diagnostics_len > 0 {
+            format!("{} (and {} more)", title, other_diagnostics_len)
+        } else {
+            title.to_string()
+        };
+
+        let lines = EditorPrinter::chop_string(title, self.terminal_width())
+            .into_iter()
+            .chain(EditorPrinter::chop_string(
+                diagnostic.to_string(),
+                self.terminal_width(),
+            ))
+            .collect();
+
+        self.print_error_message(buf, lines, background)
+    }
+
+    fn print_analyzer_result_definition_error(&self, buf: &mut String, error: &str) -> usize {
+        let mut lines = EditorPrinter::chop_string(error.to_string(), self.terminal_width());
 
         lines.insert(
             0,
@@ -223,10 +328,11 @@ impl EditorPrinter {
             ),
         );
 
-        self.print_error_message(buf, lines);
+        self.print_error_message(buf, lines, BACKGROUND_ERROR)
     }
 
-    fn print_error_message(&self, buf: &mut String, lines: Vec<String>) {
+    // Returns the height of the message.
+    fn print_error_message(&self, buf: &mut String, lines: Vec<String>, background: u8) -> usize {
         for i in 0..lines.len() {
             TerminalHandler::append_cursor_location(
                 buf,
@@ -238,23 +344,94 @@ impl EditorPrinter {
                 buf.push_str("\x1B[1m");
             }
 
-            buf.push_str("\x1B[48;5;52m");
+            buf.push_str(&format!("\x1B[48;5;{}m", background));
             buf.push_str(&lines[i]);
             buf.push_str("\x1B[0m");
         }
+
+        lines.len()
     }
 
-    fn print_title_bar(&self, buf: &mut String, file_name: &Option<PathBuf>, is_file_save: bool) {
+    // Docs panel on the right, above the error message.
+    fn print_documentation(
+        &self,
+        buf: &mut String,
+        documentation: &Documentation,
+        error_message_height: usize,
+    ) {
+        let panel_width = self.terminal_dimension.0 / POPUP_BAR_WIDTH_DIVIDER;
+        let text_width = panel_width - 1;
+
+        let mut lines = vec![(
+            documentation.signature.clone(),
+            Some(COLOR_DOCUMENTATION_SIGNATURE),
+        )];
+        if let Some(parent) = &documentation.parent {
+            lines.push((format!("in {}", parent), Some(COLOR_DOCUMENTATION_PARENT)));
+        }
+        if let Some(deprecation_reason) = &documentation.deprecation_reason {
+            lines.push((
+                format!("Deprecated: {}", deprecation_reason),
+                Some(COLOR_DOCUMENTATION_DEPRECATION),
+            ));
+        }
+        if let Some(description) = &documentation.description {
+            for description_line in description.lines() {
+                lines.push((description_line.to_string(), None));
+            }
+        }
+
+        let lines = lines
+            .into_iter()
+            .flat_map(|(line, color)| {
+                EditorPrinter::chop_string(line, text_width)
+                    .into_iter()
+                    .map(move |chopped_line| (chopped_line, color))
+            })
+            .collect::<Vec<_>>();
+
+        let max_height = self.editor_area_height() / 2;
+        let bottom = self.editor_area_height() - error_message_height;
+        let height = lines.len().min(max_height).min(bottom);
+
+        for (i, (line, color)) in lines.into_iter().take(height).enumerate() {
+            TerminalHandler::append_cursor_location(
+                buf,
+                self.terminal_dimension.0 - panel_width,
+                bottom - height + i,
+            );
+
+            // Lines are chopped by chars already, descriptions can have multi-byte characters.
+            buf.push_str(&format!(
+                "\x1B[48;5;236m\x1B[92m|\x1B[{}m{}\x1B[0m",
+                color.unwrap_or(COLOR_DOCUMENTATION_TEXT),
+                line
+            ));
+        }
+    }
+
+    fn print_title_bar(&self, buf: &mut String, status: &Status) {
         TerminalHandler::append_cursor_location(buf, 0, self.editor_area_height() + 1);
 
-        let text = format!(
+        let mut text = format!(
             " GomQLet | {} {}",
-            file_name
+            status
+                .file_name
                 .as_ref()
                 .map(|path| path.to_str().unwrap())
                 .unwrap_or("unsaved file"),
-            if is_file_save { "" } else { "[not saved]" }
+            if status.is_file_saved {
+                ""
+            } else {
+                "[not saved]"
+            }
         );
+        if let Some(complexity) = &status.complexity {
+            text.push_str(&format!(" | {}", complexity));
+            if status.is_over_budget {
+                text.push_str(" [over budget]");
+            }
+        }
         let title_bar = format!(
             "\x1B[7m{: <width$}\x1B[0m",
             &text[0..self.terminal_width().min(text.len())],
@@ -264,13 +441,17 @@ impl EditorPrinter {
         buf.push_str(&title_bar);
     }
 
-    fn colorize(&self, tokens: Vec<Token>, parse_error_token: &Option<&Token>) -> String {
+    fn colorize(
+        &self,
+        tokens: &[Token],
+        alias_tokens: &[Token],
+        error_positions: &[usize],
+        warning_positions: &[usize],
+    ) -> String {
         tokens
-            .into_iter()
+            .iter()
             .map(|token| {
-                let is_error_token = parse_error_token
-                    .map(|error_token| error_token == &token)
-                    .unwrap_or(false);
+                let is_error_token = error_positions.contains(&token.pos());
 
                 if is_error_token {
                     format!(
@@ -278,8 +459,12 @@ impl EditorPrinter {
                         tokenizer::COLOR_INVALID,
                         token.original
                     )
+                } else if warning_positions.contains(&token.pos()) {
+                    format!("\x1B[{}m\x1B[4m{}\x1B[0m", COLOR_WARNING, token.original)
+                } else if alias_tokens.contains(token) {
+                    format!("\x1B[{}m{}\x1B[0m", COLOR_ALIAS, token.original)
                 } else {
-                    match token.kind {
+                    match &token.kind {
                         TokenKind::LineBreak => "\r\n".into(),
                         TokenKind::Invalid(_) => {
                             format!(
@@ -288,11 +473,16 @@ impl EditorPrinter {
                                 token.original
                             )
                         }
-                        _ => format!(
-                            "\x1B[{}m{}\x1B[0m",
-                            token.kind.vt100_color_code(),
-                            token.original
-                        ),
+                        // Block strings can span multiple lines, colorize line by line so scrolling
+                        // does not lose the color.
+                        _ => token
+                            .original
+                            .split('\n')
+                            .map(|line| {
+                                format!("\x1B[{}m{}\x1B[0m", token.kind.vt100_color_code(), line)
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
                     }
                 }
             })
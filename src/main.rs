@@ -1,10 +1,12 @@
-use std::{cell::RefCell, io, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, fs, io, mem, path::PathBuf, process, rc::Rc};
 
 use clap::Parser;
-use command_line_params::CommandLineParams;
-use editor::Editor;
+use command_line_params::{Command, CommandLineParams};
+use editor::{Editor, ExecutableOperation};
 use file_selector::FileSelector;
-use net_ops::NetOps;
+use net_ops::{NetOps, Subscription};
+use schema::{Schema, SchemaSource};
+use schema_explorer::SchemaExplorer;
 use stdin_reader::{KeyboardInput, StdinReader};
 use terminal_handler::TerminalHandler;
 use text::Text;
@@ -16,8 +18,12 @@ extern crate log;
 
 mod analyzer;
 mod ast;
+#[cfg(test)]
+mod benchmark;
 mod command_line_params;
+mod complexity;
 mod config;
+mod documentation;
 mod editor;
 mod editor_printer;
 mod file_selector;
@@ -27,25 +33,42 @@ mod magic_command;
 mod net_ops;
 mod parser;
 mod schema;
+mod schema_diff;
+mod schema_explorer;
+mod schema_explorer_printer;
+mod sdl;
+mod span;
 mod stdin_reader;
 mod terminal_handler;
 mod text;
 mod tokenizer;
 mod util;
+mod validator;
+
+// Written to the source folder by the print schema action.
+const SCHEMA_SDL_FILE_NAME: &str = "schema.graphqls";
 
 #[derive(PartialEq)]
 enum State {
     Editor,
     FileSelector,
+    SchemaExplorer,
 }
 
 struct Gomqlet {
     terminal_handler: TerminalHandler,
     editor: Editor,
     file_selector: FileSelector,
+    schema_explorer: SchemaExplorer,
     content: Rc<RefCell<Text>>,
     net_ops: NetOps,
+    subscription: Option<Subscription>,
     state: State,
+    // Where closing the schema explorer returns to.
+    schema_explorer_return_state: State,
+    // Over-budget operations execute on the second CTRL-G.
+    is_awaiting_budget_confirmation: bool,
+    schema_sdl_path: PathBuf,
 }
 
 impl Gomqlet {
@@ -55,30 +78,36 @@ impl Gomqlet {
         } else {
             State::FileSelector
         };
+        let source_folder = command_line_params.source_folder();
+        if command_line_params.migrate_comments {
+            match util::migrate_legacy_comment_files(&source_folder) {
+                Ok(changed_files) => info!("Migrated `//` comments in {} file(s)", changed_files),
+                Err(err) => error!("Failed migrating `//` comments: {}", err),
+            }
+        }
+
+        let config = command_line_params.config();
+        let net_ops = NetOps::new(&config);
+        let schema = load_schema(&net_ops, &command_line_params.schema_source(&config));
+
         let terminal_handler = TerminalHandler::new();
         let content = Rc::new(RefCell::new(Text::new(
-            command_line_params
-                .source_file
-                .clone()
-                .map(|file_path| PathBuf::from(file_path)),
+            command_line_params.source_file.clone().map(PathBuf::from),
         )));
-        let config = command_line_params.config();
-        let source_folder = command_line_params.source_folder();
-        let net_ops = NetOps::new(&config);
-        let editor = Editor::new(
-            content.clone(),
-            &net_ops,
-            &PathBuf::from(config.schema_cache),
-            command_line_params.reload_schema,
-        );
+        let editor = Editor::new(content.clone(), schema, config.complexity.clone());
 
         Ok(Gomqlet {
             terminal_handler,
             editor,
+            schema_sdl_path: source_folder.join(SCHEMA_SDL_FILE_NAME),
             file_selector: FileSelector::new(source_folder),
+            schema_explorer: SchemaExplorer::new(),
             content,
             net_ops,
+            subscription: None,
             state,
+            schema_explorer_return_state: State::Editor,
+            is_awaiting_budget_confirmation: false,
         })
     }
 
@@ -86,36 +115,115 @@ impl Gomqlet {
         match self.state {
             State::Editor => self.editor.refresh_screen(),
             State::FileSelector => self.file_selector.refresh_screen(),
+            State::SchemaExplorer => unreachable!("Starts in the editor or the file selector"),
         }
 
         loop {
             for cmd in StdinReader::read_commands()? {
+                let is_budget_confirmed = mem::take(&mut self.is_awaiting_budget_confirmation);
+
                 if cmd == KeyboardInput::CtrlC || cmd == KeyboardInput::CtrlD {
+                    self.cancel_subscription();
                     return Ok(());
                 } else if cmd == KeyboardInput::CtrlG {
                     // CTRL-G
-                    self.net_ops
-                        .execute_graphql_operation(&self.content.borrow().lines.join("\n"));
+                    match self.editor.operation_at_cursor() {
+                        Ok(ExecutableOperation {
+                            budget_violation: Some(budget_violation),
+                            ..
+                        }) if !is_budget_confirmed => {
+                            warn!(
+                                "{}, press CTRL-G again to execute it anyway",
+                                budget_violation
+                            );
+                            self.is_awaiting_budget_confirmation = true;
+                        }
+                        Ok(operation) if operation.is_subscription => {
+                            self.start_subscription(operation.name.as_deref())
+                        }
+                        Ok(operation) => self.net_ops.execute_graphql_operation(
+                            &self.content.borrow().lines.join("\n"),
+                            operation.name.as_deref(),
+                        ),
+                        Err(err) => error!("Cannot select operation to execute: {}", err),
+                    }
+                } else if cmd == KeyboardInput::Escape && self.subscription.is_some() {
+                    self.cancel_subscription();
                 } else if cmd == KeyboardInput::AltF || cmd == KeyboardInput::CtrlF {
                     self.state = State::FileSelector;
                     self.file_selector.refresh_screen();
+                } else if cmd == KeyboardInput::AltE || cmd == KeyboardInput::CtrlE {
+                    // Jumps to the item under the cursor, or back to where the explorer was left.
+                    if self.state == State::Editor {
+                        if let Some(location) = self.editor.schema_location_at_cursor() {
+                            self.schema_explorer.open(location);
+                        }
+                    }
+                    if self.state != State::SchemaExplorer {
+                        self.schema_explorer_return_state =
+                            mem::replace(&mut self.state, State::SchemaExplorer);
+                    }
+                    self.schema_explorer.refresh_screen(self.editor.schema());
+                } else if (cmd == KeyboardInput::AltP || cmd == KeyboardInput::CtrlP)
+                    && self.state == State::Editor
+                {
+                    self.write_schema_sdl();
                 } else if self.state == State::Editor {
                     self.editor.update(cmd);
                 } else if self.state == State::FileSelector {
-                    match self.file_selector.update(cmd) {
-                        Some(file_selector::Command::OpenFile(path)) => {
-                            self.state = State::Editor;
-                            self.content.borrow_mut().reload_from_file(path);
-                            self.editor.refresh_screen();
+                    if let Some(file_selector::Command::OpenFile(path)) =
+                        self.file_selector.update(cmd)
+                    {
+                        self.state = State::Editor;
+                        self.content.borrow_mut().reload_from_file(path);
+                        self.editor.refresh_screen();
+                    }
+                } else if self.state == State::SchemaExplorer {
+                    if let Some(schema_explorer::Command::Close) =
+                        self.schema_explorer.update(cmd, self.editor.schema())
+                    {
+                        self.state =
+                            mem::replace(&mut self.schema_explorer_return_state, State::Editor);
+                        match self.state {
+                            State::FileSelector => self.file_selector.refresh_screen(),
+                            _ => self.editor.refresh_screen(),
                         }
-                        _ => {}
-                    };
+                    }
                 } else {
                     unreachable!("Invalid state");
                 }
             }
         }
     }
+
+    fn write_schema_sdl(&self) {
+        match fs::write(
+            &self.schema_sdl_path,
+            sdl::print_schema(self.editor.schema()),
+        ) {
+            Ok(()) => info!("Schema written to {}", self.schema_sdl_path.display()),
+            Err(err) => error!("Failed writing schema: {}", err),
+        }
+    }
+
+    fn start_subscription(&mut self, operation_name: Option<&str>) {
+        self.cancel_subscription();
+
+        match self.net_ops.execute_graphql_subscription(
+            &self.content.borrow().lines.join("\n"),
+            operation_name,
+            |payload| info!("\x1B[95mSubscription event: \x1B[93m{:#?}\x1B[0m", payload),
+        ) {
+            Ok(subscription) => self.subscription = Some(subscription),
+            Err(err) => error!("Error while starting subscription over WebSocket: {}", err),
+        }
+    }
+
+    fn cancel_subscription(&mut self) {
+        if let Some(subscription) = self.subscription.take() {
+            subscription.cancel();
+        }
+    }
 }
 
 impl Drop for Gomqlet {
@@ -126,14 +234,61 @@ impl Drop for Gomqlet {
     }
 }
 
+// Exits with the error before the terminal is switched to raw mode.
+fn load_schema(net_ops: &NetOps, source: &SchemaSource) -> Schema {
+    Schema::new(net_ops, source).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    })
+}
+
 fn main() -> io::Result<()> {
     pretty_env_logger::init();
     info!("Gomqlet start");
 
     let args = CommandLineParams::parse();
 
-    let mut gomqlet = Gomqlet::new(args)?;
-    gomqlet.exec_loop()?;
+    match args.command {
+        Some(Command::PrintSchema) => {
+            let config = args.config();
+            let schema = load_schema(&NetOps::new(&config), &args.schema_source(&config));
+            print!("{}", sdl::print_schema(&schema));
+        }
+        Some(Command::CompareSchemas { ref old, ref new }) => {
+            let config = args.config();
+            let net_ops = NetOps::new(&config);
+            let old_source =
+                SchemaSource::from_path(PathBuf::from(old)).map_err(io::Error::other)?;
+            let new_source = match new {
+                Some(new) => {
+                    SchemaSource::from_path(PathBuf::from(new)).map_err(io::Error::other)?
+                }
+                None => args.schema_source(&config),
+            };
+
+            let changes = schema_diff::diff(
+                &load_schema(&net_ops, &old_source),
+                &load_schema(&net_ops, &new_source),
+            );
+            if changes.is_empty() {
+                println!("No changes");
+            }
+            for change in &changes {
+                println!("{}", change);
+            }
+
+            if changes
+                .iter()
+                .any(|change| change.criticality == schema_diff::Criticality::Breaking)
+            {
+                process::exit(1);
+            }
+        }
+        None => {
+            let mut gomqlet = Gomqlet::new(args)?;
+            gomqlet.exec_loop()?;
+        }
+    }
 
     Ok(())
 }
@@ -1,21 +1,64 @@
 use regex::Regex;
-use reqwest::blocking::Response;
+use reqwest::{blocking::Response, StatusCode};
 use serde_json::Value;
-use std::{fs::File, io::Read, time::Duration};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tungstenite::{
+    client::IntoClientRequest,
+    http::{HeaderName, HeaderValue},
+    stream::MaybeTlsStream,
+    Message, WebSocket,
+};
 
 use crate::{
     config::Config,
     json_path::{JsonPathResult, JsonPathRoot},
     magic_command::MagicCommand,
+    tokenizer::{TokenKind, Tokenizer},
     util::{err_ctx, random_integer, random_string, random_word, Error},
 };
 
-const INSPECTION_QUERY: &'static str = "query IntrospectionQuery { __schema { queryType { name } mutationType { name } subscriptionType { name } types { ...FullType } directives { name description locations args { ...InputValue } } }}fragment FullType on __Type { kind name description fields(includeDeprecated: true) { name description args { ...InputValue } type { ...TypeRef } isDeprecated deprecationReason } inputFields { ...InputValue } interfaces { ...TypeRef } enumValues(includeDeprecated: true) { name description isDeprecated deprecationReason } possibleTypes { ...TypeRef }}fragment InputValue on __InputValue { name description type { ...TypeRef } defaultValue}fragment TypeRef on __Type { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } }}";
+const INSPECTION_QUERY: &str = "query IntrospectionQuery { __schema { queryType { name } mutationType { name } subscriptionType { name } types { ...FullType } directives { name description locations args { ...InputValue } } }}fragment FullType on __Type { kind name description fields(includeDeprecated: true) { name description args(includeDeprecated: true) { ...InputValue } type { ...TypeRef } isDeprecated deprecationReason } inputFields(includeDeprecated: true) { ...InputValue } interfaces { ...TypeRef } enumValues(includeDeprecated: true) { name description isDeprecated deprecationReason } possibleTypes { ...TypeRef }}fragment InputValue on __InputValue { name description type { ...TypeRef } defaultValue isDeprecated deprecationReason}fragment TypeRef on __Type { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } }}";
 const TIMEOUT_SECONDS: u64 = 360;
+const SUBSCRIPTION_PROTOCOL: &str = "graphql-transport-ws";
+const SUBSCRIPTION_ID: &str = "1";
+// How often the subscription worker wakes up to check for cancellation.
+const SUBSCRIPTION_POLL_MILLISECONDS: u64 = 100;
+
+/**
+ * A running subscription. Payloads are streamed from a worker thread until the server completes
+ * the subscription or it is cancelled.
+ */
+pub struct Subscription {
+    cancelled: Arc<AtomicBool>,
+    worker: JoinHandle<()>,
+}
+
+impl Subscription {
+    pub fn cancel(self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.wait();
+    }
+
+    fn wait(self) {
+        if self.worker.join().is_err() {
+            error!("Subscription worker panicked");
+        }
+    }
+}
 
 pub struct NetOps {
     client: reqwest::blocking::Client,
     url: String,
+    subscription_url: String,
     headers: Vec<[String; 2]>,
     variables: Option<Value>,
 }
@@ -25,14 +68,17 @@ impl NetOps {
         NetOps {
             client: reqwest::blocking::Client::new(),
             url: config.url.clone(),
+            subscription_url: config
+                .subscription_url
+                .clone()
+                .unwrap_or_else(|| config.url.replacen("http", "ws", 1)),
             headers: config.headers.clone(),
             variables: config.variables.clone(),
         }
     }
 
-    pub fn execute_graphql_operation(&self, query: &str) {
-        let query = query.replace('"', "\\\"");
-        let response = match self.raw_execute_graphql_operation(&query) {
+    pub fn execute_graphql_operation(&self, query: &str, operation_name: Option<&str>) {
+        let response = match self.raw_execute_graphql_operation(query, operation_name) {
             Ok(response) => response,
             Err(err) => {
                 error!("Error while executing query over HTTP: {}", err);
@@ -49,7 +95,11 @@ impl NetOps {
             });
     }
 
-    fn raw_execute_graphql_operation(&self, query: &str) -> Result<Response, Error> {
+    fn raw_execute_graphql_operation(
+        &self,
+        query: &str,
+        operation_name: Option<&str>,
+    ) -> Result<Response, Error> {
         let mut request = self
             .client
             .post(&self.url)
@@ -59,38 +109,169 @@ impl NetOps {
             request = request.header(key, value);
         }
 
-        let query = self.remove_comments(query);
-        let query = &self.remove_new_lines(query);
-
-        let query = self
-            .replace_magic_values(query)
-            .map_err(err_ctx("Failed query execution"))?;
-
-        let body = format!("{{ \"query\": \"{}\" }}", query);
+        let query = self.prepare_query(query)?;
+        let body = NetOps::request_body(&query, operation_name);
 
         debug!("\x1B[95mBody: \x1B[94m{}\x1B[0m", &body);
         debug!("\x1B[95mHeaders: \x1B[94m{:?}\x1B[0m", self.headers);
         debug!("\x1B[95mURL: \x1B[94m{}\x1B[0m", self.url);
 
-        Ok(request.body(body).send().unwrap())
+        Ok(request.body(body).send()?)
+    }
+
+    pub fn execute_graphql_subscription(
+        &self,
+        query: &str,
+        operation_name: Option<&str>,
+        mut on_payload: impl FnMut(Value) + Send + 'static,
+    ) -> Result<Subscription, Error> {
+        let query = self.prepare_query(query)?;
+        let subscribe_message = format!(
+            "{{ \"id\": \"{}\", \"type\": \"subscribe\", \"payload\": {} }}",
+            SUBSCRIPTION_ID,
+            NetOps::request_body(&query, operation_name)
+        );
+
+        let mut request = self.subscription_url.as_str().into_client_request()?;
+        for [key, value] in &self.headers {
+            request.headers_mut().insert(
+                HeaderName::from_bytes(key.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static(SUBSCRIPTION_PROTOCOL),
+        );
+
+        debug!(
+            "\x1B[95mSubscription: \x1B[94m{}\x1B[0m",
+            &subscribe_message
+        );
+        debug!("\x1B[95mURL: \x1B[94m{}\x1B[0m", self.subscription_url);
+
+        let (mut socket, _) =
+            tungstenite::connect(request).map_err(err_ctx("Failed WebSocket connection"))?;
+        let poll_timeout = Some(Duration::from_millis(SUBSCRIPTION_POLL_MILLISECONDS));
+        match socket.get_mut() {
+            MaybeTlsStream::Plain(stream) => stream.set_read_timeout(poll_timeout)?,
+            MaybeTlsStream::NativeTls(stream) => stream.get_mut().set_read_timeout(poll_timeout)?,
+            _ => unreachable!("Unsupported WebSocket stream"),
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let worker = thread::spawn({
+            let cancelled = cancelled.clone();
+            move || match NetOps::stream_subscription(
+                &mut socket,
+                &subscribe_message,
+                &cancelled,
+                &mut on_payload,
+            ) {
+                Ok(()) => info!("\x1B[95mSubscription finished\x1B[0m"),
+                Err(err) => error!("Error while streaming subscription: {}", err),
+            }
+        });
+
+        Ok(Subscription { cancelled, worker })
     }
 
-    pub fn fetch_live_schema(&self) -> Result<String, Error> {
-        let mut response = self.raw_execute_graphql_operation(INSPECTION_QUERY)?;
+    // Speaks the client side of the graphql-transport-ws protocol until the subscription completes,
+    // fails, or gets cancelled.
+    fn stream_subscription<S: Read + Write>(
+        socket: &mut WebSocket<S>,
+        subscribe_message: &str,
+        cancelled: &AtomicBool,
+        on_payload: &mut impl FnMut(Value),
+    ) -> Result<(), Error> {
+        socket.send(Message::text(
+            "{ \"type\": \"connection_init\", \"payload\": {} }",
+        ))?;
+
+        loop {
+            if cancelled.load(Ordering::SeqCst) {
+                socket.send(Message::text(format!(
+                    "{{ \"id\": \"{}\", \"type\": \"complete\" }}",
+                    SUBSCRIPTION_ID
+                )))?;
+                socket.close(None)?;
+                return Ok(());
+            }
+
+            let message = match socket.read() {
+                Ok(Message::Text(text)) => serde_json::from_str::<Value>(&text)?,
+                Ok(Message::Close(_)) => return Err("Connection closed by the server".into()),
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(err))
+                    if err.kind() == io::ErrorKind::WouldBlock
+                        || err.kind() == io::ErrorKind::TimedOut =>
+                {
+                    continue
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            match message["type"].as_str() {
+                Some("connection_ack") => socket.send(Message::text(subscribe_message))?,
+                Some("ping") => socket.send(Message::text("{ \"type\": \"pong\" }"))?,
+                Some("next") => on_payload(message["payload"].clone()),
+                Some("error") => {
+                    return Err(format!("Subscription error: {}", message["payload"]).into())
+                }
+                Some("complete") => {
+                    socket.close(None)?;
+                    return Ok(());
+                }
+                _ => warn!("Unexpected subscription message: {}", message),
+            }
+        }
+    }
+
+    // The status is left to the caller, error responses can still have GraphQL errors in the body.
+    pub fn fetch_live_schema(&self) -> Result<(StatusCode, String), Error> {
+        let mut response = self.raw_execute_graphql_operation(INSPECTION_QUERY, None)?;
 
         let mut response_body = String::new();
-        response.read_to_string(&mut response_body).unwrap();
+        response.read_to_string(&mut response_body)?;
 
-        Ok(response_body)
+        Ok((response.status(), response_body))
     }
 
+    fn prepare_query(&self, query: &str) -> Result<String, Error> {
+        let query = self.remove_comments(query);
+        let query = &self.escape_json_string(&query);
+
+        self.replace_magic_values(query)
+            .map_err(err_ctx("Failed query execution"))
+    }
+
+    fn request_body(query: &str, operation_name: Option<&str>) -> String {
+        match operation_name {
+            Some(operation_name) => format!(
+                "{{ \"query\": \"{}\", \"operationName\": \"{}\" }}",
+                query, operation_name
+            ),
+            None => format!("{{ \"query\": \"{}\" }}", query),
+        }
+    }
+
+    // Comments are valid GraphQL, but magic values in commented out lines must not be executed.
     fn remove_comments(&self, subject: &str) -> String {
-        let re = Regex::new(r"(^|\n)//.*").unwrap();
-        re.replace_all(&subject, "").into()
+        Tokenizer::tokenize(subject, true)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .map(|token| token.original)
+            .collect()
     }
 
-    pub fn remove_new_lines(&self, subject: String) -> String {
-        subject.replace('\n', "")
+    // Line breaks are kept (escaped) as block strings depend on them.
+    fn escape_json_string(&self, subject: &str) -> String {
+        subject
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+            .replace('\t', "\\t")
     }
 
     fn replace_magic_values(&self, subject: &str) -> Result<String, Error> {
@@ -118,17 +299,15 @@ impl NetOps {
                             .and_then(|mut file| {
                                 let mut buf = String::new();
                                 file.read_to_string(&mut buf)?;
-                                self.raw_execute_graphql_operation(&buf)
+                                self.raw_execute_graphql_operation(&buf, None)
                             })
-                            .and_then(|response| {
-                                Ok(serde_json::from_reader::<_, Value>(response).unwrap())
+                            .map(|response| {
+                                serde_json::from_reader::<_, Value>(response).unwrap()
                             })?;
 
                         JsonPathRoot::from(&query_command.json_path)
                             .and_then(|json_path_root| json_path_root.extract(&json_response))
-                            .map(|json_path_result| {
-                                NetOps::insertable_snippet_from_json_path_result(json_path_result)
-                            })?
+                            .map(NetOps::insertable_snippet_from_json_path_result)?
                     }
                     MagicCommand::RandomInteger((min, max)) => random_integer(min, max).to_string(),
                     MagicCommand::RandomString(len) => {
@@ -139,8 +318,8 @@ impl NetOps {
                         .variables
                         .as_ref()
                         .ok_or("Variables are not defined in config json".into())
-                        .and_then(|variables: _| json_path_root.extract(&variables))
-                        .map(|result| NetOps::insertable_snippet_from_json_path_result(result))?,
+                        .and_then(|variables| json_path_root.extract(variables))
+                        .map(NetOps::insertable_snippet_from_json_path_result)?,
                 };
 
                 debug!("\x1B[95mReplacement: \x1B[92m{}\x1B[0m", replacement);
@@ -164,3 +343,163 @@ impl NetOps {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    use serde_json::{json, Value};
+    use tungstenite::{
+        handshake::server::{Request, Response},
+        http::HeaderValue,
+        Message, WebSocket,
+    };
+
+    use super::NetOps;
+    use crate::config::Config;
+
+    #[test]
+    fn test_prepare_query_keeps_strings_intact() {
+        let query = "mutation {\n# <random_word>\n  post(title: \"a \\\"#b\\\"\", body: \"\"\"\n    c\n    d\"\"\") # Comment\n}";
+        let body = NetOps::request_body(
+            &net_ops("http://localhost".to_string())
+                .prepare_query(query)
+                .unwrap(),
+            None,
+        );
+
+        assert_eq!(
+            "mutation {\n\n  post(title: \"a \\\"#b\\\"\", body: \"\"\"\n    c\n    d\"\"\") \n}",
+            serde_json::from_str::<Value>(&body).unwrap()["query"]
+        );
+    }
+
+    #[test]
+    fn test_subscription_streams_until_complete() {
+        let (net_ops, server) = serve_subscription(|socket| {
+            assert_eq!("connection_init", read_message(socket)["type"]);
+            send_message(socket, json!({ "type": "connection_ack" }));
+
+            let subscribe = read_message(socket);
+            assert_eq!("subscribe", subscribe["type"]);
+            assert_eq!("subscription { tick }", subscribe["payload"]["query"]);
+            assert_eq!("Ticks", subscribe["payload"]["operationName"]);
+
+            for i in 1..=2 {
+                send_message(
+                    socket,
+                    json!({ "id": subscribe["id"], "type": "next", "payload": { "data": { "tick": i } } }),
+                );
+            }
+            send_message(socket, json!({ "id": subscribe["id"], "type": "complete" }));
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        net_ops
+            .execute_graphql_subscription("subscription { tick }", Some("Ticks"), move |payload| {
+                sender.send(payload).unwrap()
+            })
+            .unwrap()
+            .wait();
+        server.join().unwrap();
+
+        assert_eq!(
+            vec![
+                json!({ "data": { "tick": 1 } }),
+                json!({ "data": { "tick": 2 } })
+            ],
+            receiver.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_subscription_cancel() {
+        let (net_ops, server) = serve_subscription(|socket| {
+            assert_eq!("connection_init", read_message(socket)["type"]);
+            send_message(socket, json!({ "type": "connection_ack" }));
+
+            let subscribe = read_message(socket);
+            send_message(
+                socket,
+                json!({ "id": subscribe["id"], "type": "next", "payload": { "data": { "tick": 1 } } }),
+            );
+
+            let complete = read_message(socket);
+            assert_eq!("complete", complete["type"]);
+            assert_eq!(subscribe["id"], complete["id"]);
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        let subscription = net_ops
+            .execute_graphql_subscription("subscription { tick }", None, move |payload| {
+                sender.send(payload).unwrap()
+            })
+            .unwrap();
+
+        assert_eq!(
+            json!({ "data": { "tick": 1 } }),
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+        );
+        subscription.cancel();
+        server.join().unwrap();
+    }
+
+    // The handshake callback signature is dictated by tungstenite.
+    #[allow(clippy::result_large_err)]
+    fn serve_subscription(
+        handler: impl FnOnce(&mut WebSocket<TcpStream>) + Send + 'static,
+    ) -> (NetOps, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let net_ops = net_ops(format!("http://{}/graphql", listener.local_addr().unwrap()));
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket =
+                tungstenite::accept_hdr(stream, |request: &Request, mut response: Response| {
+                    assert_eq!(
+                        "graphql-transport-ws",
+                        request.headers()["Sec-WebSocket-Protocol"]
+                    );
+                    response.headers_mut().insert(
+                        "Sec-WebSocket-Protocol",
+                        HeaderValue::from_static("graphql-transport-ws"),
+                    );
+                    Ok(response)
+                })
+                .unwrap();
+
+            handler(&mut socket);
+        });
+
+        (net_ops, server)
+    }
+
+    fn net_ops(url: String) -> NetOps {
+        NetOps::new(&Config {
+            url,
+            subscription_url: None,
+            headers: vec![],
+            schema_cache: String::new(),
+            schema_file: None,
+            variables: None,
+            complexity: Default::default(),
+        })
+    }
+
+    fn read_message(socket: &mut WebSocket<TcpStream>) -> Value {
+        loop {
+            if let Message::Text(text) = socket.read().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    fn send_message(socket: &mut WebSocket<TcpStream>, message: Value) {
+        socket.send(Message::text(message.to_string())).unwrap();
+    }
+}
@@ -1,9 +1,15 @@
+use std::fmt::Display;
+
 use crate::ast::{self, FieldList, ParamValue};
+use crate::span::Span;
 use crate::tokenizer::{Token, TokenKind};
 
 #[derive(Debug)]
 pub enum ParseErrorScope {
     Query,
+    Fragment,
+    VariableDefinition,
+    TypeRef,
     Field,
     ArgList,
     ArgListValue,
@@ -12,52 +18,421 @@ pub enum ParseErrorScope {
 
 #[derive(Debug)]
 pub struct ParseError {
-    pub token: Option<Token>,
+    pub token: Option<Box<Token>>,
+    // Where the error token starts, or the end of the source when ran out of tokens.
+    pub span: Span,
     pub scope: ParseErrorScope,
     pub message: String,
 }
 
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+// Operation name, variable definitions and directives.
+type OperationHeader = (
+    Option<Token>,
+    Option<ast::VariableDefinitionList>,
+    Vec<ast::Directive>,
+);
+
 pub struct Parser {
     tokens: Vec<Token>,
     ptr: usize,
+    // Errors the parser recovered from.
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens, ptr: 0 }
+        Parser {
+            tokens,
+            ptr: 0,
+            errors: vec![],
+        }
+    }
+
+    /**
+     * Parses as much of the document as possible: on a syntax error the parser skips to the next
+     * field or definition, so the returned document is partial when the error list is not empty.
+     */
+    pub fn parse(mut self) -> (ast::Document, Vec<ParseError>) {
+        let mut operations = vec![];
+        let mut fragments = vec![];
+
+        while self.peek_token().is_some() {
+            let definition_start_ptr = self.ptr;
+
+            if self.is_next_token_kind(&TokenKind::Comma) {
+                // Commas are insignificant.
+                self.ptr += 1;
+            } else if self.is_next_keyword("fragment") {
+                match self.parse_fragment_definition() {
+                    Ok(fragment) => fragments.push(fragment),
+                    Err(err) => {
+                        self.recover(err, definition_start_ptr, Parser::is_next_definition_start)
+                    }
+                }
+            } else {
+                match self.parse_operation() {
+                    Ok(operation) => operations.push(operation),
+                    Err(err) => {
+                        self.recover(err, definition_start_ptr, Parser::is_next_definition_start)
+                    }
+                }
+            }
+        }
+
+        (
+            ast::Document {
+                operations,
+                fragments,
+            },
+            self.errors,
+        )
     }
 
-    pub fn parse(mut self) -> Result<ast::Root, ParseError> {
+    // Records the error and skips tokens until a synchronization point outside of any (unclosed)
+    // braces, parens or brackets that started after the error.
+    fn recover(
+        &mut self,
+        err: ParseError,
+        start_ptr: usize,
+        is_synchronization_point: fn(&Parser) -> bool,
+    ) {
+        self.errors.push(err);
+
+        // Make progress, otherwise the same error would be reported again.
+        if self.ptr == start_ptr {
+            self.ptr += 1;
+        }
+
+        let mut depth = 0usize;
+        while let Some(token) = self.peek_token() {
+            if depth == 0 && is_synchronization_point(self) {
+                break;
+            }
+
+            match token.kind {
+                TokenKind::OpenBrace | TokenKind::OpenParen | TokenKind::OpenBracket => depth += 1,
+                TokenKind::CloseBrace | TokenKind::CloseParen | TokenKind::CloseBracket => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+            self.ptr += 1;
+        }
+    }
+
+    fn is_next_definition_start(&self) -> bool {
+        self.is_next_token_kind(&TokenKind::OpenBrace)
+            || ["query", "mutation", "subscription", "fragment"]
+                .iter()
+                .any(|keyword| self.is_next_keyword(keyword))
+    }
+
+    fn is_next_field_start_or_end(&self) -> bool {
+        self.is_next_token_keyword()
+            || self.is_next_token_kind(&TokenKind::Ellipsis)
+            || self.is_next_token_kind(&TokenKind::CloseBrace)
+    }
+
+    fn parse_operation(&mut self) -> Result<ast::Root, ParseError> {
         if self.is_next_keyword("mutation") {
             Ok(ast::Root::Mutation(self.parse_mutation()?))
+        } else if self.is_next_keyword("subscription") {
+            Ok(ast::Root::Subscription(self.parse_subscription()?))
         } else {
             Ok(ast::Root::Query(self.parse_query()?))
         }
     }
 
+    fn parse_fragment_definition(&mut self) -> Result<ast::FragmentDefinition, ParseError> {
+        if !self.is_next_keyword("fragment") {
+            return Err(self.parse_error(ParseErrorScope::Fragment, "Missing 'fragment' keyword"));
+        }
+        let span = self.peek_token().unwrap().span;
+        self.ptr += 1;
+
+        let name = if self.is_next_token_keyword() && !self.is_next_keyword("on") {
+            let name = self.peek_token().cloned().unwrap();
+            self.ptr += 1;
+            name
+        } else {
+            return Err(self.parse_error(ParseErrorScope::Fragment, "Missing fragment name"));
+        };
+
+        if !self.is_next_keyword("on") {
+            return Err(self.parse_error(
+                ParseErrorScope::Fragment,
+                "Invalid fragment: missing 'on' keyword",
+            ));
+        }
+        self.ptr += 1;
+
+        let type_condition = self.parse_type_condition()?;
+        let directives = self.parse_directives()?;
+
+        let field_list = if self.is_next_token_kind(&TokenKind::OpenBrace) {
+            self.parse_fields_subobject()?
+        } else {
+            FieldList::new_empty(
+                directives
+                    .last()
+                    .map(|directive| directive.end_span)
+                    .unwrap_or(type_condition.end_span()),
+            )
+        };
+
+        Ok(ast::FragmentDefinition {
+            span,
+            end_span: field_list.end_span,
+            name,
+            type_condition,
+            directives,
+            field_list,
+        })
+    }
+
     fn parse_query(&mut self) -> Result<ast::Query, ParseError> {
         if self.peek_token().is_none() {
             return Err(self.parse_error(ParseErrorScope::Query, "Empty query"));
         }
+        let span = self.peek_token().unwrap().span;
 
-        if self.is_next_keyword("query") {
+        let (name, variable_definitions, directives) = if self.is_next_keyword("query") {
             self.ptr += 1;
-        }
+            self.parse_operation_header()?
+        } else {
+            (None, None, vec![])
+        };
 
         let field_list = self.parse_fields_subobject()?;
 
-        Ok(ast::Query { field_list })
+        Ok(ast::Query {
+            span,
+            name,
+            variable_definitions,
+            directives,
+            field_list,
+        })
     }
 
     fn parse_mutation(&mut self) -> Result<ast::Mutation, ParseError> {
         if !self.is_next_keyword("mutation") {
             return Err(self.parse_error(ParseErrorScope::Query, "Empty mutation"));
         }
+        let span = self.peek_token().unwrap().span;
         self.ptr += 1;
 
+        let (name, variable_definitions, directives) = self.parse_operation_header()?;
         let field_list = self.parse_fields_subobject()?;
 
-        Ok(ast::Mutation { field_list })
+        Ok(ast::Mutation {
+            span,
+            name,
+            variable_definitions,
+            directives,
+            field_list,
+        })
+    }
+
+    fn parse_subscription(&mut self) -> Result<ast::Subscription, ParseError> {
+        if !self.is_next_keyword("subscription") {
+            return Err(self.parse_error(ParseErrorScope::Query, "Empty subscription"));
+        }
+        let span = self.peek_token().unwrap().span;
+        self.ptr += 1;
+
+        let (name, variable_definitions, directives) = self.parse_operation_header()?;
+        let field_list = self.parse_fields_subobject()?;
+
+        Ok(ast::Subscription {
+            span,
+            name,
+            variable_definitions,
+            directives,
+            field_list,
+        })
+    }
+
+    // Optional operation name, variable definitions and directives between the operation keyword and the selection set.
+    fn parse_operation_header(&mut self) -> Result<OperationHeader, ParseError> {
+        let name = if self.is_next_token_keyword() {
+            let name = self.peek_token().cloned().unwrap();
+            self.ptr += 1;
+            Some(name)
+        } else {
+            None
+        };
+
+        let variable_definitions = if self.is_next_token_kind(&TokenKind::OpenParen) {
+            Some(self.parse_variable_definitions()?)
+        } else {
+            None
+        };
+
+        let directives = self.parse_directives()?;
+
+        Ok((name, variable_definitions, directives))
+    }
+
+    fn parse_variable_definitions(&mut self) -> Result<ast::VariableDefinitionList, ParseError> {
+        if !self.is_next_token_kind(&TokenKind::OpenParen) {
+            return Err(self.parse_error(ParseErrorScope::VariableDefinition, "Missing open paren"));
+        }
+        let span = self.peek_token().unwrap().span;
+        self.ptr += 1;
+
+        let mut definitions = vec![];
+        loop {
+            if self.is_next_token_kind(&TokenKind::CloseParen) {
+                break;
+            }
+
+            let variable = self.parse_variable()?;
+
+            if !self.is_next_token_kind(&TokenKind::Colon) {
+                return Err(self.parse_error(
+                    ParseErrorScope::VariableDefinition,
+                    "Missing colon after variable name",
+                ));
+            }
+            self.ptr += 1;
+
+            let var_type = self.parse_type_ref()?;
+            let mut end_span = var_type.end_span();
+
+            let default_value = if self.is_next_token_kind(&TokenKind::Equals) {
+                self.ptr += 1;
+                let default_value = self.parse_arglist_value(&TokenKind::CloseParen)?;
+                end_span = default_value.end_span();
+                Some(default_value)
+            } else {
+                None
+            };
+
+            let directives = self.parse_directives()?;
+            if let Some(directive) = directives.last() {
+                end_span = directive.end_span;
+            }
+
+            definitions.push(ast::VariableDefinition {
+                span: variable.span,
+                end_span,
+                variable,
+                var_type,
+                default_value,
+                directives,
+            });
+
+            if self.is_next_token_kind(&TokenKind::Comma) {
+                self.ptr += 1;
+            } else if !self.is_next_token_kind(&TokenKind::Dollar) {
+                break;
+            }
+        }
+
+        if !self.is_next_token_kind(&TokenKind::CloseParen) {
+            return Err(
+                self.parse_error(ParseErrorScope::VariableDefinition, "Missing close paren")
+            );
+        }
+        let end_span = self.peek_token().unwrap().end_span();
+        self.ptr += 1;
+
+        Ok(ast::VariableDefinitionList {
+            span,
+            end_span,
+            definitions,
+        })
+    }
+
+    fn parse_variable(&mut self) -> Result<ast::Variable, ParseError> {
+        if !self.is_next_token_kind(&TokenKind::Dollar) {
+            return Err(self.parse_error(
+                ParseErrorScope::VariableDefinition,
+                "Missing variable sigil",
+            ));
+        }
+        let sigil = self.peek_token().cloned().unwrap();
+        let span = sigil.span;
+        self.ptr += 1;
+
+        let name = if self.is_next_token_keyword() {
+            let name = self.peek_token().cloned().unwrap();
+            self.ptr += 1;
+            name
+        } else {
+            // Error correction: empty name right after the sigil.
+            Token::new(
+                TokenKind::Keyword("".to_string()),
+                sigil.end_span(),
+                0,
+                "".to_string(),
+            )
+        };
+
+        Ok(ast::Variable {
+            span,
+            end_span: name.end_span(),
+            name,
+        })
+    }
+
+    fn parse_type_ref(&mut self) -> Result<ast::TypeRef, ParseError> {
+        let type_ref = match self.peek_token() {
+            Some(Token {
+                kind: TokenKind::OpenBracket,
+                span,
+                ..
+            }) => {
+                let span = *span;
+                self.ptr += 1;
+
+                let inner = self.parse_type_ref()?;
+
+                if !self.is_next_token_kind(&TokenKind::CloseBracket) {
+                    return Err(self.parse_error(
+                        ParseErrorScope::TypeRef,
+                        "Missing closing bracket of list type",
+                    ));
+                }
+                let end_span = self.peek_token().unwrap().end_span();
+                self.ptr += 1;
+
+                ast::TypeRef::List(ast::ListTypeRef {
+                    span,
+                    end_span,
+                    inner: Box::new(inner),
+                })
+            }
+            Some(Token {
+                kind: TokenKind::Keyword(_),
+                ..
+            }) => {
+                let name = self.peek_token().cloned().unwrap();
+                self.ptr += 1;
+                ast::TypeRef::Named(name)
+            }
+            _ => ast::TypeRef::Named(
+                self.missing_keyword(ParseErrorScope::TypeRef, "Missing space for type name")?,
+            ),
+        };
+
+        if self.is_next_token_kind(&TokenKind::Bang) {
+            let end_span = self.peek_token().unwrap().end_span();
+            self.ptr += 1;
+
+            return Ok(ast::TypeRef::NonNull(ast::NonNullTypeRef {
+                end_span,
+                inner: Box::new(type_ref),
+            }));
+        }
+
+        Ok(type_ref)
     }
 
     fn parse_field(&mut self) -> Result<ast::Field, ParseError> {
@@ -65,15 +440,17 @@ impl Parser {
             Some(Token {
                 kind: TokenKind::Keyword(_),
                 ..
-            }) => self
-                .parse_concrete_field()
-                .map(|field| ast::Field::Concrete(field)),
+            }) => self.parse_concrete_field().map(ast::Field::Concrete),
             Some(Token {
                 kind: TokenKind::Ellipsis,
                 ..
-            }) => self
-                .parse_union_field()
-                .map(|field| ast::Field::Union(field)),
+            }) => {
+                if self.is_keyword_at(self.ptr + 1, "on") {
+                    self.parse_union_field().map(ast::Field::Union)
+                } else {
+                    self.parse_fragment_spread().map(ast::Field::FragmentSpread)
+                }
+            }
             _ => Err(self.parse_error(ParseErrorScope::Field, "Invalid field start")),
         }
     }
@@ -90,29 +467,53 @@ impl Parser {
         };
         self.ptr += 1;
 
-        let mut end_pos = name_token.end_pos();
+        // `alias: name` form.
+        let (alias, name_token) = if self.is_next_token_kind(&TokenKind::Colon) {
+            self.ptr += 1;
+
+            let aliased_name_token = if self.is_next_token_keyword() {
+                let aliased_name_token = self.peek_token().cloned().unwrap();
+                self.ptr += 1;
+                aliased_name_token
+            } else {
+                self.missing_keyword(ParseErrorScope::Field, "Missing space for aliased field")?
+            };
+
+            (Some(name_token), aliased_name_token)
+        } else {
+            (None, name_token)
+        };
+
+        let mut end_span = name_token.end_span();
 
         let arglist = if self.is_next_token_kind(&TokenKind::OpenParen) {
             let arglist = self.parse_arglist(&TokenKind::OpenParen, &TokenKind::CloseParen)?;
-            end_pos = arglist.end_pos;
+            end_span = arglist.end_span;
             Some(arglist)
         } else {
             None
         };
 
+        let directives = self.parse_directives()?;
+        if let Some(directive) = directives.last() {
+            end_span = directive.end_span;
+        }
+
         let field_list = if self.is_next_token_kind(&TokenKind::OpenBrace) {
             let field_list = self.parse_fields_subobject()?;
-            end_pos = field_list.end_pos;
+            end_span = field_list.end_span;
             Some(field_list)
         } else {
             None
         };
 
         Ok(ast::ConcreteField {
-            start_pos: name_token.pos,
-            end_pos,
+            span: alias.as_ref().unwrap_or(&name_token).span,
+            end_span,
+            alias,
             name: name_token,
             arglist,
+            directives,
             field_list,
         })
     }
@@ -124,7 +525,7 @@ impl Parser {
                 "Invalid union field: missing ellipsis",
             ));
         }
-        let start_pos = self.peek_token().unwrap().pos;
+        let span = self.peek_token().unwrap().span;
         self.ptr += 1;
 
         if !self.is_next_token_kind(&TokenKind::Keyword("on".to_string())) {
@@ -135,49 +536,143 @@ impl Parser {
         }
         self.ptr += 1;
 
-        let type_name = if let Some(Token {
+        let type_name = self.parse_type_condition()?;
+        let directives = self.parse_directives()?;
+
+        let field_list = if self.is_next_token_kind(&TokenKind::OpenBrace) {
+            self.parse_fields_subobject()?
+        } else {
+            FieldList::new_empty(
+                directives
+                    .last()
+                    .map(|directive| directive.end_span)
+                    .unwrap_or(type_name.end_span()),
+            )
+        };
+        let end_span = field_list.end_span;
+
+        Ok(ast::UnionField {
+            span,
+            end_span,
+            type_name,
+            directives,
+            field_list,
+        })
+    }
+
+    // The type name after an `on` keyword.
+    fn parse_type_condition(&mut self) -> Result<Token, ParseError> {
+        if let Some(Token {
             kind: TokenKind::Keyword(_),
             ..
         }) = self.peek_token()
         {
             let type_name = self.peek_token().cloned().unwrap();
             self.ptr += 1;
-            type_name
+            Ok(type_name)
         } else {
-            // Error correction: add empty keyword token to be able to autocomple on.
-            let missing_token_pos = self.peek_previous_token().unwrap().end_pos() + 1; // Leave space for insertion.
-            let have_space = self
-                .peek_token()
-                .map(|next_token| next_token.pos >= missing_token_pos)
-                .unwrap_or(false);
-
-            if have_space {
+            self.missing_keyword(ParseErrorScope::Field, "Missing space for union type")
+        }
+    }
+
+    // Error correction: add empty keyword token to be able to autocomple on.
+    fn missing_keyword(&self, scope: ParseErrorScope, message: &str) -> Result<Token, ParseError> {
+        // Leave space for insertion.
+        let missing_token_span = self.peek_previous_token().unwrap().end_span().advance(" ");
+        let have_space = self
+            .peek_token()
+            .map(|next_token| next_token.pos() >= missing_token_span.offset)
+            .unwrap_or(false);
+
+        if have_space {
+            Ok(Token::new(
+                TokenKind::Keyword("".to_string()),
+                missing_token_span,
+                0,
+                "".to_string(),
+            ))
+        } else {
+            Err(self.parse_error(scope, message))
+        }
+    }
+
+    fn parse_fragment_spread(&mut self) -> Result<ast::FragmentSpread, ParseError> {
+        if !self.is_next_token_kind(&TokenKind::Ellipsis) {
+            return Err(self.parse_error(
+                ParseErrorScope::Field,
+                "Invalid fragment spread: missing ellipsis",
+            ));
+        }
+        let ellipsis = self.peek_token().cloned().unwrap();
+        self.ptr += 1;
+
+        let name = if self.is_next_token_keyword() {
+            let name = self.peek_token().cloned().unwrap();
+            self.ptr += 1;
+            name
+        } else {
+            // Error correction: empty name right after the ellipsis to autocomplete on.
+            Token::new(
+                TokenKind::Keyword("".to_string()),
+                ellipsis.end_span(),
+                0,
+                "".to_string(),
+            )
+        };
+
+        let directives = self.parse_directives()?;
+        let end_span = directives
+            .last()
+            .map(|directive| directive.end_span)
+            .unwrap_or(name.end_span());
+
+        Ok(ast::FragmentSpread {
+            span: ellipsis.span,
+            end_span,
+            name,
+            directives,
+        })
+    }
+
+    fn parse_directives(&mut self) -> Result<Vec<ast::Directive>, ParseError> {
+        let mut directives = vec![];
+
+        while self.is_next_token_kind(&TokenKind::At) {
+            let at = self.peek_token().cloned().unwrap();
+            self.ptr += 1;
+
+            let name = if self.is_next_token_keyword() {
+                let name = self.peek_token().cloned().unwrap();
+                self.ptr += 1;
+                name
+            } else {
+                // Error correction: empty name right after the `@` to autocomplete on.
                 Token::new(
                     TokenKind::Keyword("".to_string()),
-                    missing_token_pos,
+                    at.end_span(),
                     0,
                     "".to_string(),
                 )
+            };
+
+            let mut end_span = name.end_span();
+            let arglist = if self.is_next_token_kind(&TokenKind::OpenParen) {
+                let arglist = self.parse_arglist(&TokenKind::OpenParen, &TokenKind::CloseParen)?;
+                end_span = arglist.end_span;
+                Some(arglist)
             } else {
-                return Err(
-                    self.parse_error(ParseErrorScope::Field, "Missing space for union type")
-                );
-            }
-        };
+                None
+            };
 
-        let field_list = if self.is_next_token_kind(&TokenKind::OpenBrace) {
-            self.parse_fields_subobject()?
-        } else {
-            FieldList::new_empty(type_name.end_pos())
-        };
-        let end_pos = field_list.end_pos;
+            directives.push(ast::Directive {
+                span: at.span,
+                end_span,
+                name,
+                arglist,
+            });
+        }
 
-        Ok(ast::UnionField {
-            start_pos,
-            end_pos,
-            type_name,
-            field_list,
-        })
+        Ok(directives)
     }
 
     fn parse_arglist(
@@ -188,7 +683,7 @@ impl Parser {
         if !self.is_next_token_kind(open_token_kind) {
             return Err(self.parse_error(ParseErrorScope::ArgList, "Missing open paren"));
         }
-        let start_pos = self.peek_token().unwrap().pos;
+        let span = self.peek_token().unwrap().span;
         self.ptr += 1;
 
         let mut params = vec![];
@@ -214,16 +709,16 @@ impl Parser {
             } else {
                 // Else is omitted due to error handling (assume it's missing for now).
                 ParamValue::Missing((
-                    key.end_pos(),
+                    key.end_span(),
                     self.peek_token()
-                        .map(|token| token.pos)
-                        .unwrap_or(key.end_pos()),
+                        .map(|token| token.span)
+                        .unwrap_or(key.end_span()),
                 ))
             };
 
             params.push(ast::ParamKeyValuePair {
-                start_pos: key.pos,
-                end_pos: value.end_pos(),
+                span: key.span,
+                end_span: value.end_span(),
                 key,
                 value,
             });
@@ -243,12 +738,12 @@ impl Parser {
         if !self.is_next_token_kind(close_token_kind) {
             return Err(self.parse_error(ParseErrorScope::ArgList, "Missing close paren"));
         }
-        let end_pos = self.peek_token().unwrap().end_pos();
+        let end_span = self.peek_token().unwrap().end_span();
         self.ptr += 1;
 
         Ok(ast::ArgList {
-            start_pos,
-            end_pos,
+            span,
+            end_span,
             params,
         })
     }
@@ -259,25 +754,63 @@ impl Parser {
     ) -> Result<ast::ParamValue, ParseError> {
         let token = self.peek_token().cloned();
         match token {
-            Some(Token {
-                kind: TokenKind::Number(_),
-                ..
-            })
-            | Some(Token {
-                kind: TokenKind::Str(_),
-                ..
-            })
-            | Some(Token {
-                kind: TokenKind::Keyword(_),
-                ..
-            })
-            | Some(Token {
-                kind: TokenKind::MagicValue(_),
-                ..
-            }) => {
+            Some(
+                token @ Token {
+                    kind: TokenKind::Number(_),
+                    ..
+                },
+            ) => {
                 self.ptr += 1;
-                Ok(ast::ParamValue::Simple(token.unwrap()))
+                if token.original.contains(['.', 'e', 'E']) {
+                    Ok(ast::ParamValue::Float(token))
+                } else {
+                    Ok(ast::ParamValue::Int(token))
+                }
+            }
+            Some(
+                token @ Token {
+                    kind: TokenKind::Str(_),
+                    ..
+                },
+            ) => {
+                self.ptr += 1;
+                Ok(ast::ParamValue::String(token))
+            }
+            Some(
+                token @ Token {
+                    kind: TokenKind::BlockStr(_),
+                    ..
+                },
+            ) => {
+                self.ptr += 1;
+                Ok(ast::ParamValue::BlockString(token))
+            }
+            Some(
+                token @ Token {
+                    kind: TokenKind::Keyword(_),
+                    ..
+                },
+            ) => {
+                self.ptr += 1;
+                match token.original.as_str() {
+                    "true" | "false" => Ok(ast::ParamValue::Boolean(token)),
+                    "null" => Ok(ast::ParamValue::Null(token)),
+                    _ => Ok(ast::ParamValue::Enum(token)),
+                }
+            }
+            Some(
+                token @ Token {
+                    kind: TokenKind::MagicValue(_),
+                    ..
+                },
+            ) => {
+                self.ptr += 1;
+                Ok(ast::ParamValue::Magic(token))
             }
+            Some(Token {
+                kind: TokenKind::Dollar,
+                ..
+            }) => Ok(ast::ParamValue::Variable(self.parse_variable()?)),
             Some(Token {
                 kind: TokenKind::OpenBracket,
                 ..
@@ -291,13 +824,13 @@ impl Parser {
                 self.parse_arglist(&TokenKind::OpenBrace, &TokenKind::CloseBrace)?,
             )),
             // Error handling:
-            Some(Token { ref kind, pos, .. }) => {
+            Some(Token { ref kind, span, .. }) => {
                 if kind == close_token_kind || kind == &TokenKind::Comma {
-                    let start_pos = self
+                    let start_span = self
                         .peek_previous_token()
-                        .map(|token| token.end_pos())
-                        .unwrap_or(0);
-                    Ok(ast::ParamValue::Missing((start_pos, pos)))
+                        .map(|token| token.end_span())
+                        .unwrap_or(Span::new(0, 0, 1, 1));
+                    Ok(ast::ParamValue::Missing((start_span, span)))
                 } else {
                     Err(self.parse_error(
                         ParseErrorScope::ArgListValue,
@@ -322,21 +855,27 @@ impl Parser {
                 "Expected list opening backet for list param value",
             ));
         }
-        let start_pos = self.peek_token().unwrap().pos;
+        let span = self.peek_token().unwrap().span;
         self.ptr += 1;
 
         let mut elems = vec![];
         loop {
+            // Commas are insignificant.
+            while self.is_next_token_kind(&TokenKind::Comma) {
+                self.ptr += 1;
+            }
+
             if self.is_next_token_kind(&TokenKind::CloseBracket) {
                 break;
             }
 
-            elems.push(self.parse_arglist_value(close_token_kind)?);
+            let elem = self.parse_arglist_value(close_token_kind)?;
+            let is_missing = matches!(elem, ast::ParamValue::Missing(_));
+            elems.push(elem);
 
-            if !self.is_next_token_kind(&TokenKind::Comma) {
+            if is_missing {
                 break;
             }
-            self.ptr += 1;
         }
 
         if !self.is_next_token_kind(&TokenKind::CloseBracket) {
@@ -345,21 +884,21 @@ impl Parser {
                 "Expected list closing backet for list param value",
             ));
         }
-        let end_pos = self.peek_token().unwrap().pos;
+        let end_span = self.peek_token().unwrap().span;
         self.ptr += 1;
 
         Ok(ast::ListParamValue {
-            start_pos,
-            end_pos,
+            span,
+            end_span,
             elems,
         })
     }
 
     fn parse_fields_subobject(&mut self) -> Result<FieldList, ParseError> {
-        let start_pos = if self.is_next_token_kind(&TokenKind::OpenBrace) {
-            let start_pos = self.peek_token().unwrap().pos;
+        let span = if self.is_next_token_kind(&TokenKind::OpenBrace) {
+            let span = self.peek_token().unwrap().span;
             self.ptr += 1;
-            start_pos
+            span
         } else {
             return Err(self.parse_error(ParseErrorScope::Query, "Missing opening brace"));
         };
@@ -367,26 +906,53 @@ impl Parser {
         let mut fields = vec![];
         loop {
             if self.peek_token().is_none() {
-                return Err(self.parse_error(ParseErrorScope::Query, "Missing closing brace"));
+                // Keep what has been parsed so far.
+                self.errors
+                    .push(self.parse_error(ParseErrorScope::Query, "Missing closing brace"));
+                return Ok(ast::FieldList {
+                    span,
+                    end_span: self.peek_previous_token().unwrap().end_span(),
+                    fields,
+                });
             }
 
             if self.is_next_token_kind(&TokenKind::CloseBrace) {
-                let end_pos = self.peek_token().unwrap().end_pos();
+                let end_span = self.peek_token().unwrap().end_span();
                 self.ptr += 1;
                 return Ok(ast::FieldList {
-                    start_pos,
-                    end_pos,
+                    span,
+                    end_span,
                     fields,
                 });
             }
 
-            fields.push(self.parse_field()?);
+            // Commas are insignificant.
+            if self.is_next_token_kind(&TokenKind::Comma) {
+                self.ptr += 1;
+                continue;
+            }
+
+            let field_start_ptr = self.ptr;
+            match self.parse_field() {
+                Ok(field) => fields.push(field),
+                Err(err) => self.recover(err, field_start_ptr, Parser::is_next_field_start_or_end),
+            }
         }
     }
 
     fn parse_error(&self, scope: ParseErrorScope, message: &str) -> ParseError {
+        let token = self.peek_token().cloned().map(Box::new);
+        let span = match token {
+            Some(ref token) => token.span,
+            None => self
+                .peek_previous_token()
+                .map(|token| token.end_span())
+                .unwrap_or(Span::new(0, 0, 1, 1)),
+        };
+
         ParseError {
-            token: self.peek_token().cloned(),
+            token,
+            span,
             scope,
             message: message.into(),
         }
@@ -422,18 +988,28 @@ impl Parser {
         false
     }
 
-    fn is_next_token_keyword(&self) -> bool {
+    fn is_keyword_at(&self, ptr: usize, value: &str) -> bool {
         if let Some(Token {
-            kind: TokenKind::Keyword(_),
+            kind: TokenKind::Keyword(keyword),
             ..
-        }) = self.peek_token()
+        }) = self.tokens.get(ptr)
         {
-            true
+            keyword == value
         } else {
             false
         }
     }
 
+    fn is_next_token_keyword(&self) -> bool {
+        matches!(
+            self.peek_token(),
+            Some(Token {
+                kind: TokenKind::Keyword(_),
+                ..
+            })
+        )
+    }
+
     fn peek_keyword(&self) -> Option<&String> {
         if let Some(Token {
             kind: TokenKind::Keyword(keyword),
@@ -450,11 +1026,12 @@ impl Parser {
 #[cfg(test)]
 mod test {
     use crate::{
-        ast::{Mutation, Query, Root},
+        ast::{Document, Mutation, ParamValue, Query, Root, Subscription},
+        span::Span,
         tokenizer::Tokenizer,
     };
 
-    use super::Parser;
+    use super::{ParseError, Parser};
 
     #[test]
     fn test_empty() {
@@ -560,7 +1137,7 @@ mod test {
                 .value
                 .as_list()
                 .elems[0]
-                .as_simple()
+                .as_scalar()
                 .original,
         );
     }
@@ -631,12 +1208,12 @@ mod test {
             .unwrap();
         assert_eq!(1, args.params.len());
 
-        let first_arg_value = args.params.get(0).unwrap().value.as_object();
+        let first_arg_value = args.params.first().unwrap().value.as_object();
         assert_eq!(2, first_arg_value.params.len());
         assert_eq!("city".to_string(), first_arg_value.params[0].key.original);
         assert_eq!(
             "\"London\"".to_string(),
-            first_arg_value.params[0].value.as_simple().original
+            first_arg_value.params[0].value.as_scalar().original
         );
 
         assert_eq!(2, first_arg_value.params[1].value.as_list().elems.len());
@@ -671,7 +1248,7 @@ mod test {
         );
 
         assert_eq!(
-            9,
+            Span::new(9, 9, 1, 10),
             query.field_list.fields[0]
                 .as_concrete_field()
                 .field_list
@@ -679,11 +1256,11 @@ mod test {
                 .unwrap()
                 .fields[0]
                 .as_union_field()
-                .start_pos
+                .span
         );
 
         assert_eq!(
-            34,
+            Span::new(34, 34, 1, 35),
             query.field_list.fields[0]
                 .as_concrete_field()
                 .field_list
@@ -691,27 +1268,439 @@ mod test {
                 .unwrap()
                 .fields[0]
                 .as_union_field()
-                .end_pos
+                .end_span
         );
     }
 
-    fn parse_query(raw: &str) -> Query {
-        let tokens = Tokenizer::tokenize(raw, false);
-        let parser = Parser::new(tokens);
+    #[test]
+    fn test_named_query_with_variables() {
+        let query = parse_query("query GetUser($id: ID!, $first: Int = 10) { user(id: $id) }");
+
+        assert_eq!("GetUser".to_string(), query.name.as_ref().unwrap().original);
+
+        let definitions = &query.variable_definitions.as_ref().unwrap().definitions;
+        assert_eq!(2, definitions.len());
+        assert_eq!("id".to_string(), definitions[0].variable.name.original);
+        assert_eq!("ID!".to_string(), definitions[0].var_type.to_string());
+        assert!(definitions[0].default_value.is_none());
+        assert_eq!("first".to_string(), definitions[1].variable.name.original);
+        assert_eq!("Int".to_string(), definitions[1].var_type.to_string());
+        assert_eq!(
+            "10".to_string(),
+            definitions[1]
+                .default_value
+                .as_ref()
+                .unwrap()
+                .as_scalar()
+                .original
+        );
+
+        let arg_value = &query.field_list.fields[0]
+            .as_concrete_field()
+            .arglist
+            .as_ref()
+            .unwrap()
+            .params[0]
+            .value;
+        match arg_value {
+            ParamValue::Variable(variable) => assert_eq!("id", variable.name.original),
+            _ => panic!("Expected variable arg value"),
+        }
+    }
+
+    #[test]
+    fn test_insignificant_commas() {
+        let document = parse_document("{ id, name, posts(ids: [1 2, 3,]) { title, } }, { id }");
+
+        assert_eq!(2, document.operations.len());
+
+        let query = match &document.operations[0] {
+            Root::Query(query) => query,
+            _ => panic!("Expected query"),
+        };
+        assert_eq!(3, query.field_list.fields.len());
+
+        let posts = query.field_list.fields[2].as_concrete_field();
+        assert_eq!(
+            3,
+            posts.arglist.as_ref().unwrap().params[0]
+                .value
+                .as_list()
+                .elems
+                .len()
+        );
+        assert_eq!(1, posts.field_list.as_ref().unwrap().fields.len());
+    }
+
+    #[test]
+    fn test_typed_arglist_values() {
+        let query = parse_query(
+            "{ search(a: 1, b: 1.5, c: \"x\\ty\", d: \"\"\"\n  z\n\"\"\", e: true, f: null, g: ADMIN, h: <random_word>) }",
+        );
+
+        let params = &query.field_list.fields[0]
+            .as_concrete_field()
+            .arglist
+            .as_ref()
+            .unwrap()
+            .params;
+
+        assert!(matches!(params[0].value, ParamValue::Int(_)));
+        assert!(matches!(params[1].value, ParamValue::Float(_)));
+        assert!(matches!(params[2].value, ParamValue::String(_)));
+        assert_eq!(Some("x\ty"), params[2].value.string_value());
+        assert!(matches!(params[3].value, ParamValue::BlockString(_)));
+        assert_eq!(Some("z"), params[3].value.string_value());
+        assert_eq!(Some(true), params[4].value.boolean_value());
+        assert!(matches!(params[5].value, ParamValue::Null(_)));
+        assert!(matches!(params[6].value, ParamValue::Enum(_)));
+        assert!(matches!(params[7].value, ParamValue::Magic(_)));
+    }
+
+    #[test]
+    fn test_variable_list_type() {
+        let query = parse_query("query ($ids: [ID!]!) { users }");
+
+        assert!(query.name.is_none());
+        let definitions = &query.variable_definitions.as_ref().unwrap().definitions;
+        assert_eq!("[ID!]!".to_string(), definitions[0].var_type.to_string());
+        assert_eq!(
+            "ID".to_string(),
+            definitions[0].var_type.named_type().original
+        );
+    }
+
+    #[test]
+    fn test_variable_missing_type() {
+        let query = parse_query("query ($id: ) { users }");
+
+        let definitions = &query.variable_definitions.as_ref().unwrap().definitions;
+        assert_eq!(1, definitions.len());
+        assert_eq!(12, definitions[0].var_type.named_type().pos());
+        assert_eq!(0, definitions[0].var_type.named_type().len);
+    }
+
+    #[test]
+    fn test_named_mutation() {
+        let mutation =
+            parse_mutation("mutation CreateUser($name: String) { createUser(name: $name) { id } }");
+
+        assert_eq!("CreateUser".to_string(), mutation.name.unwrap().original);
+        assert_eq!(1, mutation.variable_definitions.unwrap().definitions.len());
+    }
+
+    #[test]
+    fn test_subscription() {
+        let subscription = parse_subscription(
+            "subscription OnMessage($room: ID!) @live { messageAdded(room: $room) { id body } }",
+        );
+
+        assert_eq!(Span::new(0, 0, 1, 1), subscription.span);
+        assert_eq!("OnMessage".to_string(), subscription.name.unwrap().original);
+        assert_eq!(
+            1,
+            subscription.variable_definitions.unwrap().definitions.len()
+        );
+        assert_eq!(1, subscription.directives.len());
+        assert_eq!(1, subscription.field_list.fields.len());
+    }
+
+    #[test]
+    fn test_alias() {
+        let query =
+            parse_query("{ primary: user(id: 1) { name } secondary: user(id: 2) { name } }");
+
+        assert_eq!(2, query.field_list.fields.len());
+
+        let primary = query.field_list.fields[0].as_concrete_field();
+        assert_eq!(
+            "primary".to_string(),
+            primary.alias.as_ref().unwrap().original
+        );
+        assert_eq!("user".to_string(), primary.name.original);
+        assert_eq!(Span::new(2, 2, 1, 3), primary.span);
+
+        let secondary = query.field_list.fields[1].as_concrete_field();
+        assert_eq!(
+            "secondary".to_string(),
+            secondary.alias.as_ref().unwrap().original
+        );
+        assert_eq!("user".to_string(), secondary.name.original);
+    }
+
+    #[test]
+    fn test_alias_without_field_name() {
+        let query = parse_query("{ primary: }");
+
+        let field = query.field_list.fields[0].as_concrete_field();
+        assert_eq!(
+            "primary".to_string(),
+            field.alias.as_ref().unwrap().original
+        );
+        assert_eq!(11, field.name.pos());
+        assert_eq!(0, field.name.len);
+    }
+
+    #[test]
+    fn test_directives() {
+        let document = parse_document(
+            "query Q($flag: Boolean! @dir) @live { user @include(if: $flag) { ...P @skip(if: true) ... on Admin @defer { id } } } fragment P on User @frag { id }",
+        );
+
+        let query = match &document.operations[0] {
+            Root::Query(query) => query,
+            _ => panic!("Expected query"),
+        };
+        assert_eq!("live".to_string(), query.directives[0].name.original);
+        assert_eq!(
+            "dir".to_string(),
+            query.variable_definitions.as_ref().unwrap().definitions[0].directives[0]
+                .name
+                .original
+        );
+
+        let user = query.field_list.fields[0].as_concrete_field();
+        assert_eq!(1, user.directives.len());
+        assert_eq!("include".to_string(), user.directives[0].name.original);
+        assert_eq!(
+            "if".to_string(),
+            user.directives[0].arglist.as_ref().unwrap().params[0]
+                .key
+                .original
+        );
+
+        let user_fields = &user.field_list.as_ref().unwrap().fields;
+        assert_eq!(
+            "skip".to_string(),
+            user_fields[0].as_fragment_spread().directives[0]
+                .name
+                .original
+        );
+        assert_eq!(
+            "defer".to_string(),
+            user_fields[1].as_union_field().directives[0].name.original
+        );
+
+        assert_eq!(
+            "frag".to_string(),
+            document.fragments[0].directives[0].name.original
+        );
+    }
+
+    #[test]
+    fn test_directive_without_name() {
+        let query = parse_query("{ user @ }");
+
+        let directive = &query.field_list.fields[0].as_concrete_field().directives[0];
+        assert_eq!(8, directive.name.pos());
+        assert_eq!(0, directive.name.len);
+    }
+
+    #[test]
+    fn test_fragment_definition() {
+        let document = parse_document(
+            "query { user { ...UserParts } } fragment UserParts on User { id name }",
+        );
+
+        assert_eq!(1, document.operations.len());
+        assert_eq!(1, document.fragments.len());
+
+        let fragment = &document.fragments[0];
+        assert_eq!("UserParts".to_string(), fragment.name.original);
+        assert_eq!("User".to_string(), fragment.type_condition.original);
+        assert_eq!(2, fragment.field_list.fields.len());
+        assert_eq!(Span::new(32, 32, 1, 33), fragment.span);
+        assert_eq!(Span::new(70, 70, 1, 71), fragment.end_span);
+    }
+
+    #[test]
+    fn test_spans_on_later_lines() {
+        let document =
+            parse_document("query {\n  user {\n    id\n  }\n}\nfragment F on User { id }");
+
+        let user = document.operations[0].field_list().fields[0].as_concrete_field();
+        assert_eq!(Span::new(10, 10, 2, 3), user.span);
+        assert_eq!(Span::new(27, 27, 4, 4), user.end_span);
+        assert_eq!(Span::new(30, 30, 6, 1), document.fragments[0].span);
+    }
+
+    #[test]
+    fn test_fragments_only() {
+        let document = parse_document("fragment A on User { id } fragment B on Company { ...C }");
+
+        assert!(document.operations.is_empty());
+        assert_eq!(2, document.fragments.len());
+        assert!(document.fragment("B").is_some());
+        assert!(document.fragment("C").is_none());
+    }
+
+    #[test]
+    fn test_fragment_spread() {
+        let query = parse_query("{ user { id ...UserParts } }");
+
+        let fields = &query.field_list.fields[0]
+            .as_concrete_field()
+            .field_list
+            .as_ref()
+            .unwrap()
+            .fields;
+        assert_eq!(2, fields.len());
+        assert_eq!(
+            "UserParts".to_string(),
+            fields[1].as_fragment_spread().name.original
+        );
+    }
+
+    #[test]
+    fn test_fragment_spread_without_name() {
+        let query = parse_query("{ user { ... } }");
+
+        let spread = query.field_list.fields[0]
+            .as_concrete_field()
+            .field_list
+            .as_ref()
+            .unwrap()
+            .fields[0]
+            .as_fragment_spread();
+        assert_eq!(12, spread.name.pos());
+        assert_eq!(0, spread.name.len);
+    }
+
+    #[test]
+    fn test_multiple_operations() {
+        let document = parse_document(
+            "query Users { users } mutation CreateUser { createUser { id } } fragment F on User { id } { company }",
+        );
+
+        assert_eq!(3, document.operations.len());
+        assert_eq!(1, document.fragments.len());
 
-        match parser.parse().unwrap() {
+        assert_eq!("Users", document.operations[0].name().unwrap().original);
+        assert_eq!(0..=21, document.operations[0].range_inclusive());
+        assert_eq!(
+            "CreateUser",
+            document.operations[1].name().unwrap().original
+        );
+        assert_eq!(22..=63, document.operations[1].range_inclusive());
+        assert!(document.operations[2].name().is_none());
+    }
+
+    #[test]
+    fn test_operation_at() {
+        let document = parse_document("query A { a } query B { b }");
+
+        assert_eq!(
+            "A",
+            document.operation_at(3).unwrap().name().unwrap().original
+        );
+        assert_eq!(
+            "B",
+            document.operation_at(20).unwrap().name().unwrap().original
+        );
+        assert!(document.operation_at(100).is_none());
+    }
+
+    #[test]
+    fn test_recover_from_field_error() {
+        let (document, errors) =
+            parse_with_errors("{ user(id: 1 { name } posts { title } company { id } }");
+
+        assert_eq!(1, errors.len());
+        assert_eq!("Missing close paren", errors[0].message);
+
+        let fields = &document.operations[0].field_list().fields;
+        assert_eq!(2, fields.len());
+        assert_eq!("posts", fields[0].as_concrete_field().name.original);
+        assert_eq!("company", fields[1].as_concrete_field().name.original);
+    }
+
+    #[test]
+    fn test_recover_from_invalid_field_start() {
+        let (document, errors) = parse_with_errors("{ user { : id } ] name }");
+
+        assert_eq!(2, errors.len());
+
+        let fields = &document.operations[0].field_list().fields;
+        assert_eq!(2, fields.len());
+        assert_eq!(
+            1,
+            fields[0]
+                .as_concrete_field()
+                .field_list
+                .as_ref()
+                .unwrap()
+                .fields
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_recover_from_definition_error() {
+        let (document, errors) =
+            parse_with_errors("{ a } ) ] query B { b } fragment F on User { id }");
+
+        assert_eq!(1, errors.len());
+        assert_eq!(2, document.operations.len());
+        assert_eq!(1, document.fragments.len());
+    }
+
+    #[test]
+    fn test_missing_closing_brace_keeps_partial_document() {
+        let (document, errors) = parse_with_errors("query { user { id name");
+
+        assert_eq!(2, errors.len());
+
+        let user = document.operations[0].field_list().fields[0].as_concrete_field();
+        assert_eq!(2, user.field_list.as_ref().unwrap().fields.len());
+        assert_eq!(
+            Span::new(22, 22, 1, 23),
+            user.field_list.as_ref().unwrap().end_span
+        );
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let (_, errors) = parse_with_errors("{\n  user(id: 1 {\n    name\n  }\n}");
+
+        assert_eq!(1, errors.len());
+        assert_eq!(Span::new(15, 15, 2, 14), errors[0].span);
+        assert_eq!(
+            "Missing close paren at line 2, col 14",
+            errors[0].to_string()
+        );
+
+        let (_, errors) = parse_with_errors("{\n  user {\n    name");
+        assert_eq!(Span::new(19, 19, 3, 9), errors[1].span);
+    }
+
+    fn parse_document(raw: &str) -> Document {
+        let (document, errors) = parse_with_errors(raw);
+        assert!(errors.is_empty(), "Unexpected parse errors: {:?}", errors);
+        document
+    }
+
+    fn parse_with_errors(raw: &str) -> (Document, Vec<ParseError>) {
+        Parser::new(Tokenizer::tokenize(raw, false)).parse()
+    }
+
+    fn parse_query(raw: &str) -> Query {
+        match parse_document(raw).operations.remove(0) {
             Root::Query(query) => query,
             _ => panic!("This must be called with a valid query"),
         }
     }
 
     fn parse_mutation(raw: &str) -> Mutation {
-        let tokens = Tokenizer::tokenize(raw, false);
-        let parser = Parser::new(tokens);
-
-        match parser.parse().unwrap() {
+        match parse_document(raw).operations.remove(0) {
             Root::Mutation(mutation) => mutation,
             _ => panic!("This must be called with a valid mutation"),
         }
     }
+
+    fn parse_subscription(raw: &str) -> Subscription {
+        match parse_document(raw).operations.remove(0) {
+            Root::Subscription(subscription) => subscription,
+            _ => panic!("This must be called with a valid subscription"),
+        }
+    }
 }
@@ -1,28 +1,74 @@
 use std::{
-    fmt,
-    fs::{self, File},
-    io::Write,
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
 };
 
-use serde_json::Value;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
 
 use crate::{
-    analyzer::SuggestionElem,
+    analyzer::{rank_suggestion_elems, Snippet, SuggestionElem},
     net_ops::NetOps,
-    util::{fuzzy_match, Error},
+    schema_diff,
+    sdl::SdlDocument,
+    util::Error,
 };
 
+// Same as the default of the `@deprecated` directive.
+pub const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+// Selectable on every composite type, unions included.
+const TYPENAME_FIELD: &str = "__typename";
+// Nesting of input object skeletons in snippets, input types can refer to themselves.
+const MAX_SNIPPET_INPUT_DEPTH: usize = 3;
+// Characters of a failed response shown in the error.
+const MAX_RESPONSE_EXCERPT_LENGTH: usize = 200;
+
+// Names repeat across the schema (eg.: type names in every field and argument referring to the type,
+// `id` fields), so they are shared instead of copied.
+pub type Name = Rc<str>;
+
+/**
+ * Hands out a single shared copy of every name while a schema is read.
+ */
+#[derive(Default)]
+struct Interner {
+    names: HashSet<Name>,
+}
+
+impl Interner {
+    fn intern(&mut self, name: &str) -> Name {
+        if let Some(interned) = self.names.get(name) {
+            return interned.clone();
+        }
+
+        let interned = Name::from(name);
+        self.names.insert(interned.clone());
+        interned
+    }
+}
+
+// Positions of the items by name, so lookups do not get slower as the schema grows.
+fn index_by_name<T>(items: &[T], name: impl Fn(&T) -> &Name) -> HashMap<Name, usize> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (name(item).clone(), i))
+        .collect()
+}
+
 #[derive(Debug)]
 pub enum TypeClass {
     NonNull(Box<TypeClass>),
     List(Box<TypeClass>),
-    Object(String),
-    Enum(String),
-    Interface(String),
-    Scalar(String),
-    Input(String),
-    Union(String),
+    Object(Name),
+    Enum(Name),
+    Interface(Name),
+    Scalar(Name),
+    Input(Name),
+    Union(Name),
 }
 
 impl fmt::Display for TypeClass {
@@ -41,19 +87,50 @@ impl fmt::Display for TypeClass {
 }
 
 impl TypeClass {
-    fn underlying_type_name(&self) -> Option<&String> {
+    pub fn underlying_type_name(&self) -> Option<&str> {
         match self {
-            TypeClass::Object(name) => Some(name),
-            TypeClass::Enum(name) => Some(name),
-            TypeClass::Interface(name) => Some(name),
-            TypeClass::Scalar(name) => Some(name),
-            TypeClass::Input(name) => Some(name),
-            TypeClass::Union(name) => Some(name),
+            TypeClass::Object(name)
+            | TypeClass::Enum(name)
+            | TypeClass::Interface(name)
+            | TypeClass::Scalar(name)
+            | TypeClass::Input(name)
+            | TypeClass::Union(name) => Some(name),
             TypeClass::NonNull(inner) => inner.underlying_type_name(),
             TypeClass::List(inner) => inner.underlying_type_name(),
         }
     }
 
+    // GraphQL notation, eg.: `[Post!]!`.
+    pub fn signature(&self) -> String {
+        match self {
+            TypeClass::NonNull(inner) => format!("{}!", inner.signature()),
+            TypeClass::List(inner) => format!("[{}]", inner.signature()),
+            TypeClass::Object(name)
+            | TypeClass::Enum(name)
+            | TypeClass::Interface(name)
+            | TypeClass::Scalar(name)
+            | TypeClass::Input(name)
+            | TypeClass::Union(name) => name.to_string(),
+        }
+    }
+
+    // Whether values of the type need a selection of subfields.
+    pub fn is_composite(&self) -> bool {
+        match self {
+            TypeClass::NonNull(inner) | TypeClass::List(inner) => inner.is_composite(),
+            TypeClass::Object(_) | TypeClass::Interface(_) | TypeClass::Union(_) => true,
+            TypeClass::Enum(_) | TypeClass::Scalar(_) | TypeClass::Input(_) => false,
+        }
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self.skip_non_null(), TypeClass::List(_))
+    }
+
+    pub fn is_non_null(&self) -> bool {
+        matches!(self, TypeClass::NonNull(_))
+    }
+
     pub fn skip_non_null(&self) -> &TypeClass {
         match self {
             TypeClass::Object(_) => self,
@@ -67,237 +144,392 @@ impl TypeClass {
         }
     }
 
-    fn from_json_value(node: &Value) -> TypeClass {
-        let kind = node.as_object().unwrap()["kind"].as_str().unwrap();
-        match kind {
+    fn from_json_value(node: &Value, interner: &mut Interner) -> Result<TypeClass, SchemaError> {
+        let kind = json_str(node, "kind")?;
+        let type_class = match kind {
             "NON_NULL" => TypeClass::NonNull(Box::new(TypeClass::from_json_value(
-                &node.as_object().unwrap()["ofType"],
-            ))),
+                &node["ofType"],
+                interner,
+            )?)),
             "LIST" => TypeClass::List(Box::new(TypeClass::from_json_value(
-                &node.as_object().unwrap()["ofType"],
-            ))),
-            "OBJECT" => TypeClass::Object(
-                node.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-            ),
-            "INTERFACE" => TypeClass::Interface(
-                node.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-            ),
-            "SCALAR" => TypeClass::Scalar(
-                node.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-            ),
-            "INPUT_OBJECT" => TypeClass::Input(
-                node.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-            ),
-            "ENUM" => TypeClass::Enum(
-                node.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-            ),
-            "UNION" => TypeClass::Union(
-                node.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-            ),
-            _ => unimplemented!("Unmapped field type: {}", kind),
-        }
+                &node["ofType"],
+                interner,
+            )?)),
+            _ => {
+                let name = interner.intern(json_str(node, "name")?);
+
+                match kind {
+                    "OBJECT" => TypeClass::Object(name),
+                    "INTERFACE" => TypeClass::Interface(name),
+                    "SCALAR" => TypeClass::Scalar(name),
+                    "INPUT_OBJECT" => TypeClass::Input(name),
+                    "ENUM" => TypeClass::Enum(name),
+                    "UNION" => TypeClass::Union(name),
+                    _ => {
+                        return Err(SchemaError::Invalid(format!(
+                            "Unknown kind {} of type {}",
+                            kind, name
+                        )))
+                    }
+                }
+            }
+        };
+
+        Ok(type_class)
+    }
+}
+
+// The `reason` of deprecated items, `None` when not deprecated.
+fn deprecation_reason_from_json_value(node: &Value) -> Option<String> {
+    if node["isDeprecated"].as_bool() == Some(true) {
+        Some(
+            node["deprecationReason"]
+                .as_str()
+                .unwrap_or(DEFAULT_DEPRECATION_REASON)
+                .to_string(),
+        )
+    } else {
+        None
     }
 }
 
+// `node[key]` as a string, the error names the missing key.
+fn json_str<'v>(node: &'v Value, key: &str) -> Result<&'v str, SchemaError> {
+    node[key]
+        .as_str()
+        .ok_or_else(|| missing_json_value(node, key))
+}
+
+fn json_array<'v>(node: &'v Value, key: &str) -> Result<&'v Vec<Value>, SchemaError> {
+    node[key]
+        .as_array()
+        .ok_or_else(|| missing_json_value(node, key))
+}
+
+fn missing_json_value(node: &Value, key: &str) -> SchemaError {
+    match node["name"].as_str() {
+        Some(name) => SchemaError::Invalid(format!("Missing `{}` of {}", key, name)),
+        None => SchemaError::Invalid(format!("Missing `{}`", key)),
+    }
+}
+
+// Messages of the GraphQL `errors` of a response.
+fn server_errors(response: &Value) -> Vec<String> {
+    let Some(errors) = response["errors"].as_array() else {
+        return vec![];
+    };
+
+    errors
+        .iter()
+        .map(|error| match error["message"].as_str() {
+            Some(message) => message.to_string(),
+            None => error.to_string(),
+        })
+        .collect()
+}
+
+// Whitespace collapsed, eg.: the start of an HTML error page.
+fn response_excerpt(response_body: &str) -> String {
+    let excerpt = response_body
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if excerpt.is_empty() {
+        return "empty response".to_string();
+    }
+    excerpt.chars().take(MAX_RESPONSE_EXCERPT_LENGTH).collect()
+}
+
+fn description_from_json_value(node: &Value) -> Option<String> {
+    node["description"]
+        .as_str()
+        .map(|description| description.to_string())
+}
+
 pub struct Arg {
-    pub name: String,
+    pub name: Name,
+    pub description: Option<String>,
     pub arg_type: TypeClass,
+    // As a GraphQL literal, eg.: `"hello"` or `10`.
+    pub default_value: Option<String>,
+    pub deprecation_reason: Option<String>,
 }
 
 impl Arg {
-    fn from_json_value(node: &Value) -> Arg {
-        let object = node.as_object().unwrap();
-        let name = object["name"].as_str().unwrap().to_string();
-        let arg_type = TypeClass::from_json_value(&object["type"]);
+    fn from_json_value(node: &Value, interner: &mut Interner) -> Result<Arg, SchemaError> {
+        let name = interner.intern(json_str(node, "name")?);
+        let arg_type = TypeClass::from_json_value(&node["type"], interner)?;
 
-        Arg { name, arg_type }
+        Ok(Arg {
+            name,
+            description: description_from_json_value(node),
+            arg_type,
+            default_value: node["defaultValue"]
+                .as_str()
+                .map(|default_value| default_value.to_string()),
+            deprecation_reason: deprecation_reason_from_json_value(node),
+        })
+    }
+
+    // Eg.: `first: Int = 10`.
+    pub fn signature(&self) -> String {
+        match &self.default_value {
+            Some(default_value) => format!(
+                "{}: {} = {}",
+                self.name,
+                self.arg_type.signature(),
+                default_value
+            ),
+            None => format!("{}: {}", self.name, self.arg_type.signature()),
+        }
     }
 }
 
 pub struct ArgList {
     elems: Vec<Arg>,
+    index: HashMap<Name, usize>,
 }
 
 impl ArgList {
-    pub fn arg_names(&self, prefix: &String) -> Vec<SuggestionElem> {
-        self.elems
+    fn new(elems: Vec<Arg>) -> ArgList {
+        ArgList {
+            index: index_by_name(&elems, |arg| &arg.name),
+            elems,
+        }
+    }
+
+    fn from_json_value(
+        node: &Value,
+        key: &str,
+        interner: &mut Interner,
+    ) -> Result<ArgList, SchemaError> {
+        let elems = json_array(node, key)?
             .iter()
+            .map(|arg| Arg::from_json_value(arg, interner))
+            .collect::<Result<_, _>>()?;
+
+        Ok(ArgList::new(elems))
+    }
+
+    // Args matching the prefix, except the ones already present.
+    pub fn arg_names(&self, prefix: &str, present: &[&str]) -> Vec<SuggestionElem> {
+        let elems = self
+            .elems
+            .iter()
+            .filter(|arg| !present.contains(&arg.name.as_ref()))
             .filter_map(|arg| {
-                if let Some(fuzzy_match_positions) = fuzzy_match(&arg.name, prefix) {
-                    Some(SuggestionElem {
-                        name: arg.name.clone(),
-                        kind: format!("{}", arg.arg_type),
-                        fuzzy_match_positions,
-                    })
-                } else {
-                    None
-                }
+                SuggestionElem::matching(
+                    &arg.name,
+                    format!("{}", arg.arg_type),
+                    prefix,
+                    arg.deprecation_reason.is_some(),
+                )
             })
-            .collect()
+            .collect();
+
+        rank_suggestion_elems(elems)
     }
 
-    pub fn arg(&self, name: &String) -> Option<&Arg> {
-        for arg in &self.elems {
-            if &arg.name == name {
-                return Some(arg);
-            }
+    // Non-null arguments without a default value, these must be present.
+    pub fn required_args(&self) -> impl Iterator<Item = &Arg> {
+        self.elems
+            .iter()
+            .filter(|arg| arg.arg_type.is_non_null() && arg.default_value.is_none())
+    }
+
+    // Eg.: `(first: Int = 10, after: String)`, empty without args.
+    pub fn signature(&self) -> String {
+        if self.elems.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "({})",
+                self.elems
+                    .iter()
+                    .map(|arg| arg.signature())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
         }
+    }
 
-        None
+    pub fn args(&self) -> &[Arg] {
+        &self.elems
+    }
+
+    pub fn arg(&self, name: &str) -> Option<&Arg> {
+        self.index.get(name).map(|&i| &self.elems[i])
     }
 }
 
 pub struct Field {
-    pub name: String,
+    pub name: Name,
+    pub description: Option<String>,
     pub field_type: TypeClass,
     pub args: ArgList,
+    pub deprecation_reason: Option<String>,
 }
 
 impl Field {
-    fn from_json_value(node: &Value) -> Field {
-        let name = node.as_object().unwrap()["name"]
-            .as_str()
-            .unwrap()
-            .to_string();
+    fn from_json_value(node: &Value, interner: &mut Interner) -> Result<Field, SchemaError> {
+        Ok(Field {
+            name: interner.intern(json_str(node, "name")?),
+            description: description_from_json_value(node),
+            field_type: TypeClass::from_json_value(&node["type"], interner)?,
+            args: ArgList::from_json_value(node, "args", interner)?,
+            deprecation_reason: deprecation_reason_from_json_value(node),
+        })
+    }
 
-        Field {
-            name,
-            field_type: TypeClass::from_json_value(&node.as_object().unwrap()["type"]),
-            args: ArgList {
-                elems: Field::resolve_args(node.as_object().unwrap()["args"].as_array().unwrap()),
-            },
-        }
+    fn suggestion(&self, prefix: &str) -> Option<SuggestionElem> {
+        SuggestionElem::matching(
+            &self.name,
+            format!("{}", self.field_type),
+            prefix,
+            self.deprecation_reason.is_some(),
+        )
     }
 
-    fn resolve_args(raw_args: &Vec<Value>) -> Vec<Arg> {
-        raw_args
-            .iter()
-            .map(|raw_arg| Arg {
-                name: raw_arg.as_object().unwrap()["name"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-                arg_type: TypeClass::from_json_value(&raw_arg.as_object().unwrap()["type"]),
-            })
-            .collect()
+    // Eg.: `posts(first: Int = 10): [Post!]!`.
+    pub fn signature(&self) -> String {
+        format!(
+            "{}{}: {}",
+            self.name,
+            self.args.signature(),
+            self.field_type.signature()
+        )
     }
 }
 
 pub struct ObjectType {
-    name: String,
+    name: Name,
+    description: Option<String>,
     fields: Vec<Field>,
-    possible_types: Vec<String>,
+    field_index: HashMap<Name, usize>,
+    possible_types: Vec<Name>,
+    // Implemented interfaces, interfaces can implement other interfaces too.
+    interfaces: Vec<Name>,
 }
 
 impl ObjectType {
-    pub fn field_names(&self, prefix: &str) -> Vec<SuggestionElem> {
-        self.fields
+    // Fields matching the prefix, except the ones already present.
+    pub fn field_names(&self, prefix: &str, present: &[&str]) -> Vec<SuggestionElem> {
+        let elems = self
+            .fields
             .iter()
-            .filter_map(|field| {
-                if let Some(fuzzy_match_positions) = fuzzy_match(&field.name, prefix) {
-                    Some(SuggestionElem {
-                        name: field.name.clone(),
-                        kind: format!("{}", field.field_type),
-                        fuzzy_match_positions,
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect()
-    }
+            .filter(|field| !present.contains(&field.name.as_ref()))
+            .filter_map(|field| field.suggestion(prefix))
+            .collect();
 
-    pub fn possible_type_names(&self, prefix: &str) -> Vec<SuggestionElem> {
-        self.possible_types
-            .iter()
-            .filter_map(|type_name| {
-                if let Some(fuzzy_match_positions) = fuzzy_match(&type_name, prefix) {
-                    Some(SuggestionElem {
-                        name: type_name.clone(),
-                        kind: "Object".to_string(),
-                        fuzzy_match_positions,
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect()
+        rank_suggestion_elems(elems)
     }
 }
 
 pub struct InputObjectType {
-    pub name: String,
+    pub name: Name,
+    description: Option<String>,
     pub args: ArgList,
 }
 
+pub struct EnumValue {
+    pub name: Name,
+    pub description: Option<String>,
+    pub deprecation_reason: Option<String>,
+}
+
+impl EnumValue {
+    fn from_json_value(node: &Value, interner: &mut Interner) -> Result<EnumValue, SchemaError> {
+        Ok(EnumValue {
+            name: interner.intern(json_str(node, "name")?),
+            description: description_from_json_value(node),
+            deprecation_reason: deprecation_reason_from_json_value(node),
+        })
+    }
+}
+
 pub struct EnumType {
-    name: String,
-    elems: Vec<String>,
+    name: Name,
+    description: Option<String>,
+    elems: Vec<EnumValue>,
+    index: HashMap<Name, usize>,
 }
 
 impl EnumType {
+    pub fn values(&self) -> &[EnumValue] {
+        &self.elems
+    }
+
+    pub fn value(&self, value: &str) -> Option<&EnumValue> {
+        self.index.get(value).map(|&i| &self.elems[i])
+    }
+
     pub fn field_names(&self, prefix: &str) -> Vec<SuggestionElem> {
-        self.elems
+        let elems = self
+            .elems
             .iter()
             .filter_map(|enum_value| {
-                if let Some(fuzzy_match_positions) = fuzzy_match(&enum_value, prefix) {
-                    Some(SuggestionElem {
-                        name: enum_value.clone(),
-                        kind: "Enum".to_string(),
-                        fuzzy_match_positions,
-                    })
-                } else {
-                    None
-                }
+                SuggestionElem::matching(
+                    &enum_value.name,
+                    "Enum".to_string(),
+                    prefix,
+                    enum_value.deprecation_reason.is_some(),
+                )
             })
-            .collect()
+            .collect();
+
+        rank_suggestion_elems(elems)
     }
 }
 
 pub struct UnionType {
-    name: String,
+    name: Name,
+    description: Option<String>,
     // This has the assumption that all types are Object type.
-    possible_types: Vec<String>,
+    possible_types: Vec<Name>,
+}
+
+pub struct DirectiveDefinition {
+    pub name: Name,
+    pub description: Option<String>,
+    pub locations: Vec<String>,
+    pub args: ArgList,
 }
 
-impl UnionType {
-    pub fn possible_type_names(&self, prefix: &str) -> Vec<SuggestionElem> {
-        self.possible_types
+impl DirectiveDefinition {
+    fn from_json_value(
+        node: &Value,
+        interner: &mut Interner,
+    ) -> Result<DirectiveDefinition, SchemaError> {
+        let name = interner.intern(json_str(node, "name")?);
+        let locations = json_array(node, "locations")?
             .iter()
-            .filter_map(|type_name| {
-                if let Some(fuzzy_match_positions) = fuzzy_match(&type_name, prefix) {
-                    Some(SuggestionElem {
-                        name: type_name.clone(),
-                        kind: "Object".to_string(),
-                        fuzzy_match_positions,
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect()
+            .filter_map(|location| location.as_str())
+            .map(|location| location.to_string())
+            .collect();
+        let args = ArgList::from_json_value(node, "args", interner)?;
+
+        Ok(DirectiveDefinition {
+            name,
+            description: description_from_json_value(node),
+            locations,
+            args,
+        })
+    }
+
+    // Eg.: `@include(if: Boolean!)`.
+    pub fn signature(&self) -> String {
+        format!("@{}{}", self.name, self.args.signature())
     }
 }
 
+pub struct ScalarType {
+    name: Name,
+    description: Option<String>,
+}
+
 pub enum Type {
+    Scalar(ScalarType),
     Object(ObjectType),
     Interface(ObjectType),
     InputObject(InputObjectType),
@@ -306,108 +538,194 @@ pub enum Type {
 }
 
 impl Type {
-    pub fn from_json_value(node: &Value) -> Option<Type> {
-        let object = node.as_object().unwrap();
-        let name = object["name"].as_str().unwrap().to_string();
-        let kind = object["kind"].as_str().unwrap();
+    // None for kinds the editor has no use for.
+    fn from_json_value(node: &Value, interner: &mut Interner) -> Result<Option<Type>, SchemaError> {
+        let name = interner.intern(json_str(node, "name")?);
+        let kind = json_str(node, "kind")?;
+        let description = description_from_json_value(node);
 
-        match kind {
+        let ty = match kind {
+            "SCALAR" => Some(Type::Scalar(ScalarType { name, description })),
             "OBJECT" | "INTERFACE" => {
-                let fields = object["fields"]
-                    .as_array()
-                    .unwrap()
+                let fields = json_array(node, "fields")?
                     .iter()
-                    .map(|field_def| Field::from_json_value(field_def))
-                    .collect();
-
-                let possible_types = if object["possibleTypes"].is_array() {
-                    object["possibleTypes"]
-                        .as_array()
-                        .unwrap()
-                        .iter()
-                        .map(|value| {
-                            value.as_object().unwrap()["name"]
-                                .as_str()
-                                .unwrap()
-                                .to_string()
-                        })
-                        .collect()
-                } else {
-                    vec![]
-                };
+                    .map(|field_def| Field::from_json_value(field_def, interner))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let field_index = index_by_name(&fields, |field| &field.name);
+                let possible_types =
+                    Type::type_names_from_json_value(node, "possibleTypes", interner)?;
+                let interfaces = Type::type_names_from_json_value(node, "interfaces", interner)?;
 
                 if kind == "OBJECT" {
                     Some(Type::Object(ObjectType {
                         name,
+                        description,
                         fields,
+                        field_index,
                         possible_types,
+                        interfaces,
                     }))
                 } else {
                     Some(Type::Interface(ObjectType {
                         name,
+                        description,
                         fields,
+                        field_index,
                         possible_types,
+                        interfaces,
                     }))
                 }
             }
-            "INPUT_OBJECT" => {
-                let args_elems = object["inputFields"]
-                    .as_array()
-                    .unwrap()
-                    .iter()
-                    .map(|arg_def| Arg::from_json_value(arg_def))
-                    .collect();
-                let args = ArgList { elems: args_elems };
-
-                Some(Type::InputObject(InputObjectType { name, args }))
-            }
+            "INPUT_OBJECT" => Some(Type::InputObject(InputObjectType {
+                name,
+                description,
+                args: ArgList::from_json_value(node, "inputFields", interner)?,
+            })),
             "ENUM" => {
-                let elems = object["enumValues"]
-                    .as_array()
-                    .unwrap()
-                    .iter()
-                    .map(|value| {
-                        value.as_object().unwrap()["name"]
-                            .as_str()
-                            .unwrap()
-                            .to_string()
-                    })
-                    .collect();
-
-                Some(Type::Enum(EnumType { name, elems }))
-            }
-            "UNION" => {
-                let possible_types = object["possibleTypes"]
-                    .as_array()
-                    .unwrap()
+                let elems = json_array(node, "enumValues")?
                     .iter()
-                    .map(|value| {
-                        value.as_object().unwrap()["name"]
-                            .as_str()
-                            .unwrap()
-                            .to_string()
-                    })
-                    .collect();
-
-                Some(Type::Union(UnionType {
+                    .map(|enum_value| EnumValue::from_json_value(enum_value, interner))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Some(Type::Enum(EnumType {
                     name,
-                    possible_types,
+                    description,
+                    index: index_by_name(&elems, |enum_value| &enum_value.name),
+                    elems,
                 }))
             }
+            "UNION" => Some(Type::Union(UnionType {
+                name,
+                description,
+                possible_types: Type::type_names_from_json_value(node, "possibleTypes", interner)?,
+            })),
             _ => None,
+        };
+
+        Ok(ty)
+    }
+
+    // Names of the type references under `key`. Types without such references (eg.: `possibleTypes` of objects)
+    // have null instead of an empty list, some servers for interfaces too.
+    fn type_names_from_json_value(
+        node: &Value,
+        key: &str,
+        interner: &mut Interner,
+    ) -> Result<Vec<Name>, SchemaError> {
+        let Some(type_refs) = node[key].as_array() else {
+            return Ok(vec![]);
+        };
+
+        type_refs
+            .iter()
+            .map(|type_ref| Ok(interner.intern(json_str(type_ref, "name")?)))
+            .collect()
+    }
+
+    // The name shared with the references to the type.
+    fn interned_name(&self) -> &Name {
+        match self {
+            Type::Scalar(scalar_type) => &scalar_type.name,
+            Type::Object(object_type) | Type::Interface(object_type) => &object_type.name,
+            Type::InputObject(input_object) => &input_object.name,
+            Type::Enum(enum_type) => &enum_type.name,
+            Type::Union(union_type) => &union_type.name,
         }
     }
 
-    pub fn field(&self, name: &str) -> Option<&Field> {
+    pub fn name(&self) -> &str {
+        self.interned_name()
+    }
+
+    pub fn description(&self) -> Option<&String> {
         match self {
+            Type::Scalar(scalar_type) => scalar_type.description.as_ref(),
             Type::Object(object_type) | Type::Interface(object_type) => {
-                for field in &object_type.fields {
-                    if field.name == name {
-                        return Some(field);
-                    }
-                }
-                None
+                object_type.description.as_ref()
             }
+            Type::InputObject(input_object) => input_object.description.as_ref(),
+            Type::Enum(enum_type) => enum_type.description.as_ref(),
+            Type::Union(union_type) => union_type.description.as_ref(),
+        }
+    }
+
+    // The SDL keyword defining this kind of type.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Type::Scalar(_) => "scalar",
+            Type::Object(_) => "type",
+            Type::Interface(_) => "interface",
+            Type::InputObject(_) => "input",
+            Type::Enum(_) => "enum",
+            Type::Union(_) => "union",
+        }
+    }
+
+    // Types that can be used for variables and arguments.
+    pub fn is_input_type(&self) -> bool {
+        matches!(self, Type::Scalar(_) | Type::Enum(_) | Type::InputObject(_))
+    }
+
+    // Types that have fields, and so need a selection of subfields.
+    pub fn is_composite_type(&self) -> bool {
+        matches!(self, Type::Object(_) | Type::Interface(_) | Type::Union(_))
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Type::Scalar(_) => "Scalar",
+            Type::Object(_) => "Object",
+            Type::Interface(_) => "Interface",
+            Type::InputObject(_) => "Input",
+            Type::Enum(_) => "Enum",
+            Type::Union(_) => "Union",
+        }
+    }
+
+    // Interfaces implemented by an object or interface type.
+    pub fn interface_names(&self) -> Vec<&str> {
+        match self {
+            Type::Object(object_type) | Type::Interface(object_type) => object_type
+                .interfaces
+                .iter()
+                .map(|name| name.as_ref())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // The concrete object types a value of this type can be.
+    pub fn possible_type_names(&self) -> Vec<&str> {
+        match self {
+            Type::Object(object_type) => vec![&object_type.name],
+            Type::Interface(object_type) => object_type
+                .possible_types
+                .iter()
+                .map(|name| name.as_ref())
+                .collect(),
+            Type::Union(union_type) => union_type
+                .possible_types
+                .iter()
+                .map(|name| name.as_ref())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        match self {
+            Type::Object(object_type) | Type::Interface(object_type) => &object_type.fields,
+            _ => &[],
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        match self {
+            Type::Object(object_type) | Type::Interface(object_type) => object_type
+                .field_index
+                .get(name)
+                .map(|&i| &object_type.fields[i]),
+            Type::Scalar(_) => None,
             Type::InputObject(_) => None,
             Type::Enum(_) => None,
             Type::Union(_) => None,
@@ -415,108 +733,540 @@ impl Type {
     }
 }
 
+// Where the schema is read from.
+pub enum SchemaSource {
+    // Introspection of the live endpoint, cached in the file.
+    Introspection {
+        cache_file_path: PathBuf,
+        reload: bool,
+    },
+    // An SDL file, or a folder of them, for endpoints with introspection disabled.
+    Sdl(PathBuf),
+}
+
+/**
+ * Why the schema could not be loaded.
+ */
+#[derive(Debug)]
+pub enum SchemaError {
+    // The introspection query could not be sent, eg.: the server is down.
+    Request(Error),
+    // Non-success HTTP status, with the GraphQL errors or the start of the response.
+    Status(StatusCode, String),
+    // The introspection query failed, eg.: introspection is disabled.
+    Server(Vec<String>),
+    // Not an introspection response, or one missing required parts.
+    Invalid(String),
+    Io(PathBuf, io::Error),
+    Sdl(PathBuf, String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Request(err) => write!(f, "Failed fetching the schema: {}", err),
+            SchemaError::Status(status, details) => {
+                write!(
+                    f,
+                    "Fetching the schema failed with HTTP {}: {}",
+                    status, details
+                )
+            }
+            SchemaError::Server(messages) => write!(
+                f,
+                "The server returned errors for the introspection query: {}",
+                messages.join("; ")
+            ),
+            SchemaError::Invalid(message) => {
+                write!(f, "Invalid introspection response: {}", message)
+            }
+            SchemaError::Io(path, err) => write!(f, "Failed reading {}: {}", path.display(), err),
+            SchemaError::Sdl(path, message) => {
+                write!(
+                    f,
+                    "Failed loading SDL schema {}: {}",
+                    path.display(),
+                    message
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
 pub struct Schema {
     types: Vec<Type>,
+    type_index: HashMap<Name, usize>,
+    directives: Vec<DirectiveDefinition>,
+    directive_index: HashMap<Name, usize>,
     pub query_root_name: String,
-    pub mutation_root_name: String,
+    pub mutation_root_name: Option<String>,
+    pub subscription_root_name: Option<String>,
+    // Introspection fields the schema does not list in its types.
+    meta_fields: Vec<Field>,
+}
+
+impl SchemaSource {
+    // Introspection responses for `.json` files, SDL otherwise.
+    pub fn from_path(path: PathBuf) -> Result<SchemaSource, Error> {
+        if !path.exists() {
+            return Err(format!("Schema file {} not found", path.display()).into());
+        }
+
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Ok(SchemaSource::Introspection {
+                cache_file_path: path,
+                reload: false,
+            })
+        } else {
+            Ok(SchemaSource::Sdl(path))
+        }
+    }
 }
 
 impl Schema {
-    pub fn new(net_ops: &NetOps, schema_cache_file_path: &PathBuf, reload_schema: bool) -> Schema {
-        let schema: Value = Schema::fetch_schema(&net_ops, schema_cache_file_path, reload_schema);
-
-        let query_root_name = schema.as_object().unwrap()["data"].as_object().unwrap()["__schema"]
-            .as_object()
-            .unwrap()["queryType"]
-            .as_object()
-            .unwrap()["name"]
-            .as_str()
-            .unwrap()
-            .to_string();
+    pub fn new(net_ops: &NetOps, source: &SchemaSource) -> Result<Schema, SchemaError> {
+        match source {
+            SchemaSource::Introspection {
+                cache_file_path,
+                reload,
+            } => Schema::load_introspection(net_ops, cache_file_path, *reload),
+            SchemaSource::Sdl(path) => Schema::read_sdl(path)
+                .map_err(|err| SchemaError::Sdl(path.clone(), err.to_string())),
+        }
+    }
+
+    // The cache unless reloading or missing, the live schema otherwise. When fetching fails the last good
+    // cache is used.
+    fn load_introspection(
+        net_ops: &NetOps,
+        cache_file_path: &Path,
+        reload: bool,
+    ) -> Result<Schema, SchemaError> {
+        let cached_schema = if cache_file_path.exists() {
+            match Schema::read_cache(cache_file_path) {
+                Ok(schema) if !reload => return Ok(schema),
+                Ok(schema) => Some(schema),
+                Err(err) => {
+                    warn!("Ignoring the schema cache: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        match Schema::fetch_live_schema(net_ops) {
+            Ok((schema, response_body)) => {
+                if let Some(cached_schema) = &cached_schema {
+                    Schema::log_changes(cached_schema, &schema);
+                }
+                Schema::save_cache(cache_file_path, &response_body, cached_schema.is_some());
+                Ok(schema)
+            }
+            Err(err) => match cached_schema {
+                Some(cached_schema) => {
+                    warn!(
+                        "{}, using the cached schema of {}",
+                        err,
+                        cache_file_path.display()
+                    );
+                    Ok(cached_schema)
+                }
+                None => Err(err),
+            },
+        }
+    }
 
-        let mutation_root_name = schema.as_object().unwrap()["data"].as_object().unwrap()
-            ["__schema"]
-            .as_object()
-            .unwrap()["mutationType"]
-            .as_object()
-            .unwrap()["name"]
+    fn read_cache(cache_file_path: &Path) -> Result<Schema, SchemaError> {
+        let cache = fs::read_to_string(cache_file_path)
+            .map_err(|err| SchemaError::Io(cache_file_path.to_path_buf(), err))?;
+        let response = serde_json::from_str(&cache).map_err(|err| {
+            SchemaError::Invalid(format!("{}: {}", cache_file_path.display(), err))
+        })?;
+
+        Schema::from_json_value(&response)
+    }
+
+    // The schema and the response to cache, once it is known to be a valid schema.
+    fn fetch_live_schema(net_ops: &NetOps) -> Result<(Schema, String), SchemaError> {
+        let (status, response_body) = net_ops.fetch_live_schema().map_err(SchemaError::Request)?;
+        let response = serde_json::from_str::<Value>(&response_body);
+
+        if !status.is_success() {
+            let server_errors = response.as_ref().map(server_errors).unwrap_or_default();
+            let details = if server_errors.is_empty() {
+                response_excerpt(&response_body)
+            } else {
+                server_errors.join("; ")
+            };
+            return Err(SchemaError::Status(status, details));
+        }
+        let response = response.map_err(|err| {
+            SchemaError::Invalid(format!("{} in {}", err, response_excerpt(&response_body)))
+        })?;
+
+        Ok((Schema::from_json_value(&response)?, response_body))
+    }
+
+    // Copies the cache aside before it is overwritten by the reload, for `compare-schemas`.
+    fn save_cache(cache_file_path: &Path, response_body: &str, keep_previous: bool) {
+        if keep_previous {
+            let mut previous_cache_file_path = cache_file_path.as_os_str().to_owned();
+            previous_cache_file_path.push(".previous");
+
+            if let Err(err) = fs::copy(cache_file_path, &previous_cache_file_path) {
+                warn!("Failed keeping the previous schema cache: {}", err);
+            }
+        }
+
+        if let Err(err) = fs::write(cache_file_path, response_body) {
+            warn!(
+                "Failed saving the schema cache {}: {}",
+                cache_file_path.display(),
+                err
+            );
+        }
+    }
+
+    // Reads the `.graphql` / `.graphqls` files of a folder (recursively) as one schema.
+    fn read_sdl(path: &Path) -> Result<Schema, Error> {
+        let mut document = SdlDocument::default();
+        for file_path in Schema::sdl_file_paths(path)? {
+            let source = fs::read_to_string(&file_path)?;
+            document
+                .add_source(&source)
+                .map_err(|err| format!("{}: {}", file_path.display(), err))?;
+        }
+
+        Ok(Schema::from_json_value(&document.into_introspection()?)?)
+    }
+
+    fn sdl_file_paths(path: &Path) -> Result<Vec<PathBuf>, Error> {
+        if !path.is_dir() {
+            return Ok(vec![path.to_path_buf()]);
+        }
+
+        let mut file_paths = vec![];
+        for dir_entry in fs::read_dir(path)? {
+            let path = dir_entry?.path();
+
+            if path.is_dir() {
+                file_paths.extend(Schema::sdl_file_paths(&path)?);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "graphql" || ext == "graphqls")
+            {
+                file_paths.push(path);
+            }
+        }
+        // Keeps the type order stable.
+        file_paths.sort();
+
+        Ok(file_paths)
+    }
+
+    // Builds the schema from an introspection query response.
+    pub fn from_json_value(response: &Value) -> Result<Schema, SchemaError> {
+        let server_errors = server_errors(response);
+        let schema = &response["data"]["__schema"];
+        if !schema.is_object() {
+            return Err(if server_errors.is_empty() {
+                SchemaError::Invalid("Missing `data.__schema`".to_string())
+            } else {
+                SchemaError::Server(server_errors)
+            });
+        }
+        // Partial results, the schema is still usable.
+        for server_error in server_errors {
+            warn!("Introspection query error: {}", server_error);
+        }
+
+        let query_root_name = schema["queryType"]["name"]
             .as_str()
-            .unwrap()
+            .ok_or(SchemaError::Invalid(
+                "The schema has no query root type".to_string(),
+            ))?
             .to_string();
+        // Servers without mutations or subscriptions have null for these.
+        let mutation_root_name = schema["mutationType"]["name"]
+            .as_str()
+            .map(|name| name.to_string());
+        let subscription_root_name = schema["subscriptionType"]["name"]
+            .as_str()
+            .map(|name| name.to_string());
+
+        let mut interner = Interner::default();
+        let types = Schema::read_types(schema, &mut interner)?;
+        let directives = Schema::read_directives(schema, &mut interner)?;
 
-        Schema {
-            types: Schema::read_types(&schema),
+        Ok(Schema {
+            type_index: index_by_name(&types, |ty| ty.interned_name()),
+            types,
+            directive_index: index_by_name(&directives, |directive| &directive.name),
+            directives,
             query_root_name,
             mutation_root_name,
-        }
+            subscription_root_name,
+            meta_fields: Schema::meta_fields(&mut interner)?,
+        })
     }
 
-    fn fetch_schema(
-        net_ops: &NetOps,
-        schema_cache_file_path: &PathBuf,
-        reload_schema: bool,
-    ) -> Value {
-        if reload_schema
-            || !fs::exists(schema_cache_file_path).expect("Failed checking schema cache file")
-        {
-            let response_body = net_ops.fetch_live_schema().unwrap();
-
-            let mut cache =
-                File::create(schema_cache_file_path).expect("Failed creating schema cache");
-            cache
-                .write_all(response_body.as_bytes())
-                .expect("Failed saving schema");
-
-            serde_json::from_str(&response_body).unwrap()
-        } else {
-            let cache = File::open(schema_cache_file_path).expect("Failed opening schema cache");
-            serde_json::from_reader(cache).unwrap()
+    fn meta_fields(interner: &mut Interner) -> Result<Vec<Field>, SchemaError> {
+        let named = |kind: &str, name: &str| json!({ "kind": kind, "name": name });
+        let non_null = |of_type: Value| json!({ "kind": "NON_NULL", "ofType": of_type });
+
+        [
+            json!({
+                "name": TYPENAME_FIELD,
+                "type": non_null(named("SCALAR", "String")),
+                "args": [],
+            }),
+            json!({
+                "name": "__schema",
+                "type": non_null(named("OBJECT", "__Schema")),
+                "args": [],
+            }),
+            json!({
+                "name": "__type",
+                "type": named("OBJECT", "__Type"),
+                "args": [{ "name": "name", "type": non_null(named("SCALAR", "String")) }],
+            }),
+        ]
+        .iter()
+        .map(|field_def| Field::from_json_value(field_def, interner))
+        .collect()
+    }
+
+    // `__typename` on any composite type, `__schema` and `__type` only on the query root.
+    fn meta_fields_of<'s>(&'s self, scope: &Type) -> impl Iterator<Item = &'s Field> {
+        let is_composite = scope.is_composite_type();
+        let is_query_root = scope.name() == self.query_root_name;
+
+        self.meta_fields
+            .iter()
+            .filter(move |field| is_composite && (&*field.name == TYPENAME_FIELD || is_query_root))
+    }
+
+    // Definition of a field selected within `scope`, including the meta fields.
+    pub fn field_definition<'s>(&'s self, scope: &'s Type, name: &str) -> Option<&'s Field> {
+        scope.field(name).or_else(|| {
+            self.meta_fields_of(scope)
+                .find(|field| &*field.name == name)
+        })
+    }
+
+    // Selectable fields matching the prefix, except the ones already present.
+    pub fn field_names(&self, scope: &Type, prefix: &str, present: &[&str]) -> Vec<SuggestionElem> {
+        let mut elems = match scope {
+            Type::Object(object_type) | Type::Interface(object_type) => {
+                object_type.field_names(prefix, present)
+            }
+            _ => vec![],
+        };
+        elems.extend(
+            self.meta_fields_of(scope)
+                .filter(|field| !present.contains(&field.name.as_ref()))
+                .filter_map(|field| field.suggestion(prefix)),
+        );
+        for elem in &mut elems {
+            elem.snippet = self
+                .field_definition(scope, &elem.name)
+                .and_then(|field| self.field_snippet(field));
         }
+
+        rank_suggestion_elems(elems)
     }
 
-    pub fn type_definition(&self, name: &String) -> Option<&Type> {
-        for ty in &self.types {
-            match ty {
-                Type::Object(object_type) | Type::Interface(object_type) => {
-                    if &object_type.name == name {
-                        return Some(ty);
-                    }
-                }
-                Type::InputObject(input_object) => {
-                    if &input_object.name == name {
-                        return Some(ty);
-                    }
+    // Eg.: `user(id: |) {  }`, None when the bare name is all there is to insert.
+    pub fn field_snippet(&self, field: &Field) -> Option<Snippet> {
+        let mut snippet = Snippet::new(&field.name);
+
+        let required_args = field.args.required_args().collect::<Vec<_>>();
+        if !required_args.is_empty() {
+            snippet.push("(");
+            for (i, arg) in required_args.into_iter().enumerate() {
+                if i > 0 {
+                    snippet.push(", ");
                 }
-                Type::Enum(enum_type) => {
-                    if &enum_type.name == name {
-                        return Some(ty);
-                    }
+                snippet.push(&format!("{}: ", arg.name));
+                self.value_skeleton(&arg.arg_type, &mut snippet, 0);
+            }
+            snippet.push(")");
+        }
+        if field.field_type.is_composite() {
+            snippet.push(" { ");
+            snippet.placeholder();
+            snippet.push(" }");
+        }
+
+        (*snippet.text != *field.name).then_some(snippet)
+    }
+
+    // Eg.: `input: { name: | }` for arguments and input object fields.
+    pub fn arg_snippet(&self, arg: &Arg) -> Snippet {
+        let mut snippet = Snippet::new(&format!("{}: ", arg.name));
+        self.value_skeleton(&arg.arg_type, &mut snippet, 0);
+
+        snippet
+    }
+
+    // Input objects list their required fields, other values are left for the user to fill.
+    fn value_skeleton(&self, type_class: &TypeClass, snippet: &mut Snippet, depth: usize) {
+        match type_class {
+            TypeClass::NonNull(inner) => self.value_skeleton(inner, snippet, depth),
+            TypeClass::List(inner) => {
+                snippet.push("[");
+                self.value_skeleton(inner, snippet, depth);
+                snippet.push("]");
+            }
+            TypeClass::Input(input_name) if depth < MAX_SNIPPET_INPUT_DEPTH => {
+                let Some(Type::InputObject(input_type)) = self.type_definition(input_name) else {
+                    snippet.placeholder();
+                    return;
+                };
+
+                snippet.push("{ ");
+                let required_fields = input_type.args.required_args().collect::<Vec<_>>();
+                if required_fields.is_empty() {
+                    snippet.placeholder();
                 }
-                Type::Union(union_type) => {
-                    if &union_type.name == name {
-                        return Some(ty);
+                for (i, input_field) in required_fields.into_iter().enumerate() {
+                    if i > 0 {
+                        snippet.push(", ");
                     }
+                    snippet.push(&format!("{}: ", input_field.name));
+                    self.value_skeleton(&input_field.arg_type, snippet, depth + 1);
                 }
+                snippet.push(" }");
             }
+            _ => snippet.placeholder(),
         }
+    }
 
-        None
+    fn log_changes(previous_schema: &Schema, schema: &Schema) {
+        let changes = schema_diff::diff(previous_schema, schema);
+        if changes.is_empty() {
+            info!("Schema did not change since the last load");
+            return;
+        }
+
+        warn!("Schema changed since the last load:");
+        for change in changes {
+            match change.criticality {
+                schema_diff::Criticality::Safe => info!("{}", change),
+                _ => warn!("{}", change),
+            }
+        }
+    }
+
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    pub fn type_definition(&self, name: &str) -> Option<&Type> {
+        self.type_index.get(name).map(|&i| &self.types[i])
+    }
+
+    pub fn directives(&self) -> &[DirectiveDefinition] {
+        &self.directives
     }
 
-    fn read_types(schema: &Value) -> Vec<Type> {
-        schema.as_object().unwrap()["data"].as_object().unwrap()["__schema"]
-            .as_object()
-            .unwrap()["types"]
-            .as_array()
-            .unwrap()
+    pub fn directive(&self, name: &str) -> Option<&DirectiveDefinition> {
+        self.directive_index.get(name).map(|&i| &self.directives[i])
+    }
+
+    // Directives applicable on the given location (eg.: `FIELD`).
+    pub fn directive_names(&self, prefix: &str, location: &str) -> Vec<SuggestionElem> {
+        let elems = self
+            .directives
             .iter()
-            .filter_map(|type_def| Type::from_json_value(type_def))
-            .collect()
+            .filter(|directive| {
+                directive
+                    .locations
+                    .iter()
+                    .any(|directive_location| directive_location == location)
+            })
+            .filter_map(|directive| {
+                SuggestionElem::matching(&directive.name, "Directive".to_string(), prefix, false)
+            })
+            .collect();
+
+        rank_suggestion_elems(elems)
+    }
+
+    pub fn input_type_names(&self, prefix: &str) -> Vec<SuggestionElem> {
+        self.type_names(prefix, |ty| ty.is_input_type())
+    }
+
+    // Types that can be used as fragment type conditions.
+    pub fn composite_type_names(&self, prefix: &str) -> Vec<SuggestionElem> {
+        self.type_names(prefix, |ty| ty.is_composite_type())
+    }
+
+    fn type_names(&self, prefix: &str, predicate: impl Fn(&Type) -> bool) -> Vec<SuggestionElem> {
+        let elems = self
+            .types
+            .iter()
+            .filter(|ty| predicate(ty))
+            .filter_map(|ty| {
+                SuggestionElem::matching(ty.name(), ty.kind_name().to_string(), prefix, false)
+            })
+            .collect();
+
+        rank_suggestion_elems(elems)
+    }
+
+    // Type conditions that can be used for inline fragments within `scope`.
+    pub fn possible_type_condition_names(&self, scope: &Type, prefix: &str) -> Vec<SuggestionElem> {
+        self.type_names(prefix, |ty| {
+            ty.is_composite_type() && self.type_condition_applies(scope, ty.name())
+        })
+    }
+
+    // Whether a fragment on `type_condition` can be spread within `scope` (aka their possible types overlap).
+    pub fn type_condition_applies(&self, scope: &Type, type_condition: &str) -> bool {
+        let Some(condition_type) = self.type_definition(type_condition) else {
+            return false;
+        };
+
+        let scope_possible_types = scope.possible_type_names();
+        condition_type
+            .possible_type_names()
+            .iter()
+            .any(|name| scope_possible_types.contains(name))
+    }
+
+    fn read_types(schema: &Value, interner: &mut Interner) -> Result<Vec<Type>, SchemaError> {
+        let mut types = vec![];
+        for type_def in json_array(schema, "types")? {
+            types.extend(Type::from_json_value(type_def, interner)?);
+        }
+
+        Ok(types)
     }
 
-    pub fn field_type(&self, type_definition: &Type, field_name: &str) -> Result<&Type, Error> {
-        type_definition
-            .field(&field_name)
+    fn read_directives(
+        schema: &Value,
+        interner: &mut Interner,
+    ) -> Result<Vec<DirectiveDefinition>, SchemaError> {
+        // Older schema caches might not have the directives.
+        match schema["directives"].as_array() {
+            Some(directives) => directives
+                .iter()
+                .map(|directive| DirectiveDefinition::from_json_value(directive, interner))
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn field_type<'s>(
+        &'s self,
+        type_definition: &'s Type,
+        field_name: &str,
+    ) -> Result<&'s Type, Error> {
+        self.field_definition(type_definition, field_name)
             .ok_or(format!("Field {} not found", field_name).into())
             .and_then(|field_definition| {
                 field_definition
@@ -537,3 +1287,382 @@ impl Schema {
             })
     }
 }
+
+// A small schema for the analyzer and validator tests.
+#[cfg(test)]
+pub mod fixture {
+    use serde_json::{json, Value};
+
+    use super::Schema;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({ "kind": kind, "name": name, "ofType": null })
+    }
+
+    fn non_null(of_type: Value) -> Value {
+        json!({ "kind": "NON_NULL", "name": null, "ofType": of_type })
+    }
+
+    fn list(of_type: Value) -> Value {
+        json!({ "kind": "LIST", "name": null, "ofType": of_type })
+    }
+
+    fn scalar(name: &str) -> Value {
+        named("SCALAR", name)
+    }
+
+    fn arg(name: &str, arg_type: Value) -> Value {
+        json!({ "name": name, "type": arg_type })
+    }
+
+    fn field(name: &str, field_type: Value, args: Vec<Value>) -> Value {
+        json!({ "name": name, "type": field_type, "args": args })
+    }
+
+    fn described(mut node: Value, description: &str) -> Value {
+        node["description"] = json!(description);
+        node
+    }
+
+    fn implementing(mut node: Value, interfaces: Vec<&str>) -> Value {
+        node["interfaces"] = interfaces
+            .into_iter()
+            .map(|name| named("INTERFACE", name))
+            .collect();
+        node
+    }
+
+    fn deprecated(mut node: Value, reason: &str) -> Value {
+        node["isDeprecated"] = json!(true);
+        node["deprecationReason"] = json!(reason);
+        node
+    }
+
+    fn object(kind: &str, name: &str, fields: Vec<Value>, possible_types: Vec<&str>) -> Value {
+        let possible_types = possible_types
+            .into_iter()
+            .map(|name| named("OBJECT", name))
+            .collect::<Vec<_>>();
+        json!({ "kind": kind, "name": name, "fields": fields, "possibleTypes": possible_types })
+    }
+
+    pub fn schema() -> Schema {
+        Schema::from_json_value(&introspection()).unwrap()
+    }
+
+    // The introspection query response of `schema`.
+    pub fn introspection() -> Value {
+        let types = vec![
+            object(
+                "OBJECT",
+                "Query",
+                vec![
+                    field(
+                        "user",
+                        named("OBJECT", "User"),
+                        vec![arg("id", non_null(scalar("ID")))],
+                    ),
+                    field(
+                        "users",
+                        non_null(list(non_null(named("OBJECT", "User")))),
+                        vec![
+                            arg("first", scalar("Int")),
+                            deprecated(arg("offset", scalar("Int")), "Use cursors."),
+                            arg("filter", named("INPUT_OBJECT", "UserFilter")),
+                        ],
+                    ),
+                    field(
+                        "node",
+                        named("INTERFACE", "Node"),
+                        vec![arg("id", non_null(scalar("ID")))],
+                    ),
+                    field(
+                        "search",
+                        list(named("UNION", "SearchResult")),
+                        vec![arg("term", non_null(scalar("String")))],
+                    ),
+                    field("version", non_null(scalar("String")), vec![]),
+                ],
+                vec![],
+            ),
+            object(
+                "OBJECT",
+                "Mutation",
+                vec![field(
+                    "createUser",
+                    named("OBJECT", "User"),
+                    vec![arg(
+                        "input",
+                        non_null(named("INPUT_OBJECT", "CreateUserInput")),
+                    )],
+                )],
+                vec![],
+            ),
+            described(
+                implementing(
+                    object(
+                        "OBJECT",
+                        "User",
+                        vec![
+                            field("id", non_null(scalar("ID")), vec![]),
+                            field("name", scalar("String"), vec![]),
+                            field("role", named("ENUM", "Role"), vec![]),
+                            deprecated(field("username", scalar("String"), vec![]), "Use name."),
+                            described(
+                                field(
+                                    "posts",
+                                    list(named("OBJECT", "Post")),
+                                    vec![
+                                        json!({ "name": "first", "type": scalar("Int"), "defaultValue": "10" }),
+                                    ],
+                                ),
+                                "Most recent first.",
+                            ),
+                        ],
+                        vec![],
+                    ),
+                    vec!["Node"],
+                ),
+                "A registered user.",
+            ),
+            implementing(
+                object(
+                    "OBJECT",
+                    "Post",
+                    vec![
+                        field("id", non_null(scalar("ID")), vec![]),
+                        field("title", non_null(scalar("String")), vec![]),
+                        field("author", named("OBJECT", "User"), vec![]),
+                    ],
+                    vec![],
+                ),
+                vec!["Node"],
+            ),
+            object(
+                "INTERFACE",
+                "Node",
+                vec![field("id", non_null(scalar("ID")), vec![])],
+                vec!["User", "Post"],
+            ),
+            json!({
+                "kind": "UNION",
+                "name": "SearchResult",
+                "possibleTypes": [named("OBJECT", "User"), named("OBJECT", "Post")],
+            }),
+            json!({
+                "kind": "ENUM",
+                "name": "Role",
+                "enumValues": [
+                    { "name": "ADMIN" },
+                    { "name": "MEMBER" },
+                    deprecated(json!({ "name": "GUEST" }), "Sign up instead."),
+                ],
+            }),
+            json!({
+                "kind": "INPUT_OBJECT",
+                "name": "UserFilter",
+                "inputFields": [
+                    arg("name", scalar("String")),
+                    arg("role", named("ENUM", "Role")),
+                ],
+            }),
+            json!({
+                "kind": "INPUT_OBJECT",
+                "name": "CreateUserInput",
+                "inputFields": [
+                    arg("name", non_null(scalar("String"))),
+                    arg("role", named("ENUM", "Role")),
+                    arg("tags", list(non_null(scalar("String")))),
+                ],
+            }),
+            object(
+                "OBJECT",
+                "__Schema",
+                vec![field(
+                    "queryType",
+                    non_null(named("OBJECT", "__Type")),
+                    vec![],
+                )],
+                vec![],
+            ),
+            object(
+                "OBJECT",
+                "__Type",
+                vec![field("name", scalar("String"), vec![])],
+                vec![],
+            ),
+            scalar("ID"),
+            scalar("String"),
+            scalar("Int"),
+            scalar("Float"),
+            scalar("Boolean"),
+        ];
+
+        let condition_directive = |name: &str| {
+            json!({
+                "name": name,
+                "locations": ["FIELD", "FRAGMENT_SPREAD", "INLINE_FRAGMENT"],
+                "args": [arg("if", non_null(scalar("Boolean")))],
+            })
+        };
+
+        json!({
+            "data": {
+                "__schema": {
+                    "queryType": { "name": "Query" },
+                    "mutationType": { "name": "Mutation" },
+                    "subscriptionType": null,
+                    "types": types,
+                    "directives": [condition_directive("include"), condition_directive("skip")],
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        env, fs,
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use serde_json::{json, Value};
+
+    use super::{fixture, Schema, SchemaError};
+    use crate::{config::Config, net_ops::NetOps};
+
+    fn schema_error(response: Value) -> String {
+        match Schema::from_json_value(&response) {
+            Ok(_) => panic!("The schema is valid"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_from_json_value_without_mutations() {
+        let schema = Schema::from_json_value(&json!({
+            "data": {
+                "__schema": {
+                    "queryType": { "name": "Query" },
+                    "mutationType": null,
+                    "types": [
+                        {
+                            "kind": "OBJECT",
+                            "name": "Query",
+                            "fields": [
+                                { "name": "ok", "args": [], "type": { "kind": "SCALAR", "name": "Boolean" } },
+                            ],
+                        },
+                        { "kind": "SCALAR", "name": "Boolean" },
+                    ],
+                }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(None, schema.mutation_root_name);
+        assert_eq!(None, schema.subscription_root_name);
+        assert!(schema.directives().is_empty());
+    }
+
+    #[test]
+    fn test_from_json_value_errors() {
+        assert_eq!(
+            "The server returned errors for the introspection query: Introspection is disabled",
+            schema_error(json!({ "errors": [{ "message": "Introspection is disabled" }] }))
+        );
+        assert_eq!(
+            "Invalid introspection response: Missing `data.__schema`",
+            schema_error(json!({ "data": null }))
+        );
+
+        let mut response = fixture::introspection();
+        response["data"]["__schema"]["types"][0]["fields"][0]["type"] =
+            json!({ "kind": "MYSTERY", "name": "User" });
+        assert_eq!(
+            "Invalid introspection response: Unknown kind MYSTERY of type User",
+            schema_error(response)
+        );
+
+        let mut response = fixture::introspection();
+        response["data"]["__schema"]["types"][0]["fields"][0]["args"] = Value::Null;
+        assert_eq!(
+            "Invalid introspection response: Missing `args` of user",
+            schema_error(response)
+        );
+    }
+
+    #[test]
+    fn test_load_falls_back_to_cache() {
+        let (net_ops, server) = serve_unauthorized(2);
+        let cache_file_path =
+            env::temp_dir().join(format!("gomqlet-schema-test-{}.json", std::process::id()));
+
+        match Schema::load_introspection(&net_ops, &cache_file_path, true) {
+            Err(SchemaError::Status(status, details)) => {
+                assert_eq!(401, status.as_u16());
+                assert_eq!("Invalid token", details);
+            }
+            _ => panic!("Expected an HTTP error"),
+        }
+
+        fs::write(&cache_file_path, fixture::introspection().to_string()).unwrap();
+        let schema = Schema::load_introspection(&net_ops, &cache_file_path, true);
+        let cache = fs::read_to_string(&cache_file_path).unwrap();
+        fs::remove_file(&cache_file_path).unwrap();
+        server.join().unwrap();
+
+        assert!(schema.unwrap().type_definition("User").is_some());
+        // The failed response does not replace the last good cache.
+        assert_eq!(fixture::introspection().to_string(), cache);
+    }
+
+    // Answers the requests with a 401 and a GraphQL error, like a server refusing a token.
+    fn serve_unauthorized(request_count: usize) -> (NetOps, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let net_ops = NetOps::new(&Config {
+            url: format!("http://{}/graphql", listener.local_addr().unwrap()),
+            subscription_url: None,
+            headers: vec![],
+            schema_cache: String::new(),
+            schema_file: None,
+            variables: None,
+            complexity: Default::default(),
+        });
+
+        let server = thread::spawn(move || {
+            for _ in 0..request_count {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                // The whole request is read, so the response is not cut by a reset.
+                let mut reader = BufReader::new(&stream);
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+                reader.read_exact(&mut vec![0; content_length]).unwrap();
+
+                let body = json!({ "errors": [{ "message": "Invalid token" }] }).to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (net_ops, server)
+    }
+}
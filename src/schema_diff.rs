@@ -0,0 +1,416 @@
+use std::fmt::Display;
+
+use crate::schema::{ArgList, Schema, Type, TypeClass};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Criticality {
+    // Existing operations can fail.
+    Breaking,
+    // Existing operations keep working, but clients might not expect the new values.
+    Dangerous,
+    Safe,
+}
+
+impl Display for Criticality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Criticality::Breaking => write!(f, "breaking"),
+            Criticality::Dangerous => write!(f, "dangerous"),
+            Criticality::Safe => write!(f, "safe"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SchemaChange {
+    pub criticality: Criticality,
+    pub message: String,
+}
+
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.criticality, self.message)
+    }
+}
+
+/**
+ * Structural changes from the `old` schema to the `new` one, the breaking ones first.
+ */
+pub fn diff(old: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let mut diff = SchemaDiff { changes: vec![] };
+
+    for old_type in user_types(old) {
+        match new.type_definition(old_type.name()) {
+            None => diff.push(
+                Criticality::Breaking,
+                format!("Type {} was removed", old_type.name()),
+            ),
+            Some(new_type) if new_type.keyword() != old_type.keyword() => diff.push(
+                Criticality::Breaking,
+                format!(
+                    "Type {} changed from {} to {}",
+                    old_type.name(),
+                    old_type.keyword(),
+                    new_type.keyword()
+                ),
+            ),
+            Some(new_type) => diff.diff_type(old_type, new_type),
+        }
+    }
+    for new_type in user_types(new) {
+        if old.type_definition(new_type.name()).is_none() {
+            diff.push(
+                Criticality::Safe,
+                format!("Type {} was added", new_type.name()),
+            );
+        }
+    }
+
+    for old_directive in old.directives() {
+        match new.directive(&old_directive.name) {
+            None => diff.push(
+                Criticality::Breaking,
+                format!("Directive @{} was removed", old_directive.name),
+            ),
+            Some(new_directive) => diff.diff_args(
+                |name| format!("Argument {} of directive @{}", name, old_directive.name),
+                &old_directive.args,
+                &new_directive.args,
+            ),
+        }
+    }
+    for new_directive in new.directives() {
+        if old.directive(&new_directive.name).is_none() {
+            diff.push(
+                Criticality::Safe,
+                format!("Directive @{} was added", new_directive.name),
+            );
+        }
+    }
+
+    diff.changes.sort_by_key(|change| change.criticality);
+    diff.changes
+}
+
+// Introspection types are the same in every schema, but not listed in SDL.
+fn user_types(schema: &Schema) -> impl Iterator<Item = &Type> {
+    schema
+        .types()
+        .iter()
+        .filter(|ty| !ty.name().starts_with("__"))
+}
+
+struct SchemaDiff {
+    changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    fn push(&mut self, criticality: Criticality, message: String) {
+        self.changes.push(SchemaChange {
+            criticality,
+            message,
+        });
+    }
+
+    // Types of the same kind.
+    fn diff_type(&mut self, old_type: &Type, new_type: &Type) {
+        let type_name = old_type.name();
+
+        match (old_type, new_type) {
+            (Type::Object(_) | Type::Interface(_), _) => {
+                for old_field in old_type.fields() {
+                    let subject = format!("Field {}.{}", type_name, old_field.name);
+                    let Some(new_field) = new_type.field(&old_field.name) else {
+                        self.push(Criticality::Breaking, format!("{} was removed", subject));
+                        continue;
+                    };
+
+                    if old_field.field_type.signature() != new_field.field_type.signature() {
+                        self.push(
+                            if is_safe_output_change(&old_field.field_type, &new_field.field_type) {
+                                Criticality::Safe
+                            } else {
+                                Criticality::Breaking
+                            },
+                            format!(
+                                "{} changed type from {} to {}",
+                                subject,
+                                old_field.field_type.signature(),
+                                new_field.field_type.signature()
+                            ),
+                        );
+                    }
+                    self.diff_args(
+                        |name| {
+                            format!(
+                                "Argument {} of field {}.{}",
+                                name, type_name, old_field.name
+                            )
+                        },
+                        &old_field.args,
+                        &new_field.args,
+                    );
+                }
+                for new_field in new_type.fields() {
+                    if old_type.field(&new_field.name).is_none() {
+                        self.push(
+                            Criticality::Safe,
+                            format!("Field {}.{} was added", type_name, new_field.name),
+                        );
+                    }
+                }
+
+                if let Type::Interface(_) = old_type {
+                    self.diff_possible_types(old_type, new_type);
+                }
+            }
+            (Type::Union(_), _) => self.diff_possible_types(old_type, new_type),
+            (Type::Enum(old_enum), Type::Enum(new_enum)) => {
+                for old_value in old_enum.values() {
+                    if new_enum.value(&old_value.name).is_none() {
+                        self.push(
+                            Criticality::Breaking,
+                            format!("Enum value {}.{} was removed", type_name, old_value.name),
+                        );
+                    }
+                }
+                for new_value in new_enum.values() {
+                    if old_enum.value(&new_value.name).is_none() {
+                        self.push(
+                            Criticality::Dangerous,
+                            format!("Enum value {}.{} was added", type_name, new_value.name),
+                        );
+                    }
+                }
+            }
+            (Type::InputObject(old_input), Type::InputObject(new_input)) => self.diff_args(
+                |name| format!("Input field {}.{}", type_name, name),
+                &old_input.args,
+                &new_input.args,
+            ),
+            _ => {}
+        }
+    }
+
+    // Implementations of interfaces, members of unions.
+    fn diff_possible_types(&mut self, old_type: &Type, new_type: &Type) {
+        let old_names = old_type.possible_type_names();
+        let new_names = new_type.possible_type_names();
+        let is_union = matches!(old_type, Type::Union(_));
+
+        for old_name in &old_names {
+            if !new_names.contains(old_name) {
+                self.push(
+                    Criticality::Breaking,
+                    if is_union {
+                        format!(
+                            "Type {} was removed from union {}",
+                            old_name,
+                            old_type.name()
+                        )
+                    } else {
+                        format!(
+                            "Type {} no longer implements interface {}",
+                            old_name,
+                            old_type.name()
+                        )
+                    },
+                );
+            }
+        }
+        for new_name in &new_names {
+            if !old_names.contains(new_name) {
+                self.push(
+                    Criticality::Dangerous,
+                    if is_union {
+                        format!("Type {} was added to union {}", new_name, old_type.name())
+                    } else {
+                        format!(
+                            "Type {} now implements interface {}",
+                            new_name,
+                            old_type.name()
+                        )
+                    },
+                );
+            }
+        }
+    }
+
+    // Arguments, and input object fields. `subject` names the argument in the messages.
+    fn diff_args(
+        &mut self,
+        subject: impl Fn(&str) -> String,
+        old_args: &ArgList,
+        new_args: &ArgList,
+    ) {
+        for old_arg in old_args.args() {
+            let Some(new_arg) = new_args.arg(&old_arg.name) else {
+                self.push(
+                    Criticality::Breaking,
+                    format!("{} was removed", subject(&old_arg.name)),
+                );
+                continue;
+            };
+
+            if old_arg.arg_type.signature() != new_arg.arg_type.signature() {
+                self.push(
+                    if is_safe_input_change(&old_arg.arg_type, &new_arg.arg_type) {
+                        Criticality::Safe
+                    } else {
+                        Criticality::Breaking
+                    },
+                    format!(
+                        "{} changed type from {} to {}",
+                        subject(&old_arg.name),
+                        old_arg.arg_type.signature(),
+                        new_arg.arg_type.signature()
+                    ),
+                );
+            }
+            // Operations leaving out the argument get a different value.
+            if old_arg.default_value != new_arg.default_value {
+                self.push(
+                    Criticality::Dangerous,
+                    format!(
+                        "{} changed default value from {} to {}",
+                        subject(&old_arg.name),
+                        old_arg.default_value.as_deref().unwrap_or("none"),
+                        new_arg.default_value.as_deref().unwrap_or("none")
+                    ),
+                );
+            }
+        }
+
+        for new_arg in new_args.args() {
+            if old_args.arg(&new_arg.name).is_some() {
+                continue;
+            }
+
+            if new_arg.arg_type.is_non_null() && new_arg.default_value.is_none() {
+                self.push(
+                    Criticality::Breaking,
+                    format!("{} was added and is required", subject(&new_arg.name)),
+                );
+            } else {
+                self.push(
+                    Criticality::Safe,
+                    format!("{} was added", subject(&new_arg.name)),
+                );
+            }
+        }
+    }
+}
+
+// Whether clients expecting the `old` type can read the `new` one: only nullable types turning
+// non-null are fine.
+fn is_safe_output_change(old: &TypeClass, new: &TypeClass) -> bool {
+    match (old, new) {
+        (TypeClass::NonNull(old), TypeClass::NonNull(new))
+        | (TypeClass::List(old), TypeClass::List(new)) => is_safe_output_change(old, new),
+        (old, TypeClass::NonNull(new)) => is_safe_output_change(old, new),
+        (TypeClass::NonNull(_) | TypeClass::List(_), _) | (_, TypeClass::List(_)) => false,
+        (old, new) => old.underlying_type_name() == new.underlying_type_name(),
+    }
+}
+
+// Whether values valid for the `old` type are valid for the `new` one: only non-null types turning
+// nullable are fine.
+fn is_safe_input_change(old: &TypeClass, new: &TypeClass) -> bool {
+    match (old, new) {
+        (TypeClass::NonNull(old), TypeClass::NonNull(new))
+        | (TypeClass::List(old), TypeClass::List(new)) => is_safe_input_change(old, new),
+        (TypeClass::NonNull(old), new) => is_safe_input_change(old, new),
+        (_, TypeClass::NonNull(_) | TypeClass::List(_)) | (TypeClass::List(_), _) => false,
+        (old, new) => old.underlying_type_name() == new.underlying_type_name(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{schema::Schema, sdl::SdlDocument};
+
+    use super::diff;
+
+    fn schema(sdl: &str) -> Schema {
+        let mut document = SdlDocument::default();
+        document.add_source(sdl).unwrap();
+        Schema::from_json_value(&document.into_introspection().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = schema(
+            r#"
+            type Query { user(id: ID!): User, users(first: Int = 10): [User], search: [SearchResult] }
+            type User implements Node { id: ID!, name: String, email: String }
+            interface Node { id: ID! }
+            type Post implements Node { id: ID! }
+            union SearchResult = User | Post
+            enum Role { ADMIN MEMBER }
+            input UserFilter { role: Role! }
+            type Legacy { id: ID }
+            "#,
+        );
+        let new = schema(
+            r#"
+            type Query { user(id: ID!, active: Boolean!): User!, users(first: Int = 20, after: String): [User], search: [SearchResult] }
+            type User implements Node { id: ID!, name: Int, age: Int }
+            interface Node { id: ID! }
+            type Post { id: ID! }
+            type Comment implements Node { id: ID! }
+            union SearchResult = User | Post | Comment
+            enum Role { ADMIN MEMBER GUEST }
+            input UserFilter { role: Role }
+            "#,
+        );
+
+        assert_eq!(
+            vec![
+                "[breaking] Argument active of field Query.user was added and is required",
+                "[breaking] Field User.name changed type from String to Int",
+                "[breaking] Field User.email was removed",
+                "[breaking] Type Post no longer implements interface Node",
+                "[breaking] Type Legacy was removed",
+                "[dangerous] Argument first of field Query.users changed default value from 10 to 20",
+                "[dangerous] Type Comment now implements interface Node",
+                "[dangerous] Type Comment was added to union SearchResult",
+                "[dangerous] Enum value Role.GUEST was added",
+                "[safe] Field Query.user changed type from User to User!",
+                "[safe] Argument after of field Query.users was added",
+                "[safe] Field User.age was added",
+                "[safe] Input field UserFilter.role changed type from Role! to Role",
+                "[safe] Type Comment was added",
+            ],
+            diff(&old, &new)
+                .iter()
+                .map(|change| change.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_list_and_nullability_changes() {
+        let old = schema("type Query { a: [Int] b: [Int!] c(x: [Int!]): Int d(y: Int): Int }");
+        let new = schema("type Query { a: [Int!]! b: [Int] c(x: [Int]): Int d(y: [Int]): Int }");
+
+        assert_eq!(
+            vec![
+                "[breaking] Field Query.b changed type from [Int!] to [Int]",
+                "[breaking] Argument y of field Query.d changed type from Int to [Int]",
+                "[safe] Field Query.a changed type from [Int] to [Int!]!",
+                "[safe] Argument x of field Query.c changed type from [Int!] to [Int]",
+            ],
+            diff(&old, &new)
+                .iter()
+                .map(|change| change.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let sdl = "type Query { user(id: ID!): User } type User { id: ID! }";
+
+        assert!(diff(&schema(sdl), &schema(sdl)).is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+use std::{cmp::Reverse, mem};
+
+use crate::{
+    schema::{Field, Schema, Type},
+    schema_explorer_printer::SchemaExplorerPrinter,
+    stdin_reader::KeyboardInput,
+    util::fuzzy_match_scored,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Types,
+    Type(String),
+    Field {
+        type_name: String,
+        field_name: String,
+    },
+}
+
+pub enum Command {
+    Close,
+}
+
+pub struct Entry {
+    pub label: String,
+    // Printed after the label, eg.: the arguments and type of a field.
+    pub detail: String,
+    // Where selecting the entry leads, if anywhere.
+    pub target: Option<Location>,
+    pub is_deprecated: bool,
+}
+
+/**
+ * What the explorer shows for a location: a heading and the entries to pick from.
+ */
+pub struct View {
+    pub title: String,
+    pub description: Option<String>,
+    pub deprecation_reason: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+impl View {
+    pub fn of(schema: &Schema, location: &Location) -> View {
+        match location {
+            Location::Types => View {
+                title: "Types".to_string(),
+                description: None,
+                deprecation_reason: None,
+                entries: schema
+                    .types()
+                    .iter()
+                    .map(|ty| Entry {
+                        label: ty.name().to_string(),
+                        detail: format!(" ({})", ty.keyword()),
+                        target: Some(Location::Type(ty.name().to_string())),
+                        is_deprecated: false,
+                    })
+                    .collect(),
+            },
+            Location::Type(type_name) => match schema.type_definition(type_name) {
+                Some(ty) => View::of_type(ty),
+                None => View::missing(format!("Type {} is not found in the schema", type_name)),
+            },
+            Location::Field {
+                type_name,
+                field_name,
+            } => match schema
+                .type_definition(type_name)
+                .and_then(|ty| schema.field_definition(ty, field_name))
+            {
+                Some(field) => View::of_field(type_name, field),
+                None => View::missing(format!(
+                    "Field {}.{} is not found in the schema",
+                    type_name, field_name
+                )),
+            },
+        }
+    }
+
+    fn of_type(ty: &Type) -> View {
+        let mut entries = vec![];
+
+        for interface_name in ty.interface_names() {
+            entries.push(Entry {
+                label: interface_name.to_string(),
+                detail: " (implemented interface)".to_string(),
+                target: Some(Location::Type(interface_name.to_string())),
+                is_deprecated: false,
+            });
+        }
+
+        if let Type::Interface(_) | Type::Union(_) = ty {
+            for possible_type_name in ty.possible_type_names() {
+                entries.push(Entry {
+                    label: possible_type_name.to_string(),
+                    detail: " (possible type)".to_string(),
+                    target: Some(Location::Type(possible_type_name.to_string())),
+                    is_deprecated: false,
+                });
+            }
+        }
+
+        for field in ty.fields() {
+            entries.push(Entry {
+                label: field.name.to_string(),
+                detail: without_name(field.signature(), &field.name),
+                target: Some(Location::Field {
+                    type_name: ty.name().to_string(),
+                    field_name: field.name.to_string(),
+                }),
+                is_deprecated: field.deprecation_reason.is_some(),
+            });
+        }
+
+        match ty {
+            Type::Enum(enum_type) => {
+                for enum_value in enum_type.values() {
+                    entries.push(Entry {
+                        label: enum_value.name.to_string(),
+                        detail: String::new(),
+                        target: None,
+                        is_deprecated: enum_value.deprecation_reason.is_some(),
+                    });
+                }
+            }
+            Type::InputObject(input_type) => {
+                for arg in input_type.args.args() {
+                    entries.push(Entry {
+                        label: arg.name.to_string(),
+                        detail: without_name(arg.signature(), &arg.name),
+                        target: arg
+                            .arg_type
+                            .underlying_type_name()
+                            .map(|type_name| Location::Type(type_name.to_string())),
+                        is_deprecated: arg.deprecation_reason.is_some(),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        View {
+            title: format!("{} {}", ty.keyword(), ty.name()),
+            description: ty.description().cloned(),
+            deprecation_reason: None,
+            entries,
+        }
+    }
+
+    fn of_field(type_name: &str, field: &Field) -> View {
+        let mut entries = vec![];
+
+        if let Some(field_type_name) = field.field_type.underlying_type_name() {
+            entries.push(Entry {
+                label: field_type_name.to_string(),
+                detail: format!(" (returned as {})", field.field_type.signature()),
+                target: Some(Location::Type(field_type_name.to_string())),
+                is_deprecated: false,
+            });
+        }
+
+        for arg in field.args.args() {
+            entries.push(Entry {
+                label: arg.name.to_string(),
+                detail: without_name(arg.signature(), &arg.name),
+                target: arg
+                    .arg_type
+                    .underlying_type_name()
+                    .map(|type_name| Location::Type(type_name.to_string())),
+                is_deprecated: arg.deprecation_reason.is_some(),
+            });
+        }
+
+        View {
+            title: format!("{}.{}", type_name, field.signature()),
+            description: field.description.clone(),
+            deprecation_reason: field.deprecation_reason.clone(),
+            entries,
+        }
+    }
+
+    fn missing(title: String) -> View {
+        View {
+            title,
+            description: None,
+            deprecation_reason: None,
+            entries: vec![],
+        }
+    }
+}
+
+// Eg.: `first: Int = 10` -> `: Int = 10`.
+fn without_name(signature: String, name: &str) -> String {
+    signature[name.len()..].to_string()
+}
+
+struct Visit {
+    location: Location,
+    selection_index: usize,
+}
+
+/**
+ * Browses the schema: types, their fields, arguments and the types those refer to.
+ * Visited locations are kept, so references can be followed back and forth.
+ */
+pub struct SchemaExplorer {
+    location: Location,
+    selection_index: usize,
+    filter: String,
+    back: Vec<Visit>,
+    forward: Vec<Visit>,
+    printer: SchemaExplorerPrinter,
+}
+
+impl SchemaExplorer {
+    pub fn new() -> SchemaExplorer {
+        SchemaExplorer {
+            location: Location::Types,
+            selection_index: 0,
+            filter: String::new(),
+            back: vec![],
+            forward: vec![],
+            printer: SchemaExplorerPrinter::new(),
+        }
+    }
+
+    pub fn update(&mut self, input: KeyboardInput, schema: &Schema) -> Option<Command> {
+        let cmd = self.handle_input(input, schema);
+        if cmd.is_some() {
+            return cmd;
+        }
+
+        self.refresh_screen(schema);
+
+        None
+    }
+
+    fn handle_input(&mut self, input: KeyboardInput, schema: &Schema) -> Option<Command> {
+        let view = View::of(schema, &self.location);
+        let entries = self.filtered_entries(&view);
+
+        match input {
+            KeyboardInput::Up if !entries.is_empty() => {
+                self.selection_index = (self.selection_index + entries.len() - 1) % entries.len();
+            }
+            KeyboardInput::Down if !entries.is_empty() => {
+                self.selection_index = (self.selection_index + 1) % entries.len();
+            }
+            KeyboardInput::Enter => {
+                if let Some(target) = entries
+                    .get(self.selection_index)
+                    .and_then(|(entry, _)| entry.target.clone())
+                {
+                    self.open(target);
+                }
+            }
+            KeyboardInput::Left => self.go_back(),
+            KeyboardInput::Right => self.go_forward(),
+            KeyboardInput::Backspace => {
+                self.filter.pop();
+                self.selection_index = 0;
+            }
+            KeyboardInput::VisibleChar(ch) => {
+                self.filter.push(ch as char);
+                self.selection_index = 0;
+            }
+            KeyboardInput::Escape => {
+                if self.filter.is_empty() {
+                    return Some(Command::Close);
+                }
+                self.filter.clear();
+                self.selection_index = 0;
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    // Navigates to a new location, dropping the forward history.
+    pub fn open(&mut self, location: Location) {
+        if self.location == location {
+            return;
+        }
+
+        let previous = self.visit(location, 0);
+        self.back.push(previous);
+        self.forward.clear();
+    }
+
+    fn go_back(&mut self) {
+        if let Some(visit) = self.back.pop() {
+            let current = self.visit(visit.location, visit.selection_index);
+            self.forward.push(current);
+        }
+    }
+
+    fn go_forward(&mut self) {
+        if let Some(visit) = self.forward.pop() {
+            let current = self.visit(visit.location, visit.selection_index);
+            self.back.push(current);
+        }
+    }
+
+    // Moves to `location`, returns where we were.
+    fn visit(&mut self, location: Location, selection_index: usize) -> Visit {
+        self.filter.clear();
+        Visit {
+            location: mem::replace(&mut self.location, location),
+            selection_index: mem::replace(&mut self.selection_index, selection_index),
+        }
+    }
+
+    // Entries matching the filter, with the matching positions of their labels.
+    // Best matches go first, equally good ones keep the order of the view.
+    fn filtered_entries<'v>(&self, view: &'v View) -> Vec<(&'v Entry, Vec<usize>)> {
+        let mut entries = view
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                fuzzy_match_scored(&entry.label, &self.filter)
+                    .map(|fuzzy_match| (entry, fuzzy_match))
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, fuzzy_match)| Reverse(fuzzy_match.score));
+
+        entries
+            .into_iter()
+            .map(|(entry, fuzzy_match)| (entry, fuzzy_match.positions))
+            .collect()
+    }
+
+    pub fn refresh_screen(&mut self, schema: &Schema) {
+        let view = View::of(schema, &self.location);
+        let entries = self.filtered_entries(&view);
+
+        self.printer
+            .print(&view, &entries, self.selection_index, &self.filter);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{schema::fixture, stdin_reader::KeyboardInput};
+
+    use super::{Location, SchemaExplorer, View};
+
+    fn labels(view: &View) -> Vec<String> {
+        view.entries
+            .iter()
+            .map(|entry| format!("{}{}", entry.label, entry.detail))
+            .collect()
+    }
+
+    #[test]
+    fn test_type_view() {
+        let view = View::of(&fixture::schema(), &Location::Type("User".to_string()));
+
+        assert_eq!("type User", view.title);
+        assert_eq!(Some("A registered user.".to_string()), view.description);
+        assert_eq!(
+            vec![
+                "Node (implemented interface)",
+                "id: ID!",
+                "name: String",
+                "role: Role",
+                "username: String",
+                "posts(first: Int = 10): [Post]",
+            ],
+            labels(&view)
+        );
+        assert!(view.entries[4].is_deprecated);
+    }
+
+    #[test]
+    fn test_field_view() {
+        let view = View::of(
+            &fixture::schema(),
+            &Location::Field {
+                type_name: "Query".to_string(),
+                field_name: "users".to_string(),
+            },
+        );
+
+        assert_eq!(
+            vec![
+                "User (returned as [User!]!)",
+                "first: Int",
+                "offset: Int",
+                "filter: UserFilter",
+            ],
+            labels(&view)
+        );
+        assert_eq!(
+            Some(Location::Type("UserFilter".to_string())),
+            view.entries[3].target
+        );
+    }
+
+    #[test]
+    fn test_filter_ranks_entries() {
+        let schema = fixture::schema();
+        let mut explorer = SchemaExplorer::new();
+        explorer.filter = "user".to_string();
+
+        let view = View::of(&schema, &explorer.location);
+        let labels = explorer
+            .filtered_entries(&view)
+            .into_iter()
+            .map(|(entry, _)| entry.label.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["User", "UserFilter", "CreateUserInput"], labels);
+    }
+
+    #[test]
+    fn test_navigation() {
+        let schema = fixture::schema();
+        let mut explorer = SchemaExplorer::new();
+
+        // Filters the types, then drills into User and its posts field.
+        for ch in "usr".bytes() {
+            explorer.handle_input(KeyboardInput::VisibleChar(ch), &schema);
+        }
+        explorer.handle_input(KeyboardInput::Enter, &schema);
+        assert_eq!(Location::Type("User".to_string()), explorer.location);
+        assert!(explorer.filter.is_empty());
+
+        explorer.open(Location::Field {
+            type_name: "User".to_string(),
+            field_name: "posts".to_string(),
+        });
+        explorer.handle_input(KeyboardInput::Enter, &schema);
+        assert_eq!(Location::Type("Post".to_string()), explorer.location);
+
+        explorer.handle_input(KeyboardInput::Left, &schema);
+        explorer.handle_input(KeyboardInput::Left, &schema);
+        assert_eq!(Location::Type("User".to_string()), explorer.location);
+
+        explorer.handle_input(KeyboardInput::Right, &schema);
+        assert_eq!(
+            Location::Field {
+                type_name: "User".to_string(),
+                field_name: "posts".to_string(),
+            },
+            explorer.location
+        );
+        assert!(explorer
+            .handle_input(KeyboardInput::Escape, &schema)
+            .is_some());
+    }
+}
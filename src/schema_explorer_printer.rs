@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+
+use crate::{
+    schema_explorer::{Entry, View},
+    terminal_handler::TerminalHandler,
+};
+
+const COLOR_LABEL: usize = 32;
+const COLOR_LABEL_MATCH: usize = 97;
+const STRIKE_THROUGH: usize = 9;
+const STRIKE_THROUGH_OFF: usize = 29;
+const COLOR_DETAIL: usize = 90;
+const COLOR_DESCRIPTION: usize = 37;
+const COLOR_DEPRECATION: usize = 93;
+// Description lines shown at most, long descriptions would push the entries off the screen.
+const MAX_DESCRIPTION_HEIGHT: usize = 6;
+
+/*
+
+type User
+A registered user.
+Filter: na
++ name: String
++ posts(first: Int = 10): [Post]
+| ADMIN
+
+*/
+
+pub struct SchemaExplorerPrinter;
+
+impl SchemaExplorerPrinter {
+    pub fn new() -> SchemaExplorerPrinter {
+        SchemaExplorerPrinter
+    }
+
+    pub fn print(
+        &self,
+        view: &View,
+        entries: &[(&Entry, Vec<usize>)],
+        selected_index: usize,
+        filter: &str,
+    ) {
+        let (width, height) = term_size::dimensions().unwrap();
+
+        let mut buf: String = String::new();
+        TerminalHandler::append_hide_cursor(&mut buf);
+        TerminalHandler::append_clear_screen(&mut buf);
+        TerminalHandler::append_cursor_location(&mut buf, 0, 0);
+
+        let mut header = vec![format!("\x1B[1m{}\x1B[0m", chop(&view.title, width))];
+        if let Some(deprecation_reason) = &view.deprecation_reason {
+            header.push(format!(
+                "\x1B[{}m{}\x1B[0m",
+                COLOR_DEPRECATION,
+                chop(&format!("Deprecated: {}", deprecation_reason), width)
+            ));
+        }
+        if let Some(description) = &view.description {
+            for line in description.lines().take(MAX_DESCRIPTION_HEIGHT) {
+                header.push(format!(
+                    "\x1B[{}m{}\x1B[0m",
+                    COLOR_DESCRIPTION,
+                    chop(line, width)
+                ));
+            }
+        }
+        header.push(format!("Filter: {}", chop(filter, width.saturating_sub(8))));
+
+        for line in &header {
+            buf.push_str(line);
+            buf.push_str("\n\r");
+        }
+
+        // Keeps the selection on the screen, above the help bar.
+        let list_height = height.saturating_sub(header.len() + 1).max(1);
+        let scroll = selected_index.saturating_sub(list_height - 1);
+
+        for (i, (entry, fuzzy_match_positions)) in
+            entries.iter().enumerate().skip(scroll).take(list_height)
+        {
+            let line = SchemaExplorerPrinter::entry_line(entry, fuzzy_match_positions, width);
+
+            if selected_index == i {
+                buf.push_str(&format!("\x1B[44m{}\x1B[0m", line));
+            } else {
+                buf.push_str(&format!("{}\x1B[0m", line));
+            }
+            buf.push_str("\n\r");
+        }
+
+        TerminalHandler::append_cursor_location(&mut buf, 0, height - 1);
+        let help =
+            " Schema | ENTER: open | LEFT / RIGHT: back / forward | type: filter | ESC: editor";
+        buf.push_str(&format!(
+            "\x1B[7m{: <width$}\x1B[0m",
+            chop(help, width),
+            width = width
+        ));
+
+        io::stdout()
+            .write_all(buf.as_bytes())
+            .expect("Failed writing output");
+
+        io::stdout().flush().expect("Cannot flush STDOUT");
+    }
+
+    // Entries that lead somewhere are marked with `+`, like folders in the file browser.
+    // Only the foreground changes within the line, so the selection background stays. Chopped by
+    // chars, default values in the details can have multi-byte characters.
+    fn entry_line(entry: &Entry, fuzzy_match_positions: &[usize], width: usize) -> String {
+        let marker = if entry.target.is_some() { "+ " } else { "| " };
+        let mut line = format!("\x1B[{}m{}", COLOR_DETAIL, chop(marker, width));
+        let mut len = marker.len().min(width);
+
+        if entry.is_deprecated {
+            line.push_str(&format!("\x1B[{}m", STRIKE_THROUGH));
+        }
+        for (i, ch) in entry.label.chars().take(width - len).enumerate() {
+            let color = if fuzzy_match_positions.contains(&i) {
+                COLOR_LABEL_MATCH
+            } else {
+                COLOR_LABEL
+            };
+            line.push_str(&format!("\x1B[{}m{}", color, ch));
+            len += 1;
+        }
+
+        line.push_str(&format!(
+            "\x1B[{}m\x1B[{}m{}",
+            STRIKE_THROUGH_OFF,
+            COLOR_DETAIL,
+            chop(&entry.detail, width - len)
+        ));
+
+        line
+    }
+}
+
+fn chop(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
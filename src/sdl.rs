@@ -0,0 +1,1323 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    schema::{self, Arg, Schema, Type, DEFAULT_DEPRECATION_REASON},
+    tokenizer::{Token, TokenKind, Tokenizer},
+    util::Error,
+};
+
+const BUILT_IN_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+const BUILT_IN_DIRECTIVES: [&str; 4] = ["skip", "include", "deprecated", "specifiedBy"];
+const INDENT: &str = "  ";
+
+// Part of every schema (including the introspection types), SDL files usually leave them out.
+const BUILT_IN_DEFINITIONS: &str = r#"
+scalar Int
+scalar Float
+scalar String
+scalar Boolean
+scalar ID
+
+directive @skip(if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+directive @include(if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+directive @deprecated(reason: String = "No longer supported") on FIELD_DEFINITION | ARGUMENT_DEFINITION | INPUT_FIELD_DEFINITION | ENUM_VALUE
+directive @specifiedBy(url: String!) on SCALAR
+
+type __Schema {
+  description: String
+  types: [__Type!]!
+  queryType: __Type!
+  mutationType: __Type
+  subscriptionType: __Type
+  directives: [__Directive!]!
+}
+
+type __Type {
+  kind: __TypeKind!
+  name: String
+  description: String
+  fields(includeDeprecated: Boolean = false): [__Field!]
+  interfaces: [__Type!]
+  possibleTypes: [__Type!]
+  enumValues(includeDeprecated: Boolean = false): [__EnumValue!]
+  inputFields(includeDeprecated: Boolean = false): [__InputValue!]
+  ofType: __Type
+  specifiedByURL: String
+}
+
+enum __TypeKind {
+  SCALAR
+  OBJECT
+  INTERFACE
+  UNION
+  ENUM
+  INPUT_OBJECT
+  LIST
+  NON_NULL
+}
+
+type __Field {
+  name: String!
+  description: String
+  args(includeDeprecated: Boolean = false): [__InputValue!]!
+  type: __Type!
+  isDeprecated: Boolean!
+  deprecationReason: String
+}
+
+type __InputValue {
+  name: String!
+  description: String
+  type: __Type!
+  defaultValue: String
+  isDeprecated: Boolean!
+  deprecationReason: String
+}
+
+type __EnumValue {
+  name: String!
+  description: String
+  isDeprecated: Boolean!
+  deprecationReason: String
+}
+
+type __Directive {
+  name: String!
+  description: String
+  locations: [__DirectiveLocation!]!
+  args(includeDeprecated: Boolean = false): [__InputValue!]!
+  isRepeatable: Boolean!
+}
+
+enum __DirectiveLocation {
+  QUERY
+  MUTATION
+  SUBSCRIPTION
+  FIELD
+  FRAGMENT_DEFINITION
+  FRAGMENT_SPREAD
+  INLINE_FRAGMENT
+  VARIABLE_DEFINITION
+  SCHEMA
+  SCALAR
+  OBJECT
+  FIELD_DEFINITION
+  ARGUMENT_DEFINITION
+  INTERFACE
+  UNION
+  ENUM
+  ENUM_VALUE
+  INPUT_OBJECT
+  INPUT_FIELD_DEFINITION
+}
+"#;
+
+enum TypeRef {
+    Named(Token),
+    List(Box<TypeRef>),
+    NonNull(Box<TypeRef>),
+}
+
+// Arguments and input object fields.
+struct InputValueDefinition {
+    name: String,
+    description: Option<String>,
+    type_ref: TypeRef,
+    // As a GraphQL literal, eg.: `"hello"` or `10`.
+    default_value: Option<String>,
+    deprecation_reason: Option<String>,
+}
+
+struct FieldDefinition {
+    name: String,
+    description: Option<String>,
+    args: Vec<InputValueDefinition>,
+    type_ref: TypeRef,
+    deprecation_reason: Option<String>,
+}
+
+struct EnumValueDefinition {
+    name: String,
+    description: Option<String>,
+    deprecation_reason: Option<String>,
+}
+
+/**
+ * A type definition or extension, only the lists of its kind are filled in.
+ */
+struct TypeDefinition {
+    // As in introspection, eg.: `OBJECT`.
+    kind: &'static str,
+    name: Token,
+    description: Option<String>,
+    interfaces: Vec<Token>,
+    fields: Vec<FieldDefinition>,
+    input_fields: Vec<InputValueDefinition>,
+    enum_values: Vec<EnumValueDefinition>,
+    // Members of unions.
+    possible_types: Vec<Token>,
+}
+
+impl TypeDefinition {
+    fn extend(&mut self, extension: TypeDefinition) {
+        self.interfaces.extend(extension.interfaces);
+        self.fields.extend(extension.fields);
+        self.input_fields.extend(extension.input_fields);
+        self.enum_values.extend(extension.enum_values);
+        self.possible_types.extend(extension.possible_types);
+    }
+}
+
+struct DirectiveDefinition {
+    name: String,
+    description: Option<String>,
+    args: Vec<InputValueDefinition>,
+    locations: Vec<String>,
+}
+
+/**
+ * Type system definitions read from SDL sources. Extensions are applied once all sources are read,
+ * so they can be spread over files in any order.
+ */
+#[derive(Default)]
+pub struct SdlDocument {
+    types: Vec<TypeDefinition>,
+    extensions: Vec<TypeDefinition>,
+    directives: Vec<DirectiveDefinition>,
+    query_type: Option<String>,
+    mutation_type: Option<String>,
+    subscription_type: Option<String>,
+}
+
+impl SdlDocument {
+    pub fn add_source(&mut self, source: &str) -> Result<(), Error> {
+        // Commas are insignificant.
+        let tokens = Tokenizer::tokenize(source, false)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Comma)
+            .collect();
+        let mut parser = SdlParser { tokens, ptr: 0 };
+
+        while parser.peek_token().is_some() {
+            parser.parse_definition(self)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * The schema as an introspection query response, the same `Schema::from_json_value` reads for
+     * fetched schemas.
+     */
+    pub fn into_introspection(mut self) -> Result<Value, Error> {
+        let mut type_names = HashSet::new();
+        for ty in &self.types {
+            if !type_names.insert(ty.name.original.as_str()) {
+                return Err(error_at(
+                    &ty.name,
+                    &format!("Type {} is defined more than once", ty.name.original),
+                ));
+            }
+        }
+
+        let mut built_ins = SdlDocument::default();
+        built_ins.add_source(BUILT_IN_DEFINITIONS)?;
+        for ty in built_ins.types {
+            if self.type_definition(&ty.name.original).is_none() {
+                self.types.push(ty);
+            }
+        }
+        for directive in built_ins.directives {
+            if !self
+                .directives
+                .iter()
+                .any(|defined| defined.name == directive.name)
+            {
+                self.directives.push(directive);
+            }
+        }
+
+        for extension in mem::take(&mut self.extensions) {
+            let Some(ty) = self
+                .types
+                .iter_mut()
+                .find(|ty| ty.name.original == extension.name.original)
+            else {
+                return Err(error_at(
+                    &extension.name,
+                    &format!("Cannot extend undefined type {}", extension.name.original),
+                ));
+            };
+            if ty.kind != extension.kind {
+                return Err(error_at(
+                    &extension.name,
+                    &format!(
+                        "Cannot extend {} {} as {}",
+                        ty.kind, ty.name.original, extension.kind
+                    ),
+                ));
+            }
+
+            ty.extend(extension);
+        }
+
+        let kinds = self
+            .types
+            .iter()
+            .map(|ty| (ty.name.original.as_str(), ty.kind))
+            .collect::<HashMap<_, _>>();
+
+        let query_type = match &self.query_type {
+            Some(query_type) => query_type.clone(),
+            None if kinds.contains_key("Query") => "Query".to_string(),
+            None => return Err("The schema has no query root type".into()),
+        };
+        let mutation_type = self.mutation_type.clone().or_else(|| {
+            kinds
+                .contains_key("Mutation")
+                .then(|| "Mutation".to_string())
+        });
+        let subscription_type = self.subscription_type.clone().or_else(|| {
+            kinds
+                .contains_key("Subscription")
+                .then(|| "Subscription".to_string())
+        });
+
+        let types = self
+            .types
+            .iter()
+            .map(|ty| self.type_json(ty, &kinds))
+            .collect::<Result<Vec<_>, _>>()?;
+        let directives = self
+            .directives
+            .iter()
+            .map(|directive| {
+                Ok(json!({
+                    "name": directive.name,
+                    "description": directive.description,
+                    "locations": directive.locations,
+                    "args": input_values_json(&directive.args, &kinds)?,
+                }))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(json!({
+            "data": {
+                "__schema": {
+                    "queryType": { "name": query_type },
+                    "mutationType": mutation_type.map(|name| json!({ "name": name })),
+                    "subscriptionType": subscription_type.map(|name| json!({ "name": name })),
+                    "types": types,
+                    "directives": directives,
+                }
+            }
+        }))
+    }
+
+    fn type_definition(&self, name: &str) -> Option<&TypeDefinition> {
+        self.types.iter().find(|ty| ty.name.original == name)
+    }
+
+    fn type_json(&self, ty: &TypeDefinition, kinds: &HashMap<&str, &str>) -> Result<Value, Error> {
+        let fields = match ty.kind {
+            "OBJECT" | "INTERFACE" => Some(
+                ty.fields
+                    .iter()
+                    .map(|field| {
+                        Ok(json!({
+                            "name": field.name,
+                            "description": field.description,
+                            "args": input_values_json(&field.args, kinds)?,
+                            "type": type_ref_json(&field.type_ref, kinds)?,
+                            "isDeprecated": field.deprecation_reason.is_some(),
+                            "deprecationReason": field.deprecation_reason,
+                        }))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?,
+            ),
+            _ => None,
+        };
+        let input_fields = match ty.kind {
+            "INPUT_OBJECT" => Some(input_values_json(&ty.input_fields, kinds)?),
+            _ => None,
+        };
+        let enum_values = match ty.kind {
+            "ENUM" => Some(
+                ty.enum_values
+                    .iter()
+                    .map(|enum_value| {
+                        json!({
+                            "name": enum_value.name,
+                            "description": enum_value.description,
+                            "isDeprecated": enum_value.deprecation_reason.is_some(),
+                            "deprecationReason": enum_value.deprecation_reason,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        };
+        let interfaces = match ty.kind {
+            "OBJECT" | "INTERFACE" => Some(
+                ty.interfaces
+                    .iter()
+                    .map(|interface| named_type_json(interface, kinds, &["INTERFACE"]))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            _ => None,
+        };
+        let possible_types = match ty.kind {
+            "UNION" => Some(
+                ty.possible_types
+                    .iter()
+                    .map(|member| named_type_json(member, kinds, &["OBJECT"]))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            // The object types implementing the interface.
+            "INTERFACE" => Some(
+                self.types
+                    .iter()
+                    .filter(|object| {
+                        object.kind == "OBJECT"
+                            && object
+                                .interfaces
+                                .iter()
+                                .any(|interface| interface.original == ty.name.original)
+                    })
+                    .map(|object| json!({ "kind": "OBJECT", "name": object.name.original }))
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        Ok(json!({
+            "kind": ty.kind,
+            "name": ty.name.original,
+            "description": ty.description,
+            "fields": fields,
+            "inputFields": input_fields,
+            "interfaces": interfaces,
+            "enumValues": enum_values,
+            "possibleTypes": possible_types,
+        }))
+    }
+}
+
+fn input_values_json(
+    input_values: &[InputValueDefinition],
+    kinds: &HashMap<&str, &str>,
+) -> Result<Vec<Value>, Error> {
+    input_values
+        .iter()
+        .map(|input_value| {
+            Ok(json!({
+                "name": input_value.name,
+                "description": input_value.description,
+                "type": type_ref_json(&input_value.type_ref, kinds)?,
+                "defaultValue": input_value.default_value,
+                "isDeprecated": input_value.deprecation_reason.is_some(),
+                "deprecationReason": input_value.deprecation_reason,
+            }))
+        })
+        .collect()
+}
+
+fn type_ref_json(type_ref: &TypeRef, kinds: &HashMap<&str, &str>) -> Result<Value, Error> {
+    match type_ref {
+        TypeRef::Named(name) => named_type_json(name, kinds, &[]),
+        TypeRef::List(inner) => {
+            Ok(json!({ "kind": "LIST", "ofType": type_ref_json(inner, kinds)? }))
+        }
+        TypeRef::NonNull(inner) => {
+            Ok(json!({ "kind": "NON_NULL", "ofType": type_ref_json(inner, kinds)? }))
+        }
+    }
+}
+
+// Reference to a defined type, of one of the `expected_kinds` when not empty.
+fn named_type_json(
+    name: &Token,
+    kinds: &HashMap<&str, &str>,
+    expected_kinds: &[&str],
+) -> Result<Value, Error> {
+    let Some(kind) = kinds.get(name.original.as_str()) else {
+        return Err(error_at(name, &format!("Unknown type {}", name.original)));
+    };
+    if !expected_kinds.is_empty() && !expected_kinds.contains(kind) {
+        return Err(error_at(
+            name,
+            &format!(
+                "Type {} is {}, expected {}",
+                name.original,
+                kind,
+                expected_kinds.join(" or ")
+            ),
+        ));
+    }
+
+    Ok(json!({ "kind": kind, "name": name.original, "ofType": null }))
+}
+
+fn error_at(token: &Token, message: &str) -> Error {
+    format!("{} at {}", message, token.span).into()
+}
+
+struct SdlParser {
+    tokens: Vec<Token>,
+    ptr: usize,
+}
+
+impl SdlParser {
+    fn parse_definition(&mut self, document: &mut SdlDocument) -> Result<(), Error> {
+        let description = self.parse_description();
+        let is_extension = description.is_none() && self.consume_keyword("extend");
+        let keyword = self.expect_name("a definition")?;
+
+        match keyword.original.as_str() {
+            "schema" => self.parse_schema_definition(document),
+            "scalar" | "type" | "interface" | "union" | "enum" | "input" => {
+                let definition = self.parse_type_definition(&keyword.original, description)?;
+                if is_extension {
+                    document.extensions.push(definition);
+                } else {
+                    document.types.push(definition);
+                }
+                Ok(())
+            }
+            "directive" if !is_extension => {
+                let definition = self.parse_directive_definition(description)?;
+                document.directives.push(definition);
+                Ok(())
+            }
+            _ => Err(error_at(
+                &keyword,
+                &format!("Unexpected {}, expected a definition", keyword.original),
+            )),
+        }
+    }
+
+    // Eg.: `schema { query: Root mutation: RootMutation }`.
+    fn parse_schema_definition(&mut self, document: &mut SdlDocument) -> Result<(), Error> {
+        self.parse_directives()?;
+        self.expect(TokenKind::OpenBrace, "{")?;
+
+        while !self.consume(&TokenKind::CloseBrace) {
+            let operation = self.expect_name("an operation type")?;
+            self.expect(TokenKind::Colon, ":")?;
+            let type_name = self.expect_name("a type name")?.original;
+
+            match operation.original.as_str() {
+                "query" => document.query_type = Some(type_name),
+                "mutation" => document.mutation_type = Some(type_name),
+                "subscription" => document.subscription_type = Some(type_name),
+                _ => {
+                    return Err(error_at(
+                        &operation,
+                        &format!("Unknown operation type {}", operation.original),
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_type_definition(
+        &mut self,
+        keyword: &str,
+        description: Option<String>,
+    ) -> Result<TypeDefinition, Error> {
+        let mut definition = TypeDefinition {
+            kind: match keyword {
+                "scalar" => "SCALAR",
+                "type" => "OBJECT",
+                "interface" => "INTERFACE",
+                "union" => "UNION",
+                "enum" => "ENUM",
+                _ => "INPUT_OBJECT",
+            },
+            name: self.expect_name("a type name")?,
+            description,
+            interfaces: vec![],
+            fields: vec![],
+            input_fields: vec![],
+            enum_values: vec![],
+            possible_types: vec![],
+        };
+
+        match keyword {
+            "type" | "interface" => {
+                if self.consume_keyword("implements") {
+                    definition.interfaces = self.parse_name_list(TokenKind::Amp)?;
+                }
+                self.parse_directives()?;
+
+                if self.consume(&TokenKind::OpenBrace) {
+                    while !self.consume(&TokenKind::CloseBrace) {
+                        definition.fields.push(self.parse_field_definition()?);
+                    }
+                }
+            }
+            "union" => {
+                self.parse_directives()?;
+
+                if self.consume(&TokenKind::Equals) {
+                    definition.possible_types = self.parse_name_list(TokenKind::Pipe)?;
+                }
+            }
+            "enum" => {
+                self.parse_directives()?;
+
+                if self.consume(&TokenKind::OpenBrace) {
+                    while !self.consume(&TokenKind::CloseBrace) {
+                        definition.enum_values.push(EnumValueDefinition {
+                            description: self.parse_description(),
+                            name: self.expect_name("an enum value")?.original,
+                            deprecation_reason: self.parse_directives()?,
+                        });
+                    }
+                }
+            }
+            "input" => {
+                self.parse_directives()?;
+
+                if self.consume(&TokenKind::OpenBrace) {
+                    while !self.consume(&TokenKind::CloseBrace) {
+                        definition
+                            .input_fields
+                            .push(self.parse_input_value_definition()?);
+                    }
+                }
+            }
+            _ => {
+                self.parse_directives()?;
+            }
+        }
+
+        Ok(definition)
+    }
+
+    // Eg.: `@cached(ttl: Int = 60) repeatable on FIELD_DEFINITION | OBJECT`.
+    fn parse_directive_definition(
+        &mut self,
+        description: Option<String>,
+    ) -> Result<DirectiveDefinition, Error> {
+        self.expect(TokenKind::At, "@")?;
+        let name = self.expect_name("a directive name")?.original;
+        let args = self.parse_arguments_definition()?;
+        self.consume_keyword("repeatable");
+
+        if !self.consume_keyword("on") {
+            return Err(self.unexpected("on"));
+        }
+        let locations = self
+            .parse_name_list(TokenKind::Pipe)?
+            .into_iter()
+            .map(|location| location.original)
+            .collect();
+
+        Ok(DirectiveDefinition {
+            name,
+            description,
+            args,
+            locations,
+        })
+    }
+
+    // Eg.: `posts(first: Int = 10): [Post] @deprecated`.
+    fn parse_field_definition(&mut self) -> Result<FieldDefinition, Error> {
+        let description = self.parse_description();
+        let name = self.expect_name("a field name")?.original;
+        let args = self.parse_arguments_definition()?;
+        self.expect(TokenKind::Colon, ":")?;
+        let type_ref = self.parse_type_ref()?;
+
+        Ok(FieldDefinition {
+            name,
+            description,
+            args,
+            type_ref,
+            deprecation_reason: self.parse_directives()?,
+        })
+    }
+
+    fn parse_arguments_definition(&mut self) -> Result<Vec<InputValueDefinition>, Error> {
+        let mut args = vec![];
+
+        if self.consume(&TokenKind::OpenParen) {
+            while !self.consume(&TokenKind::CloseParen) {
+                args.push(self.parse_input_value_definition()?);
+            }
+        }
+
+        Ok(args)
+    }
+
+    // Eg.: `first: Int = 10`.
+    fn parse_input_value_definition(&mut self) -> Result<InputValueDefinition, Error> {
+        let description = self.parse_description();
+        let name = self.expect_name("an argument name")?.original;
+        self.expect(TokenKind::Colon, ":")?;
+        let type_ref = self.parse_type_ref()?;
+        let default_value = if self.consume(&TokenKind::Equals) {
+            Some(self.parse_value()?)
+        } else {
+            None
+        };
+
+        Ok(InputValueDefinition {
+            name,
+            description,
+            type_ref,
+            default_value,
+            deprecation_reason: self.parse_directives()?,
+        })
+    }
+
+    fn parse_type_ref(&mut self) -> Result<TypeRef, Error> {
+        let type_ref = if self.consume(&TokenKind::OpenBracket) {
+            let inner = self.parse_type_ref()?;
+            self.expect(TokenKind::CloseBracket, "]")?;
+            TypeRef::List(Box::new(inner))
+        } else {
+            TypeRef::Named(self.expect_name("a type")?)
+        };
+
+        if self.consume(&TokenKind::Bang) {
+            Ok(TypeRef::NonNull(Box::new(type_ref)))
+        } else {
+            Ok(type_ref)
+        }
+    }
+
+    // A constant value, returned as a GraphQL literal.
+    fn parse_value(&mut self) -> Result<String, Error> {
+        let Some(token) = self.peek_token().cloned() else {
+            return Err(self.unexpected("a value"));
+        };
+
+        match token.kind {
+            TokenKind::Number(_) | TokenKind::Keyword(_) | TokenKind::Str(_) => {
+                self.ptr += 1;
+                Ok(token.original)
+            }
+            TokenKind::BlockStr(value) => {
+                self.ptr += 1;
+                Ok(Value::String(value).to_string())
+            }
+            TokenKind::OpenBracket => {
+                self.ptr += 1;
+                let mut elems = vec![];
+                while !self.consume(&TokenKind::CloseBracket) {
+                    elems.push(self.parse_value()?);
+                }
+
+                Ok(format!("[{}]", elems.join(", ")))
+            }
+            TokenKind::OpenBrace => {
+                self.ptr += 1;
+                let mut fields = vec![];
+                while !self.consume(&TokenKind::CloseBrace) {
+                    let name = self.expect_name("a field name")?.original;
+                    self.expect(TokenKind::Colon, ":")?;
+                    fields.push(format!("{}: {}", name, self.parse_value()?));
+                }
+
+                Ok(format!("{{{}}}", fields.join(", ")))
+            }
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    // Skips the directives, returns the deprecation reason when `@deprecated` is among them.
+    fn parse_directives(&mut self) -> Result<Option<String>, Error> {
+        let mut deprecation_reason = None;
+
+        while self.consume(&TokenKind::At) {
+            let name = self.expect_name("a directive name")?.original;
+            let is_deprecated = name == "deprecated";
+            if is_deprecated {
+                deprecation_reason = Some(DEFAULT_DEPRECATION_REASON.to_string());
+            }
+
+            if self.consume(&TokenKind::OpenParen) {
+                while !self.consume(&TokenKind::CloseParen) {
+                    let arg_name = self.expect_name("an argument name")?.original;
+                    self.expect(TokenKind::Colon, ":")?;
+
+                    if let Some(Token {
+                        kind: TokenKind::Str(reason) | TokenKind::BlockStr(reason),
+                        ..
+                    }) = self.peek_token()
+                    {
+                        if is_deprecated && arg_name == "reason" {
+                            deprecation_reason = Some(reason.clone());
+                        }
+                    }
+                    self.parse_value()?;
+                }
+            }
+        }
+
+        Ok(deprecation_reason)
+    }
+
+    // Eg.: `& Node & Entity` or `| User | Post`, the leading separator is optional.
+    fn parse_name_list(&mut self, separator: TokenKind) -> Result<Vec<Token>, Error> {
+        self.consume(&separator);
+
+        let mut names = vec![self.expect_name("a name")?];
+        while self.consume(&separator) {
+            names.push(self.expect_name("a name")?);
+        }
+
+        Ok(names)
+    }
+
+    fn parse_description(&mut self) -> Option<String> {
+        match self.peek_token() {
+            Some(Token {
+                kind: TokenKind::Str(description) | TokenKind::BlockStr(description),
+                ..
+            }) => {
+                let description = description.clone();
+                self.ptr += 1;
+                Some(description)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_name(&mut self, expected: &str) -> Result<Token, Error> {
+        match self.peek_token() {
+            Some(
+                token @ Token {
+                    kind: TokenKind::Keyword(_),
+                    ..
+                },
+            ) => {
+                let token = token.clone();
+                self.ptr += 1;
+                Ok(token)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<(), Error> {
+        if self.consume(&kind) {
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn consume(&mut self, kind: &TokenKind) -> bool {
+        if self.peek_token().is_some_and(|token| &token.kind == kind) {
+            self.ptr += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        self.consume(&TokenKind::Keyword(keyword.to_string()))
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        match self.peek_token() {
+            Some(Token {
+                kind: TokenKind::Invalid(message),
+                span,
+                ..
+            }) => format!("{} at {}", message, span).into(),
+            Some(token) => error_at(
+                token,
+                &format!("Unexpected {}, expected {}", token.original, expected),
+            ),
+            None => format!("Unexpected end of the schema, expected {}", expected).into(),
+        }
+    }
+
+    fn peek_token(&self) -> Option<&Token> {
+        self.tokens.get(self.ptr)
+    }
+}
+
+/**
+ * Prints the schema as SDL, leaving out the built-in scalars, directives and introspection types.
+ * Types, directives, fields, arguments and values are sorted by name, so the output only changes
+ * when the schema does.
+ */
+pub fn print_schema(schema: &Schema) -> String {
+    let mut definitions = vec![];
+
+    if let Some(schema_definition) = print_schema_definition(schema) {
+        definitions.push(schema_definition);
+    }
+
+    let mut directives = schema
+        .directives()
+        .iter()
+        .filter(|directive| !BUILT_IN_DIRECTIVES.contains(&directive.name.as_ref()))
+        .collect::<Vec<_>>();
+    directives.sort_by_key(|directive| &directive.name);
+    definitions.extend(directives.into_iter().map(print_directive));
+
+    let mut types = schema
+        .types()
+        .iter()
+        .filter(|ty| !ty.name().starts_with("__") && !BUILT_IN_SCALARS.contains(&ty.name()))
+        .collect::<Vec<_>>();
+    types.sort_by_key(|ty| ty.name());
+    definitions.extend(types.into_iter().map(print_type));
+
+    definitions.join("\n\n") + "\n"
+}
+
+// Only needed when the root types are not named after their operation.
+fn print_schema_definition(schema: &Schema) -> Option<String> {
+    let roots = [
+        ("query", Some(&schema.query_root_name)),
+        ("mutation", schema.mutation_root_name.as_ref()),
+        ("subscription", schema.subscription_root_name.as_ref()),
+    ];
+
+    let is_default = roots.iter().all(|(operation, root_name)| {
+        root_name.is_none_or(|root_name| root_name.to_lowercase() == *operation)
+    });
+    if is_default {
+        return None;
+    }
+
+    let mut buf = "schema {\n".to_string();
+    for (operation, root_name) in roots {
+        if let Some(root_name) = root_name {
+            buf.push_str(&format!("{}{}: {}\n", INDENT, operation, root_name));
+        }
+    }
+    buf.push('}');
+
+    Some(buf)
+}
+
+fn print_directive(directive: &schema::DirectiveDefinition) -> String {
+    let mut locations = directive.locations.clone();
+    locations.sort();
+
+    format!(
+        "{}directive @{}{} on {}",
+        print_description(directive.description.as_ref(), ""),
+        directive.name,
+        print_args(directive.args.args(), ""),
+        locations.join(" | ")
+    )
+}
+
+fn print_type(ty: &Type) -> String {
+    let mut buf = print_description(ty.description(), "");
+    buf.push_str(&format!("{} {}", ty.keyword(), ty.name()));
+
+    let mut lines = vec![];
+    match ty {
+        Type::Object(_) | Type::Interface(_) => {
+            let mut interfaces = ty.interface_names();
+            interfaces.sort();
+            if !interfaces.is_empty() {
+                buf.push_str(&format!(" implements {}", interfaces.join(" & ")));
+            }
+
+            let mut fields = ty.fields().iter().collect::<Vec<_>>();
+            fields.sort_by_key(|field| &field.name);
+            for field in fields {
+                lines.push(format!(
+                    "{}{}{}{}: {}{}",
+                    print_description(field.description.as_ref(), INDENT),
+                    INDENT,
+                    field.name,
+                    print_args(field.args.args(), INDENT),
+                    field.field_type.signature(),
+                    print_deprecation(field.deprecation_reason.as_ref())
+                ));
+            }
+        }
+        Type::Union(_) => {
+            let mut possible_type_names = ty.possible_type_names();
+            possible_type_names.sort();
+
+            if !possible_type_names.is_empty() {
+                buf.push_str(&format!(" = {}", possible_type_names.join(" | ")));
+            }
+        }
+        Type::Enum(enum_type) => {
+            let mut values = enum_type.values().iter().collect::<Vec<_>>();
+            values.sort_by_key(|value| &value.name);
+            for value in values {
+                lines.push(format!(
+                    "{}{}{}{}",
+                    print_description(value.description.as_ref(), INDENT),
+                    INDENT,
+                    value.name,
+                    print_deprecation(value.deprecation_reason.as_ref())
+                ));
+            }
+        }
+        Type::InputObject(input_type) => {
+            for arg in sorted_args(input_type.args.args()) {
+                lines.push(format!(
+                    "{}{}{}",
+                    print_description(arg.description.as_ref(), INDENT),
+                    INDENT,
+                    print_input_value(arg)
+                ));
+            }
+        }
+        Type::Scalar(_) => {}
+    }
+
+    if !lines.is_empty() {
+        buf.push_str(&format!(" {{\n{}\n}}", lines.join("\n")));
+    }
+
+    buf
+}
+
+// Inline, unless the arguments are described.
+fn print_args(args: &[Arg], indent: &str) -> String {
+    if args.is_empty() {
+        return String::new();
+    }
+
+    let args = sorted_args(args);
+    if args.iter().all(|arg| arg.description.is_none()) {
+        return format!(
+            "({})",
+            args.iter()
+                .map(|arg| print_input_value(arg))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let arg_indent = format!("{}{}", indent, INDENT);
+    let mut buf = "(\n".to_string();
+    for arg in args {
+        buf.push_str(&print_description(arg.description.as_ref(), &arg_indent));
+        buf.push_str(&format!("{}{}\n", arg_indent, print_input_value(arg)));
+    }
+    buf.push_str(&format!("{})", indent));
+
+    buf
+}
+
+fn sorted_args(args: &[Arg]) -> Vec<&Arg> {
+    let mut args = args.iter().collect::<Vec<_>>();
+    args.sort_by_key(|arg| &arg.name);
+    args
+}
+
+fn print_input_value(arg: &Arg) -> String {
+    format!(
+        "{}{}",
+        arg.signature(),
+        print_deprecation(arg.deprecation_reason.as_ref())
+    )
+}
+
+fn print_deprecation(deprecation_reason: Option<&String>) -> String {
+    match deprecation_reason {
+        None => String::new(),
+        Some(reason) if reason == DEFAULT_DEPRECATION_REASON => " @deprecated".to_string(),
+        Some(reason) => format!(" @deprecated(reason: {})", Value::String(reason.clone())),
+    }
+}
+
+// The description line(s) before a definition, multi-line ones as block strings.
+fn print_description(description: Option<&String>, indent: &str) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+
+    if !description.contains('\n') {
+        return format!("{}{}\n", indent, Value::String(description.clone()));
+    }
+
+    let mut buf = format!("{}\"\"\"\n", indent);
+    for line in description.replace("\"\"\"", "\\\"\"\"").lines() {
+        if !line.is_empty() {
+            buf.push_str(indent);
+        }
+        buf.push_str(line);
+        buf.push('\n');
+    }
+    buf.push_str(&format!("{}\"\"\"\n", indent));
+
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parser::Parser, schema::Schema, tokenizer::Tokenizer, validator::Validator};
+
+    use super::{print_schema, SdlDocument};
+
+    fn schema(source: &str) -> Schema {
+        Schema::from_json_value(&introspection(source).unwrap()).unwrap()
+    }
+
+    fn introspection(source: &str) -> Result<serde_json::Value, String> {
+        let mut document = SdlDocument::default();
+        document
+            .add_source(source)
+            .and_then(|_| document.into_introspection())
+            .map_err(|err| err.to_string())
+    }
+
+    fn signatures(schema: &Schema, type_name: &str) -> Vec<String> {
+        schema
+            .type_definition(type_name)
+            .unwrap()
+            .fields()
+            .iter()
+            .map(|field| field.signature())
+            .collect()
+    }
+
+    #[test]
+    fn test_type_definitions() {
+        let schema = schema(
+            r#"
+            """
+            A registered user.
+            """
+            type User implements Node & Named @key(fields: "id") {
+              id: ID!
+              name: String
+              "Most recent first."
+              posts(first: Int = 10, tags: [String!] = ["a", "b"], filter: PostFilter = { draft: false }): [Post]
+              username: String @deprecated(reason: "Use name.")
+            }
+
+            interface Node { id: ID! }
+            interface Named { name: String }
+            type Post implements Node { id: ID!, author: User }
+            union SearchResult = | User | Post
+            enum Role { ADMIN MEMBER @deprecated }
+            input PostFilter { draft: Boolean, role: Role = MEMBER }
+            scalar DateTime @specifiedBy(url: "https://example.com")
+
+            type Query { search(term: String!): [SearchResult!]! }
+            "#,
+        );
+
+        assert_eq!(
+            vec![
+                "id: ID!",
+                "name: String",
+                "posts(first: Int = 10, tags: [String!] = [\"a\", \"b\"], filter: PostFilter = {draft: false}): [Post]",
+                "username: String",
+            ],
+            signatures(&schema, "User")
+        );
+
+        let user = schema.type_definition("User").unwrap();
+        assert_eq!(Some(&"A registered user.".to_string()), user.description());
+        assert_eq!(
+            Some("Most recent first.".to_string()),
+            user.field("posts").unwrap().description
+        );
+        assert_eq!(
+            Some("Use name.".to_string()),
+            user.field("username").unwrap().deprecation_reason
+        );
+
+        let node = schema.type_definition("Node").unwrap();
+        assert_eq!(vec!["User", "Post"], node.possible_type_names());
+        let search_result = schema.type_definition("SearchResult").unwrap();
+        assert_eq!(vec!["User", "Post"], search_result.possible_type_names());
+
+        assert_eq!("Query", schema.query_root_name);
+        assert_eq!(None, schema.subscription_root_name);
+        assert!(schema.directive("deprecated").is_some());
+        assert!(schema.type_definition("Int").is_some());
+    }
+
+    #[test]
+    fn test_extensions_and_schema_definition() {
+        let schema = schema(
+            r#"
+            extend type Root { version: String }
+            schema { query: Root, mutation: Writes }
+            type Root { user: ID }
+            type Writes { touch: Boolean }
+            "#,
+        );
+
+        assert_eq!("Root", schema.query_root_name);
+        assert_eq!(Some("Writes".to_string()), schema.mutation_root_name);
+        assert_eq!(
+            vec!["user: ID", "version: String"],
+            signatures(&schema, "Root")
+        );
+    }
+
+    #[test]
+    fn test_example_schema() {
+        let schema = schema(include_str!("../misc/example.schema"));
+
+        assert_eq!(
+            vec!["title: String", "author: Author"],
+            signatures(&schema, "Book")
+        );
+    }
+
+    #[test]
+    fn test_print_schema() {
+        let sdl = r#"schema {
+  query: Root
+}
+
+"Caches the field."
+directive @cached(ttl: Int = 60) on FIELD_DEFINITION | OBJECT
+
+scalar DateTime
+
+interface Node {
+  id: ID!
+}
+
+type Post implements Node {
+  author: User
+  id: ID!
+}
+
+enum Role {
+  ADMIN
+  "Regular users."
+  MEMBER @deprecated(reason: "Everyone is an admin.")
+}
+
+type Root {
+  search(term: String!): [SearchResult!]!
+  user(
+    "The user ID."
+    id: ID!
+  ): User
+}
+
+union SearchResult = Post | User
+
+"""
+A registered user.
+Has posts.
+"""
+type User implements Node {
+  id: ID!
+  posts(first: Int = 10): [Post] @deprecated
+  role: Role
+}
+
+input UserFilter {
+  role: Role = MEMBER
+}
+"#;
+
+        assert_eq!(sdl, print_schema(&schema(sdl)));
+    }
+
+    #[test]
+    fn test_print_schema_interface_hierarchy() {
+        let sdl = r#"interface Named {
+  name: String
+}
+
+interface Node {
+  id: ID!
+}
+
+interface Owner implements Named & Node {
+  id: ID!
+  name: String
+}
+
+type Query {
+  owner: Owner
+}
+
+type Team implements Named & Node & Owner {
+  id: ID!
+  name: String
+}
+"#;
+        let schema = schema(sdl);
+
+        assert_eq!(
+            vec!["Named", "Node"],
+            schema.type_definition("Owner").unwrap().interface_names()
+        );
+        assert_eq!(sdl, print_schema(&schema));
+    }
+
+    #[test]
+    fn test_print_schema_sorts_definitions() {
+        let printed = print_schema(&schema(
+            "type Query { b: Int a(z: Int, y: Int): Int }\nenum E { B A }",
+        ));
+
+        assert_eq!(
+            "enum E {\n  A\n  B\n}\n\ntype Query {\n  a(y: Int, z: Int): Int\n  b: Int\n}\n",
+            printed
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Err("Unexpected ID, expected : at line 1, col 22".to_string()),
+            introspection("type Query { user(id ID) }").map(|_| ())
+        );
+        assert_eq!(
+            Err("Unknown type Usr at line 2, col 9".to_string()),
+            introspection("type Query {\n  user: Usr\n}").map(|_| ())
+        );
+        assert_eq!(
+            Err("Cannot extend undefined type User at line 1, col 13".to_string()),
+            introspection("extend type User { id: ID }").map(|_| ())
+        );
+        assert_eq!(
+            Err("The schema has no query root type".to_string()),
+            introspection("type User { id: ID }").map(|_| ())
+        );
+        assert_eq!(
+            Err("Type User is defined more than once at line 3, col 6".to_string()),
+            introspection(
+                "type Query { user: User }\ntype User { id: ID }\ntype User { name: String }"
+            )
+            .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_introspection_types() {
+        let schema = schema("type Query { user: User }\ntype User { id: ID }");
+        let (document, errors) = Parser::new(Tokenizer::tokenize(
+            "{ __schema { types { name fields { name type { kind } } } } __type(name: \"User\") { name } }",
+            false,
+        ))
+        .parse();
+        assert!(errors.is_empty());
+
+        assert!(Validator::validate(&schema, &document).is_empty());
+        assert_eq!(
+            vec!["kind: __TypeKind!", "name: String", "description: String"],
+            signatures(&schema, "__Type")[..3]
+        );
+        assert!(!print_schema(&schema).contains("__"));
+    }
+}
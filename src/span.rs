@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+/**
+ * Location in the source. The offset is the character position (same as the cursor position the
+ * analyzer works with), the byte offset is for slicing the source. Line and column (in characters)
+ * are 1-based for displaying.
+ */
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub offset: usize,
+    pub byte_offset: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Span {
+    pub fn new(offset: usize, byte_offset: usize, line: usize, col: usize) -> Span {
+        Span {
+            offset,
+            byte_offset,
+            line,
+            col,
+        }
+    }
+
+    // The location right after `text` when it starts at this span.
+    pub fn advance(&self, text: &str) -> Span {
+        text.chars().fold(*self, |span, ch| span.advance_char(ch))
+    }
+
+    pub fn advance_char(&self, ch: char) -> Span {
+        let byte_offset = self.byte_offset + ch.len_utf8();
+        if ch == '\n' {
+            Span::new(self.offset + 1, byte_offset, self.line + 1, 1)
+        } else {
+            Span::new(self.offset + 1, byte_offset, self.line, self.col + 1)
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, col {}", self.line, self.col)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Span;
+
+    #[test]
+    fn test_advance() {
+        let span = Span::new(3, 3, 1, 4);
+
+        assert_eq!(Span::new(6, 6, 1, 7), span.advance("abc"));
+        assert_eq!(Span::new(7, 7, 2, 2), span.advance("ab\nc"));
+        assert_eq!(Span::new(6, 7, 1, 7), span.advance("aéb"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("line 12, col 7", Span::new(200, 210, 12, 7).to_string());
+    }
+}
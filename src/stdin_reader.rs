@@ -18,7 +18,9 @@ const ESCAPE_COMBOS: &[(&[u8], KeyboardInput)] = &[
     (&[27, 55], KeyboardInput::AltDigit(7)),
     (&[27, 56], KeyboardInput::AltDigit(8)),
     (&[27, 57], KeyboardInput::AltDigit(9)),
+    (&[27, 101], KeyboardInput::AltE),
     (&[27, 102], KeyboardInput::AltF),
+    (&[27, 112], KeyboardInput::AltP),
     (&[27, 115], KeyboardInput::AltS),
     // MacOS
     (&[194, 186], KeyboardInput::AltDigit(0)),
@@ -40,16 +42,20 @@ pub enum KeyboardInput {
 
     CtrlC,
     CtrlD,
+    CtrlE,
     CtrlF,
     CtrlW,
     CtrlS,
     CtrlR,
     CtrlG,
     CtrlO,
+    CtrlP,
     CtrlSlash,
 
     AltDigit(u8),
+    AltE,
     AltF,
+    AltP,
     AltS,
 
     Left,
@@ -115,6 +121,9 @@ impl StdinReader {
             } else if buf[i] == 4 {
                 out.push(KeyboardInput::CtrlD);
                 i += 1;
+            } else if buf[i] == 5 {
+                out.push(KeyboardInput::CtrlE);
+                i += 1;
             } else if buf[i] == 6 {
                 out.push(KeyboardInput::CtrlF);
                 i += 1;
@@ -130,6 +139,9 @@ impl StdinReader {
             } else if buf[i] == 15 {
                 out.push(KeyboardInput::CtrlO);
                 i += 1;
+            } else if buf[i] == 16 {
+                out.push(KeyboardInput::CtrlP);
+                i += 1;
             } else if buf[i] == 18 {
                 out.push(KeyboardInput::CtrlR);
                 i += 1;
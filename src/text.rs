@@ -221,9 +221,9 @@ impl Text {
 
     pub fn toggle_comment(&mut self) {
         if self.has_comment_on_current_line() {
-            self.lines[self.cursor.y] = self.lines[self.cursor.y][2..].into()
+            self.lines[self.cursor.y] = self.lines[self.cursor.y][1..].into()
         } else {
-            self.lines[self.cursor.y].insert_str(0, "//");
+            self.lines[self.cursor.y].insert(0, '#');
         }
     }
 
@@ -302,51 +302,78 @@ impl Text {
             return;
         }
 
-        match &suggestion.token {
+        let elem = &suggestion.elems[idx];
+        let (start, end) = match &suggestion.token {
             Some(token) => {
-                let token_start_cursor = self.cursor_of_absolute_position(token.pos);
-                let line_len = self.lines[token_start_cursor.y].len();
-                self.lines[token_start_cursor.y].replace_range(
-                    token_start_cursor.x..(token_start_cursor.x + token.len).min(line_len),
-                    &suggestion.elems[idx].name,
-                );
-                self.cursor.x = token_start_cursor.x + suggestion.elems[idx].name.len();
-            }
-            None => {
-                self.lines[self.cursor.y].insert_str(self.cursor.x, &suggestion.elems[idx].name);
-                self.cursor.x += suggestion.elems[idx].name.len();
+                // Line and column are 1-based, the column counts characters.
+                self.cursor.y = token.span.line - 1;
+                let line = &self.lines[self.cursor.y];
+
+                (
+                    Text::byte_index_of_col(line, token.span.col - 1),
+                    Text::byte_index_of_col(line, token.span.col - 1 + token.len),
+                )
             }
+            None => (self.cursor.x, self.cursor.x),
         };
 
-        self.is_file_saved = false;
-    }
+        let line = &mut self.lines[self.cursor.y];
+        // Completing a name that already has its arguments, value or selection keeps those.
+        let is_continued = line[end..].trim_start().starts_with(['(', ':', '{']);
+        let (text, cursor_offset) = match &elem.snippet {
+            Some(snippet) if !is_continued => (&snippet.text, snippet.cursor()),
+            _ => (&elem.name, elem.name.len()),
+        };
 
-    fn cursor_of_absolute_position(&self, pos: usize) -> CoordUsize {
-        let mut y = 0usize;
-        let mut i = 0;
+        line.replace_range(start..end, text);
+        self.cursor.x = start + cursor_offset;
 
-        // Using `<` instead of `<=` to avoid end-of-line position (the X coord after the last char, which is out of index [~hidden new line]).
-        while i + self.lines[y].len() < pos {
-            i += self.lines[y].len() + 1 /* new line from tokenizer */;
-            y += 1;
-        }
+        self.is_file_saved = false;
+    }
 
-        CoordUsize { x: pos - i, y }
+    // Byte index of the 0-based character column, the line length past the end of the line.
+    fn byte_index_of_col(line: &str, col: usize) -> usize {
+        line.char_indices()
+            .nth(col)
+            .map(|(i, _)| i)
+            .unwrap_or(line.len())
     }
 
     fn has_comment_on_current_line(&self) -> bool {
-        if self.lines[self.cursor.y].len() < 2 {
-            return false;
-        }
-
-        &self.lines[self.cursor.y][0..=1] == "//"
+        self.lines[self.cursor.y].starts_with('#')
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::{
+        analyzer::{Snippet, Suggestion, SuggestionElem},
+        tokenizer::Tokenizer,
+        util::CoordUsize,
+    };
+
     use super::Text;
 
+    fn user_suggestion(token_source: &str) -> Suggestion {
+        let mut snippet = Snippet::new("user(id: ");
+        snippet.placeholder();
+        snippet.push(") {  }");
+
+        Suggestion {
+            elems: vec![SuggestionElem {
+                name: "user".to_string(),
+                kind: "Obj<User>".to_string(),
+                fuzzy_match_positions: vec![],
+                is_deprecated: false,
+                score: 0,
+                snippet: Some(snippet),
+            }],
+            token: Tokenizer::tokenize(token_source, false)
+                .into_iter()
+                .find(|token| token.original == "us"),
+        }
+    }
+
     #[test]
     fn test_minimal_flow() {
         let mut text = Text::new(None);
@@ -364,4 +391,53 @@ mod test {
 
         assert_eq!(vec!["abdc"], text.lines);
     }
+
+    #[test]
+    fn test_apply_suggestion_snippet() {
+        let mut text = Text::new(None);
+        text.lines = vec!["{ us }".to_string()];
+        text.cursor.x = 4;
+
+        text.apply_suggestion(&user_suggestion("{ us }"), 0);
+
+        assert_eq!(vec!["{ user(id: ) {  } }"], text.lines);
+        assert_eq!(11, text.cursor.x);
+    }
+
+    #[test]
+    fn test_apply_suggestion_keeps_existing_arguments() {
+        let mut text = Text::new(None);
+        text.lines = vec!["{ us(id: 1) { id } }".to_string()];
+        text.cursor.x = 4;
+
+        text.apply_suggestion(&user_suggestion("{ us(id: 1) { id } }"), 0);
+
+        assert_eq!(vec!["{ user(id: 1) { id } }"], text.lines);
+        assert_eq!(6, text.cursor.x);
+    }
+
+    #[test]
+    fn test_apply_suggestion_on_a_later_line() {
+        let mut text = Text::new(None);
+        text.lines = vec!["{".to_string(), "  us(id: 1)".to_string(), "}".to_string()];
+        text.cursor = CoordUsize { x: 4, y: 1 };
+
+        text.apply_suggestion(&user_suggestion("{\n  us(id: 1)\n}"), 0);
+
+        assert_eq!(vec!["{", "  user(id: 1)", "}"], text.lines);
+        assert_eq!(6, text.cursor.x);
+        assert_eq!(1, text.cursor.y);
+    }
+
+    #[test]
+    fn test_apply_suggestion_after_multibyte_chars() {
+        let mut text = Text::new(None);
+        text.lines = vec!["{ a(s: \"éő\") us }".to_string()];
+        text.cursor.x = 18;
+
+        text.apply_suggestion(&user_suggestion("{ a(s: \"éő\") us }"), 0);
+
+        assert_eq!(vec!["{ a(s: \"éő\") user(id: ) {  } }"], text.lines);
+        assert_eq!(24, text.cursor.x);
+    }
 }
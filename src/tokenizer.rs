@@ -1,32 +1,40 @@
 use std::ops::RangeInclusive;
 
+use crate::span::Span;
+
 pub const COLOR_INVALID: u8 = 91;
+// Ignored like whitespace, see: https://spec.graphql.org/October2021/#UnicodeBOM
+const BYTE_ORDER_MARK: char = '\u{feff}';
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Token {
     pub kind: TokenKind,
-    pub pos: usize,
+    pub span: Span,
     pub len: usize,
     pub original: String,
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, pos: usize, len: usize, original: String) -> Token {
+    pub fn new(kind: TokenKind, span: Span, len: usize, original: String) -> Token {
         Token {
             kind,
-            pos,
+            span,
             len,
             original,
         }
     }
 
-    // The position after the token (aka non inclusive).
-    pub fn end_pos(&self) -> usize {
-        self.pos + self.len
+    pub fn pos(&self) -> usize {
+        self.span.offset
+    }
+
+    // The location after the token (aka non inclusive).
+    pub fn end_span(&self) -> Span {
+        self.span.advance(&self.original)
     }
 
     pub fn range_inclusive(&self) -> RangeInclusive<usize> {
-        self.pos..=self.pos + self.len
+        self.pos()..=self.pos() + self.len
     }
 }
 
@@ -41,10 +49,17 @@ pub enum TokenKind {
     Colon,        // :
     Comma,        // ,
     Ellipsis,     // ...
+    Dollar,       // $
+    Bang,         // !
+    Equals,       // =
+    At,           // @
+    Pipe,         // |
+    Amp,          // &
     LineBreak,
     Number(String),
     Keyword(String),
-    Str(String),
+    Str(String),      // Unescaped value.
+    BlockStr(String), // Block string value with the common indentation removed.
     Whitespace(String),
     Invalid(String),
     MagicValue(String),
@@ -56,10 +71,17 @@ impl TokenKind {
         match self {
             TokenKind::CloseBrace | TokenKind::OpenBrace => 92,
             TokenKind::CloseParen | TokenKind::OpenParen => 96,
-            TokenKind::Colon | TokenKind::Comma => 97,
+            TokenKind::Colon
+            | TokenKind::Comma
+            | TokenKind::Bang
+            | TokenKind::Equals
+            | TokenKind::Pipe
+            | TokenKind::Amp => 97,
+            TokenKind::Dollar => 96,
+            TokenKind::At => 35,
             TokenKind::Keyword(_) => 93,
             TokenKind::Number(_) => 95,
-            TokenKind::Str(_) => 94,
+            TokenKind::Str(_) | TokenKind::BlockStr(_) => 94,
             TokenKind::Invalid(_) => COLOR_INVALID,
             TokenKind::MagicValue(_) => 44,
             TokenKind::Comment => 90,
@@ -71,7 +93,7 @@ impl TokenKind {
 pub struct Tokenizer;
 
 impl Tokenizer {
-    pub fn tokenize_lines(source: &Vec<String>, record_whitespace: bool) -> Vec<Token> {
+    pub fn tokenize_lines(source: &[String], record_whitespace: bool) -> Vec<Token> {
         Tokenizer::tokenize(&source.join("\n"), record_whitespace)
     }
 
@@ -84,44 +106,73 @@ impl Tokenizer {
         while pos < chars.len() {
             match chars[pos] {
                 '{' => {
-                    tokens.push(Token::new(TokenKind::OpenBrace, pos, 1, "{".into()));
+                    tokens.push(Tokenizer::token(TokenKind::OpenBrace, pos, 1, "{".into()));
                     pos += 1;
                 }
                 '}' => {
-                    tokens.push(Token::new(TokenKind::CloseBrace, pos, 1, "}".into()));
+                    tokens.push(Tokenizer::token(TokenKind::CloseBrace, pos, 1, "}".into()));
                     pos += 1;
                 }
                 '(' => {
-                    tokens.push(Token::new(TokenKind::OpenParen, pos, 1, "(".into()));
+                    tokens.push(Tokenizer::token(TokenKind::OpenParen, pos, 1, "(".into()));
                     pos += 1;
                 }
                 ')' => {
-                    tokens.push(Token::new(TokenKind::CloseParen, pos, 1, ")".into()));
+                    tokens.push(Tokenizer::token(TokenKind::CloseParen, pos, 1, ")".into()));
                     pos += 1;
                 }
                 '[' => {
-                    tokens.push(Token::new(TokenKind::OpenBracket, pos, 1, "[".into()));
+                    tokens.push(Tokenizer::token(TokenKind::OpenBracket, pos, 1, "[".into()));
                     pos += 1;
                 }
                 ']' => {
-                    tokens.push(Token::new(TokenKind::CloseBracket, pos, 1, "]".into()));
+                    tokens.push(Tokenizer::token(
+                        TokenKind::CloseBracket,
+                        pos,
+                        1,
+                        "]".into(),
+                    ));
                     pos += 1;
                 }
                 ':' => {
-                    tokens.push(Token::new(TokenKind::Colon, pos, 1, ":".into()));
+                    tokens.push(Tokenizer::token(TokenKind::Colon, pos, 1, ":".into()));
                     pos += 1;
                 }
                 ',' => {
-                    tokens.push(Token::new(TokenKind::Comma, pos, 1, ",".into()));
+                    tokens.push(Tokenizer::token(TokenKind::Comma, pos, 1, ",".into()));
+                    pos += 1;
+                }
+                '$' => {
+                    tokens.push(Tokenizer::token(TokenKind::Dollar, pos, 1, "$".into()));
+                    pos += 1;
+                }
+                '!' => {
+                    tokens.push(Tokenizer::token(TokenKind::Bang, pos, 1, "!".into()));
+                    pos += 1;
+                }
+                '=' => {
+                    tokens.push(Tokenizer::token(TokenKind::Equals, pos, 1, "=".into()));
+                    pos += 1;
+                }
+                '@' => {
+                    tokens.push(Tokenizer::token(TokenKind::At, pos, 1, "@".into()));
+                    pos += 1;
+                }
+                '|' => {
+                    tokens.push(Tokenizer::token(TokenKind::Pipe, pos, 1, "|".into()));
+                    pos += 1;
+                }
+                '&' => {
+                    tokens.push(Tokenizer::token(TokenKind::Amp, pos, 1, "&".into()));
                     pos += 1;
                 }
                 '\n' => {
                     if record_whitespace {
-                        tokens.push(Token::new(TokenKind::LineBreak, pos, 1, "\n".into()));
+                        tokens.push(Tokenizer::token(TokenKind::LineBreak, pos, 1, "\n".into()));
                     }
                     pos += 1;
                 }
-                ' ' | '\t' => {
+                ' ' | '\t' | '\r' | BYTE_ORDER_MARK => {
                     if record_whitespace {
                         tokens.push(Tokenizer::consume_whitespace(&chars, &mut pos));
                     } else {
@@ -132,22 +183,23 @@ impl Tokenizer {
                     tokens.push(Tokenizer::consume_keyword(&chars, &mut pos))
                 }
                 '0'..='9' | '-' => tokens.push(Tokenizer::consume_number(&chars, &mut pos)),
-                '"' => tokens.push(Tokenizer::consume_string(&chars, &mut pos)),
+                '"' => {
+                    if Tokenizer::is_block_quote_at(&chars, pos) {
+                        tokens.push(Tokenizer::consume_block_string(&chars, &mut pos))
+                    } else {
+                        tokens.push(Tokenizer::consume_string(&chars, &mut pos))
+                    }
+                }
                 '<' => tokens.push(Tokenizer::consume_magic_value(&chars, &mut pos)),
                 '.' => tokens.push(Tokenizer::consume_ellipsis(&chars, &mut pos)),
-                '/' => match Tokenizer::consume_comment(&chars, &mut pos) {
-                    token @ Token {
-                        kind: TokenKind::Comment,
-                        ..
-                    } => {
-                        if record_whitespace {
-                            tokens.push(token);
-                        }
+                '#' => {
+                    let token = Tokenizer::consume_comment(&chars, &mut pos);
+                    if record_whitespace {
+                        tokens.push(token);
                     }
-                    token @ _ => tokens.push(token),
-                },
+                }
                 _ => {
-                    tokens.push(Token::new(
+                    tokens.push(Tokenizer::token(
                         TokenKind::Invalid("Invalid character".into()),
                         pos,
                         1,
@@ -158,47 +210,56 @@ impl Tokenizer {
             }
         }
 
+        Tokenizer::locate(&mut tokens, &chars);
+
         tokens
     }
 
-    fn consume_ellipsis(chars: &Vec<char>, pos: &mut usize) -> Token {
+    // Byte offset, line and column are filled in by `locate` once all tokens are known.
+    fn token(kind: TokenKind, pos: usize, len: usize, original: String) -> Token {
+        Token::new(kind, Span::new(pos, 0, 0, 0), len, original)
+    }
+
+    // Assigns the byte offset, line and column of all (position ordered) tokens in one pass over the source.
+    fn locate(tokens: &mut [Token], chars: &[char]) {
+        let mut span = Span::new(0, 0, 1, 1);
+
+        for token in tokens {
+            while span.offset < token.pos() {
+                span = span.advance_char(chars[span.offset]);
+            }
+            token.span = span;
+        }
+    }
+
+    fn consume_ellipsis(chars: &[char], pos: &mut usize) -> Token {
         if chars.len() < *pos + 3 {
             *pos += 1;
-            return Token::new(
+            return Tokenizer::token(
                 TokenKind::Invalid("Invalid ellipsis lenght".to_string()),
                 *pos - 1,
                 1,
-                chars[*pos].to_string(),
+                chars[*pos - 1].to_string(),
             );
         }
 
         if chars[*pos] != '.' || chars[*pos + 1] != '.' || chars[*pos + 2] != '.' {
             *pos += 1;
-            return Token::new(
+            return Tokenizer::token(
                 TokenKind::Invalid("Invalid ellipsis chars".to_string()),
                 *pos - 1,
                 1,
-                chars[*pos].to_string(),
+                chars[*pos - 1].to_string(),
             );
         }
 
         *pos += 3;
-        Token::new(TokenKind::Ellipsis, *pos - 3, 3, "...".to_string())
+        Tokenizer::token(TokenKind::Ellipsis, *pos - 3, 3, "...".to_string())
     }
 
-    fn consume_comment(chars: &Vec<char>, pos: &mut usize) -> Token {
+    fn consume_comment(chars: &[char], pos: &mut usize) -> Token {
         let pos_orig = *pos;
 
-        if chars.len() < *pos + 2 || chars[*pos] != '/' || chars[*pos + 1] != '/' {
-            *pos += 1;
-            return Token::new(
-                TokenKind::Invalid("Invalid comment start".to_string()),
-                *pos - 1,
-                1,
-                chars[*pos - 1].to_string(),
-            );
-        }
-
         loop {
             if *pos >= chars.len() {
                 break;
@@ -210,7 +271,7 @@ impl Tokenizer {
             *pos += 1;
         }
 
-        Token::new(
+        Tokenizer::token(
             TokenKind::Comment,
             pos_orig,
             *pos - pos_orig,
@@ -218,7 +279,7 @@ impl Tokenizer {
         )
     }
 
-    fn consume_keyword(chars: &Vec<char>, pos: &mut usize) -> Token {
+    fn consume_keyword(chars: &[char], pos: &mut usize) -> Token {
         let mut fragment = String::new();
 
         while *pos < chars.len() {
@@ -231,7 +292,7 @@ impl Tokenizer {
         }
         let fragment_len = fragment.len();
 
-        Token::new(
+        Tokenizer::token(
             TokenKind::Keyword(fragment.clone()),
             *pos - fragment_len,
             fragment_len,
@@ -239,11 +300,12 @@ impl Tokenizer {
         )
     }
 
-    fn consume_whitespace(chars: &Vec<char>, pos: &mut usize) -> Token {
+    fn consume_whitespace(chars: &[char], pos: &mut usize) -> Token {
+        let start_pos = *pos;
         let mut fragment = String::new();
 
         while *pos < chars.len() {
-            if !(chars[*pos] == ' ' || chars[*pos] == '\t') {
+            if !matches!(chars[*pos], ' ' | '\t' | '\r' | BYTE_ORDER_MARK) {
                 break;
             }
 
@@ -251,85 +313,216 @@ impl Tokenizer {
             *pos += 1;
         }
 
-        let fragment_len = fragment.len();
-
-        Token::new(
+        // Counted in chars, the byte order mark is longer in bytes.
+        Tokenizer::token(
             TokenKind::Whitespace(fragment.clone()),
-            *pos - fragment_len,
-            fragment_len,
+            start_pos,
+            *pos - start_pos,
             fragment,
         )
     }
 
-    fn consume_number(chars: &Vec<char>, pos: &mut usize) -> Token {
-        let mut fragment = String::new();
-
-        while *pos < chars.len() {
-            if !(chars[*pos].is_ascii_digit() || chars[*pos] == '.' || chars[*pos] == '-') {
-                break;
+    // IntValue or FloatValue: -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn consume_number(chars: &[char], pos: &mut usize) -> Token {
+        let start_pos = *pos;
+        let is_next =
+            |pos: usize, expected: &[char]| pos < chars.len() && expected.contains(&chars[pos]);
+        let consume_digits = |pos: &mut usize| {
+            let digits_start = *pos;
+            while *pos < chars.len() && chars[*pos].is_ascii_digit() {
+                *pos += 1;
             }
+            *pos > digits_start
+        };
 
-            fragment.push(chars[*pos]);
+        if is_next(*pos, &['-']) {
             *pos += 1;
         }
+        let mut is_valid = consume_digits(pos);
 
-        let fragment_len = fragment.len();
+        if is_next(*pos, &['.']) {
+            *pos += 1;
+            is_valid &= consume_digits(pos);
+        }
 
-        Token::new(
-            TokenKind::Number(fragment.clone()),
-            *pos - fragment_len,
-            fragment_len,
-            fragment,
-        )
-    }
+        if is_next(*pos, &['e', 'E']) {
+            *pos += 1;
+            if is_next(*pos, &['+', '-']) {
+                *pos += 1;
+            }
+            is_valid &= consume_digits(pos);
+        }
 
-    fn consume_string(chars: &Vec<char>, pos: &mut usize) -> Token {
-        let mut original = String::new();
-        let mut fragment = String::new();
+        let fragment = chars[start_pos..*pos].iter().collect::<String>();
+        let kind = if is_valid {
+            TokenKind::Number(fragment.clone())
+        } else {
+            TokenKind::Invalid("Invalid number".into())
+        };
 
-        original.push(chars[*pos]);
+        Tokenizer::token(kind, start_pos, *pos - start_pos, fragment)
+    }
+
+    fn consume_string(chars: &[char], pos: &mut usize) -> Token {
+        let start_pos = *pos;
+        let mut value = String::new();
 
         *pos += 1; // Quote.
 
         let mut has_closing_quote = false;
+        let mut has_invalid_escape = false;
 
         while *pos < chars.len() {
-            if chars[*pos] == '"' {
-                has_closing_quote = true;
-                break;
+            match chars[*pos] {
+                '"' => {
+                    has_closing_quote = true;
+                    *pos += 1; // Closing quote.
+                    break;
+                }
+                '\n' => break,
+                '\\' => match Tokenizer::consume_escape_sequence(chars, pos) {
+                    Some(ch) => value.push(ch),
+                    None => has_invalid_escape = true,
+                },
+                ch => {
+                    value.push(ch);
+                    *pos += 1;
+                }
             }
-            if chars[*pos] == '\n' {
+        }
+
+        let kind = if !has_closing_quote {
+            TokenKind::Invalid("Invalid string token".into())
+        } else if has_invalid_escape {
+            TokenKind::Invalid("Invalid escape sequence".into())
+        } else {
+            TokenKind::Str(value)
+        };
+
+        Tokenizer::token(
+            kind,
+            start_pos,
+            *pos - start_pos,
+            chars[start_pos..*pos].iter().collect(),
+        )
+    }
+
+    // Consumes an escape sequence starting with a backslash. A line break is not consumed so it
+    // can terminate the (invalid) string.
+    fn consume_escape_sequence(chars: &[char], pos: &mut usize) -> Option<char> {
+        *pos += 1; // Backslash.
+
+        if *pos >= chars.len() || chars[*pos] == '\n' {
+            return None;
+        }
+
+        let escaped = chars[*pos];
+        *pos += 1;
+
+        match escaped {
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '/' => Some('/'),
+            'b' => Some('\u{8}'),
+            'f' => Some('\u{c}'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'u' => {
+                let hex_end = (*pos + 4).min(chars.len());
+                let hex = chars[*pos..hex_end].iter().collect::<String>();
+                if hex.len() != 4 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                    return None;
+                }
+
+                *pos = hex_end;
+                u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_block_quote_at(chars: &[char], pos: usize) -> bool {
+        chars.len() >= pos + 3 && chars[pos..pos + 3] == ['"', '"', '"']
+    }
+
+    fn consume_block_string(chars: &[char], pos: &mut usize) -> Token {
+        let start_pos = *pos;
+        let mut raw_value = String::new();
+
+        *pos += 3; // Opening quotes.
+
+        let mut has_closing_quotes = false;
+
+        while *pos < chars.len() {
+            if Tokenizer::is_block_quote_at(chars, *pos) {
+                has_closing_quotes = true;
+                *pos += 3; // Closing quotes.
                 break;
             }
 
-            fragment.push(chars[*pos]);
-            original.push(chars[*pos]);
+            // The only escape sequence in block strings: \""".
+            if chars[*pos] == '\\' && Tokenizer::is_block_quote_at(chars, *pos + 1) {
+                raw_value.push_str("\"\"\"");
+                *pos += 4;
+                continue;
+            }
+
+            raw_value.push(chars[*pos]);
             *pos += 1;
         }
 
-        if has_closing_quote {
-            original.push(chars[*pos]);
-            *pos += 1; // Closing quote;
-            let fragment_len = fragment.len();
-
-            Token::new(
-                TokenKind::Str(fragment.clone()),
-                *pos - fragment_len - 2,
-                fragment_len + 2,
-                original,
-            )
+        let kind = if has_closing_quotes {
+            TokenKind::BlockStr(Tokenizer::block_string_value(&raw_value))
         } else {
-            let original_len = original.len();
-            Token::new(
-                TokenKind::Invalid("Invalid string token".into()),
-                *pos - original_len,
-                original_len,
-                original,
-            )
+            TokenKind::Invalid("Invalid block string token".into())
+        };
+
+        Tokenizer::token(
+            kind,
+            start_pos,
+            *pos - start_pos,
+            chars[start_pos..*pos].iter().collect(),
+        )
+    }
+
+    // See: https://spec.graphql.org/October2021/#BlockStringValue()
+    fn block_string_value(raw_value: &str) -> String {
+        let lines = raw_value.lines().collect::<Vec<_>>();
+        let indent_len = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+
+        let common_indent = lines
+            .iter()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indent_len(line))
+            .min()
+            .unwrap_or(0);
+
+        let mut lines = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                if i == 0 {
+                    line
+                } else {
+                    &line[common_indent.min(indent_len(line))..]
+                }
+            })
+            .collect::<Vec<_>>();
+
+        while lines.first().is_some_and(|line| line.trim().is_empty()) {
+            lines.remove(0);
+        }
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
         }
+
+        lines.join("\n")
     }
 
-    fn consume_magic_value(chars: &Vec<char>, pos: &mut usize) -> Token {
+    fn consume_magic_value(chars: &[char], pos: &mut usize) -> Token {
+        let start_pos = *pos;
         let mut original = String::new();
         let mut fragment = String::new();
 
@@ -356,20 +549,18 @@ impl Tokenizer {
         if has_closing_angle_quote {
             original.push(chars[*pos]);
             *pos += 1; // Closing quote;
-            let fragment_len = fragment.len();
 
-            Token::new(
-                TokenKind::MagicValue(fragment.clone()),
-                *pos - fragment_len - 2,
-                fragment_len + 2,
+            Tokenizer::token(
+                TokenKind::MagicValue(fragment),
+                start_pos,
+                *pos - start_pos,
                 original,
             )
         } else {
-            let original_len = original.len();
-            Token::new(
+            Tokenizer::token(
                 TokenKind::Invalid("Invalid magic value token".into()),
-                *pos - original_len,
-                original_len,
+                start_pos,
+                *pos - start_pos,
                 original,
             )
         }
@@ -378,6 +569,7 @@ impl Tokenizer {
 
 #[cfg(test)]
 mod test {
+    use crate::span::Span;
     use crate::tokenizer::{Token, TokenKind};
 
     use super::Tokenizer;
@@ -457,23 +649,48 @@ mod test {
         assert_eq!(4, tokens.len());
 
         assert_eq!(
-            Token::new(TokenKind::OpenBrace, 3, 1, "{".into()),
+            Token::new(TokenKind::OpenBrace, Span::new(3, 3, 1, 4), 1, "{".into()),
             tokens[0]
         );
         assert_eq!(
-            Token::new(TokenKind::Str("hello".into()), 5, 7, "\"hello\"".into()),
+            Token::new(
+                TokenKind::Str("hello".into()),
+                Span::new(5, 5, 1, 6),
+                7,
+                "\"hello\"".into()
+            ),
             tokens[1]
         );
         assert_eq!(
-            Token::new(TokenKind::Number("123".to_string()), 13, 3, "123".into()),
+            Token::new(
+                TokenKind::Number("123".to_string()),
+                Span::new(13, 13, 1, 14),
+                3,
+                "123".into()
+            ),
             tokens[2]
         );
         assert_eq!(
-            Token::new(TokenKind::CloseBrace, 18, 1, "}".into()),
+            Token::new(
+                TokenKind::CloseBrace,
+                Span::new(18, 18, 3, 1),
+                1,
+                "}".into()
+            ),
             tokens[3]
         );
     }
 
+    #[test]
+    fn test_byte_offset() {
+        let source = "{ a(b: \"é\") }\n\"ő\" c";
+        let tokens = Tokenizer::tokenize(source, false);
+
+        let c = tokens.last().unwrap();
+        assert_eq!(Span::new(18, 20, 2, 5), c.span);
+        assert_eq!("c", &source[c.span.byte_offset..]);
+    }
+
     #[test]
     fn test_comma() {
         let tokens = Tokenizer::tokenize("foo: \"bar\", bar: 123", false);
@@ -494,6 +711,68 @@ mod test {
         assert_eq!("\"hello  ".to_string(), tokens[0].original);
     }
 
+    #[test]
+    fn test_string_escape_sequences() {
+        let tokens = Tokenizer::tokenize(r#""say \"hi\"\n\t\\ \u00e9\/""#, false);
+
+        assert_eq!(1, tokens.len());
+        assert_eq!(
+            TokenKind::Str("say \"hi\"\n\t\\ \u{e9}/".into()),
+            tokens[0].kind
+        );
+        assert_eq!(0, tokens[0].pos());
+        assert_eq!(27, tokens[0].len);
+    }
+
+    #[test]
+    fn test_string_invalid_escape_sequence() {
+        let tokens = Tokenizer::tokenize(r#"{ a(b: "\x") }"#, false);
+
+        assert_eq!(
+            TokenKind::Invalid("Invalid escape sequence".into()),
+            tokens[5].kind
+        );
+        assert_eq!("\"\\x\"", tokens[5].original);
+        assert_eq!(TokenKind::CloseParen, tokens[6].kind);
+    }
+
+    #[test]
+    fn test_block_string() {
+        let tokens = Tokenizer::tokenize(
+            "post(body: \"\"\"\n    Hello,\n      \"World\" \\\"\"\"\n    \"\"\")",
+            true,
+        );
+
+        assert_eq!(7, tokens.len());
+        assert_eq!(
+            TokenKind::BlockStr("Hello,\n  \"World\" \"\"\"".into()),
+            tokens[5].kind
+        );
+        assert_eq!(11, tokens[5].pos());
+        assert_eq!(41, tokens[5].len);
+        assert_eq!(TokenKind::CloseParen, tokens[6].kind);
+    }
+
+    #[test]
+    fn test_empty_string_is_not_block_string() {
+        let tokens = Tokenizer::tokenize("\"\" \"\"\"\"\"\"", false);
+
+        assert_eq!(2, tokens.len());
+        assert_eq!(TokenKind::Str("".into()), tokens[0].kind);
+        assert_eq!(TokenKind::BlockStr("".into()), tokens[1].kind);
+    }
+
+    #[test]
+    fn test_invalid_block_string() {
+        let tokens = Tokenizer::tokenize("\"\"\"hello\n}", true);
+
+        assert_eq!(1, tokens.len());
+        assert_eq!(
+            TokenKind::Invalid("Invalid block string token".into()),
+            tokens[0].kind
+        );
+    }
+
     #[test]
     fn test_capital_and_snake_keyword() {
         let tokens = Tokenizer::tokenize("HELLO_WORLD", false);
@@ -516,6 +795,49 @@ mod test {
         assert_eq!(TokenKind::LineBreak, tokens[2].kind);
     }
 
+    #[test]
+    fn test_name_with_digits() {
+        let tokens = Tokenizer::tokenize("{ user2 _id3 }", false);
+        assert_eq!(4, tokens.len());
+        assert_eq!(TokenKind::Keyword("user2".into()), tokens[1].kind);
+        assert_eq!(TokenKind::Keyword("_id3".into()), tokens[2].kind);
+    }
+
+    #[test]
+    fn test_union_punctuators() {
+        let tokens = Tokenizer::tokenize("union A = | B | C type D implements E & F", false);
+        assert_eq!(TokenKind::Pipe, tokens[3].kind);
+        assert_eq!(TokenKind::Pipe, tokens[5].kind);
+        assert_eq!(TokenKind::Amp, tokens[11].kind);
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let tokens = Tokenizer::tokenize("\u{feff}{ a }", false);
+        assert_eq!(3, tokens.len());
+        assert_eq!(TokenKind::OpenBrace, tokens[0].kind);
+        assert_eq!(1, tokens[0].pos());
+
+        // The editor records the whitespace.
+        let tokens = Tokenizer::tokenize("\u{feff}{ a }", true);
+        assert_eq!(
+            TokenKind::Whitespace("\u{feff}".to_string()),
+            tokens[0].kind
+        );
+        assert_eq!((0, 1), (tokens[0].pos(), tokens[0].len));
+        assert_eq!(TokenKind::OpenBrace, tokens[1].kind);
+        assert_eq!(1, tokens[1].pos());
+    }
+
+    #[test]
+    fn test_exponent() {
+        let tokens = Tokenizer::tokenize("1e10 -1.5E-3 2e", false);
+        assert_eq!(3, tokens.len());
+        assert_eq!(TokenKind::Number("1e10".to_string()), tokens[0].kind);
+        assert_eq!(TokenKind::Number("-1.5E-3".to_string()), tokens[1].kind);
+        assert_eq!(TokenKind::Invalid("Invalid number".into()), tokens[2].kind);
+    }
+
     #[test]
     fn test_float() {
         let tokens = Tokenizer::tokenize("12.12 0.23", false);
@@ -540,6 +862,10 @@ mod test {
             TokenKind::MagicValue("command:params".to_string()),
             tokens[4].kind
         );
+
+        let tokens = Tokenizer::tokenize("<word:é> 1", false);
+        assert_eq!((0, 8), (tokens[0].pos(), tokens[0].len));
+        assert_eq!(9, tokens[1].pos());
     }
 
     #[test]
@@ -549,16 +875,65 @@ mod test {
         assert_eq!(TokenKind::Ellipsis, tokens[0].kind);
     }
 
+    #[test]
+    fn test_incomplete_ellipsis_at_the_end() {
+        let tokens = Tokenizer::tokenize("{ a ..", false);
+        assert_eq!(4, tokens.len());
+        assert!(matches!(tokens[2].kind, TokenKind::Invalid(_)));
+        assert_eq!(".", tokens[2].original);
+        assert_eq!(5, tokens[3].pos());
+
+        let tokens = Tokenizer::tokenize("{ a .", false);
+        assert_eq!(3, tokens.len());
+        assert!(matches!(tokens[2].kind, TokenKind::Invalid(_)));
+        assert_eq!(".", tokens[2].original);
+    }
+
+    #[test]
+    fn test_variable_definition() {
+        let tokens = Tokenizer::tokenize("($id: ID! = 1)", false);
+        assert_eq!(9, tokens.len());
+        assert_eq!(TokenKind::Dollar, tokens[1].kind);
+        assert_eq!(TokenKind::Keyword("id".into()), tokens[2].kind);
+        assert_eq!(TokenKind::Bang, tokens[5].kind);
+        assert_eq!(TokenKind::Equals, tokens[6].kind);
+    }
+
+    #[test]
+    fn test_directive() {
+        let tokens = Tokenizer::tokenize("user @include(if: $flag)", false);
+        assert_eq!(9, tokens.len());
+        assert_eq!(TokenKind::At, tokens[1].kind);
+        assert_eq!(TokenKind::Keyword("include".into()), tokens[2].kind);
+    }
+
     #[test]
     fn test_comment() {
-        let tokens = Tokenizer::tokenize("foo\n// comment\nbar", true);
+        let tokens = Tokenizer::tokenize("foo\n# comment\nbar", true);
         assert_eq!(5, tokens.len());
         assert_eq!(TokenKind::Comment, tokens[2].kind);
     }
 
+    #[test]
+    fn test_trailing_comment() {
+        let tokens = Tokenizer::tokenize("{ user # \"comment\" {\n}", true);
+        assert_eq!(7, tokens.len());
+        assert_eq!(TokenKind::Comment, tokens[4].kind);
+        assert_eq!("# \"comment\" {", tokens[4].original);
+    }
+
+    #[test]
+    fn test_slash_comment_is_invalid() {
+        let tokens = Tokenizer::tokenize("// comment", false);
+        assert_eq!(
+            TokenKind::Invalid("Invalid character".into()),
+            tokens[0].kind
+        );
+    }
+
     #[test]
     fn test_comment_is_skipped_when_no_whitespace() {
-        let tokens = Tokenizer::tokenize("foo\n// comment\nbar", false);
+        let tokens = Tokenizer::tokenize("foo\n# comment\nbar", false);
         assert_eq!(2, tokens.len());
         assert_eq!(TokenKind::Keyword("foo".to_string()), tokens[0].kind);
         assert_eq!(TokenKind::Keyword("bar".to_string()), tokens[1].kind);
@@ -1,5 +1,10 @@
 use rand::prelude::*;
-use std::{fmt::Display, fs::File, io::Read};
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
 
 #[derive(Debug, Clone)]
 pub struct CoordUsize {
@@ -31,6 +36,7 @@ pub fn trim_coloured_string_list(elems: Vec<(String, Option<usize>)>, max_len: u
     out_elems.join("")
 }
 
+#[allow(unused)]
 pub fn fuzzy_match(subject: &str, pattern: &str) -> Option<Vec<usize>> {
     let subject = subject.to_lowercase();
     let pattern = pattern.to_lowercase();
@@ -57,13 +63,130 @@ pub fn fuzzy_match(subject: &str, pattern: &str) -> Option<Vec<usize>> {
     None
 }
 
+// Scores of `fuzzy_match_scored`, per matched character unless noted otherwise.
+const FUZZY_SCORE_MATCH: i32 = 16;
+// Matching the first character of the subject.
+const FUZZY_BONUS_PREFIX: i32 = 24;
+// Matching the start of a word: after `_` / `-` or a camelCase hump.
+const FUZZY_BONUS_WORD_BOUNDARY: i32 = 12;
+// Following the previous match directly.
+const FUZZY_BONUS_CONSECUTIVE: i32 = 12;
+const FUZZY_BONUS_SAME_CASE: i32 = 2;
+// Per skipped subject character between two matches.
+const FUZZY_PENALTY_GAP: i32 = 2;
+// Per skipped subject character before the first match.
+const FUZZY_PENALTY_LEADING_GAP: i32 = 1;
+// Per subject character left unmatched, shorter names rank higher.
+const FUZZY_PENALTY_LENGTH: i32 = 1;
+
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+/**
+ * Like `fuzzy_match`, but picks the best scoring positions instead of the first ones and scores them,
+ * so suggestions can be ranked. The empty pattern scores 0 for all subjects.
+ */
+pub fn fuzzy_match_scored(subject: &str, pattern: &str) -> Option<FuzzyMatch> {
+    let subject = subject.chars().collect::<Vec<_>>();
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: vec![],
+        });
+    }
+    if pattern.len() > subject.len() {
+        return None;
+    }
+
+    let char_score = |subject_i: usize, pattern_ch: char| -> Option<i32> {
+        let subject_ch = subject[subject_i];
+        if !subject_ch.to_lowercase().eq(pattern_ch.to_lowercase()) {
+            return None;
+        }
+
+        let mut score = FUZZY_SCORE_MATCH;
+        if subject_i == 0 {
+            score += FUZZY_BONUS_PREFIX;
+        } else {
+            let previous_ch = subject[subject_i - 1];
+            if previous_ch == '_'
+                || previous_ch == '-'
+                || (previous_ch.is_lowercase() && subject_ch.is_uppercase())
+            {
+                score += FUZZY_BONUS_WORD_BOUNDARY;
+            }
+        }
+        if subject_ch == pattern_ch {
+            score += FUZZY_BONUS_SAME_CASE;
+        }
+
+        Some(score)
+    };
+
+    // best[pattern_i][subject_i]: best score of matching the pattern up to `pattern_i`, which is
+    // matched at `subject_i`, with the previous match position to walk back the positions.
+    let mut best: Vec<Vec<Option<(i32, usize)>>> = vec![vec![None; subject.len()]; pattern.len()];
+    for (pattern_i, pattern_ch) in pattern.iter().enumerate() {
+        for subject_i in pattern_i..subject.len() {
+            let Some(score) = char_score(subject_i, *pattern_ch) else {
+                continue;
+            };
+
+            if pattern_i == 0 {
+                best[0][subject_i] = Some((
+                    score - FUZZY_PENALTY_LEADING_GAP * subject_i as i32,
+                    subject_i,
+                ));
+                continue;
+            }
+
+            best[pattern_i][subject_i] = (pattern_i - 1..subject_i)
+                .filter_map(|previous_i| {
+                    best[pattern_i - 1][previous_i].map(|(previous_score, _)| {
+                        let gap = (subject_i - previous_i - 1) as i32;
+                        let adjacency = if gap == 0 {
+                            FUZZY_BONUS_CONSECUTIVE
+                        } else {
+                            -FUZZY_PENALTY_GAP * gap
+                        };
+                        (previous_score + score + adjacency, previous_i)
+                    })
+                })
+                // Ties go to the earlier position.
+                .rev()
+                .max_by_key(|(score, _)| *score);
+        }
+    }
+
+    let (mut subject_i, (score, _)) = best[pattern.len() - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(subject_i, cell)| cell.map(|cell| (subject_i, cell)))
+        .rev()
+        .max_by_key(|(_, (score, _))| *score)?;
+
+    let mut positions = vec![subject_i; pattern.len()];
+    for pattern_i in (1..pattern.len()).rev() {
+        subject_i = best[pattern_i][subject_i].unwrap().1;
+        positions[pattern_i - 1] = subject_i;
+    }
+
+    Some(FuzzyMatch {
+        score: score - FUZZY_PENALTY_LENGTH * (subject.len() - pattern.len()) as i32,
+        positions,
+    })
+}
+
 pub fn random_integer(min: i32, max: i32) -> i32 {
     (random::<i32>() % (max - min)) + min
 }
 
 pub fn random_string(len: usize) -> String {
     (0..len)
-        .into_iter()
         .map(|_| (random::<u8>() % (b'z' - b'a' + 1) + b'a') as char)
         .collect()
 }
@@ -81,6 +204,102 @@ pub fn random_word() -> String {
     words[random::<usize>() % len].clone()
 }
 
+// Earlier versions used `//` comments, which are not valid GraphQL. Rewrites them to `#`, both at the start of
+// lines and trailing ones, except within strings and block strings.
+pub fn migrate_legacy_comments(source: &str) -> String {
+    let mut is_in_block_string = false;
+
+    source
+        .split('\n')
+        .map(|line| {
+            let (comment_start, is_in_block_string_after) =
+                scan_legacy_comment(line, is_in_block_string);
+            is_in_block_string = is_in_block_string_after;
+
+            match comment_start {
+                Some(i) => format!("{}#{}", &line[..i], &line[i + 2..]),
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Where a `//` comment starts on the line (byte index), and whether a `"""` block string is still open at the
+// end of the line.
+fn scan_legacy_comment(line: &str, mut is_in_block_string: bool) -> (Option<usize>, bool) {
+    let chars = line.char_indices().collect::<Vec<_>>();
+    let starts_with = |i: usize, pattern: &str| {
+        chars[i..]
+            .iter()
+            .map(|(_, ch)| *ch)
+            .take(pattern.len())
+            .eq(pattern.chars())
+    };
+    let mut is_in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let is_block_quote = starts_with(i, "\"\"\"");
+
+        if is_in_block_string {
+            if starts_with(i, "\\\"\"\"") {
+                i += 3;
+            } else if is_block_quote {
+                is_in_block_string = false;
+                i += 2;
+            }
+        } else if is_in_string {
+            match chars[i].1 {
+                '\\' => i += 1,
+                '"' => is_in_string = false,
+                _ => {}
+            }
+        } else {
+            match chars[i].1 {
+                '#' => break,
+                '/' if starts_with(i, "//") => return (Some(chars[i].0), false),
+                '"' if is_block_quote => {
+                    is_in_block_string = true;
+                    i += 2;
+                }
+                '"' => is_in_string = true,
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+
+    (None, is_in_block_string)
+}
+
+// Migrates the `.graphql` files of a folder (recursively), returns the number of changed files.
+pub fn migrate_legacy_comment_files(folder: &Path) -> io::Result<usize> {
+    let mut changed_files = 0;
+
+    for dir_entry in fs::read_dir(folder)? {
+        let path = dir_entry?.path();
+
+        if path.is_dir() {
+            changed_files += migrate_legacy_comment_files(&path)?;
+        } else if path
+            .extension()
+            .map(|ext| ext == "graphql")
+            .unwrap_or(false)
+        {
+            let source = fs::read_to_string(&path)?;
+            let migrated = migrate_legacy_comments(&source);
+
+            if migrated != source {
+                fs::write(&path, migrated)?;
+                changed_files += 1;
+            }
+        }
+    }
+
+    Ok(changed_files)
+}
+
 pub fn err_ctx<E: Display>(context: &str) -> impl FnOnce(E) -> Error + use<'_, E> {
     move |err| format!("{} (caused by: {})", context, err).into()
 }
@@ -89,6 +308,39 @@ pub fn err_ctx<E: Display>(context: &str) -> impl FnOnce(E) -> Error + use<'_, E
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_migrate_legacy_comments() {
+        assert_eq!(
+            "# Users\n{ users(name: \"//\") { id }\n# ids only\n}\n",
+            migrate_legacy_comments("// Users\n{ users(name: \"//\") { id }\n// ids only\n}\n")
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_comments_trailing() {
+        assert_eq!(
+            "{\n  users(name: \"é//\") { # \"users\"\n    id # note\n  }\n}",
+            migrate_legacy_comments(
+                "{\n  users(name: \"é//\") { // \"users\"\n    id // note\n  }\n}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_comments_keeps_strings() {
+        let source = "{ users(name: \"a \\\" // b\") { id } }";
+        assert_eq!(source, migrate_legacy_comments(source));
+
+        let source = "{ users(name: \"é\") { id # ids // only\n} }";
+        assert_eq!(source, migrate_legacy_comments(source));
+
+        let source = "mutation {\n  post(body: \"\"\"\n    Links:\n// https://example.com\n  \\\"\"\" \"\"\")\n}\n// Done";
+        assert_eq!(
+            source.replace("\n// Done", "\n# Done"),
+            migrate_legacy_comments(source)
+        );
+    }
+
     #[test]
     fn test_fuzzy_match_exact_match() {
         assert_eq!(fuzzy_match("hello", "hello"), Some(vec![0, 1, 2, 3, 4]));
@@ -125,6 +377,45 @@ mod tests {
         assert_eq!(fuzzy_match("fibonacci", "fbi"), Some(vec![0, 2, 8]));
     }
 
+    fn score(subject: &str, pattern: &str) -> i32 {
+        fuzzy_match_scored(subject, pattern).unwrap().score
+    }
+
+    #[test]
+    fn test_fuzzy_match_scored_positions() {
+        assert_eq!(None, fuzzy_match_scored("hello", "world"));
+        assert_eq!(
+            Some(vec![]),
+            fuzzy_match_scored("hello", "").map(|m| m.positions)
+        );
+        // Prefers the consecutive `lo` over the first `l`.
+        assert_eq!(
+            Some(vec![0, 3, 4]),
+            fuzzy_match_scored("hello", "hlo").map(|m| m.positions)
+        );
+        // Prefers the word starts.
+        assert_eq!(
+            Some(vec![0, 3, 9]),
+            fuzzy_match_scored("nonMemberCount", "nmc").map(|m| m.positions)
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match_scored_ranking() {
+        // Prefix over contained.
+        assert!(score("posts", "pos") > score("reposts", "pos"));
+        // Consecutive over scattered.
+        assert!(score("createdAt", "crea") > score("currentArea", "crea"));
+        // Word boundary over mid-word.
+        assert!(score("user_id", "id") > score("valid", "id"));
+        assert!(score("createdAt", "at") > score("theatre", "at"));
+        // Same case.
+        assert!(score("id", "id") > score("ID", "id"));
+        // Shorter over longer.
+        assert!(score("name", "nm") > score("nonMemberCount", "nm"));
+        assert!(score("user", "user") > score("users", "user"));
+    }
+
     #[test]
     fn test_trim_empty_list() {
         let input: Vec<(String, Option<usize>)> = vec![];
@@ -212,4 +503,4 @@ mod tests {
             "\x1B[31mhello\x1B[0m"
         );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,704 @@
+use std::fmt::Display;
+
+use crate::{
+    ast::{self, Field, ParamValue},
+    schema::{self, Schema, Type, TypeClass},
+    span::Span,
+    tokenizer::Token,
+};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Severity {
+    Error,
+    // Valid, but worth changing (eg.: deprecated fields).
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+/**
+ * Checks the whole document against the schema (unlike the analyzer, which only walks to the cursor).
+ * Names that are still empty (being typed) are skipped.
+ */
+pub struct Validator<'a> {
+    schema: &'a Schema,
+    document: &'a ast::Document,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    pub fn validate(schema: &'a Schema, document: &'a ast::Document) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            schema,
+            document,
+            diagnostics: vec![],
+        };
+
+        for root in &document.operations {
+            validator.validate_root(root);
+        }
+        for fragment in &document.fragments {
+            validator.validate_fragment_definition(fragment);
+        }
+        validator.validate_directives();
+
+        validator
+            .diagnostics
+            .sort_by_key(|diagnostic| diagnostic.span.offset);
+        validator.diagnostics
+    }
+
+    fn validate_root(&mut self, root: &ast::Root) {
+        if let Some(variable_definitions) = root.variable_definitions() {
+            for definition in &variable_definitions.definitions {
+                let type_name = definition.var_type.named_type();
+                match self.named_type(type_name) {
+                    Some(ty) if !ty.is_input_type() => self.report(
+                        type_name,
+                        format!(
+                            "Variable ${} cannot be of non-input type {}",
+                            definition.variable.name.original, definition.var_type
+                        ),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+
+        let (root_name, operation_name) = match root {
+            ast::Root::Query(_) => (Some(&self.schema.query_root_name), "Query"),
+            ast::Root::Mutation(_) => (self.schema.mutation_root_name.as_ref(), "Mutation"),
+            ast::Root::Subscription(_) => {
+                (self.schema.subscription_root_name.as_ref(), "Subscription")
+            }
+        };
+        match root_name.and_then(|root_name| self.schema.type_definition(root_name)) {
+            Some(scope) => self.validate_field_list(root.field_list(), scope),
+            None => {
+                if let Some(field) = root.field_list().fields.first() {
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        span: field_span(field),
+                        message: format!("{} is not supported by the schema", operation_name),
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_fragment_definition(&mut self, fragment: &ast::FragmentDefinition) {
+        let Some(scope) = self.named_type(&fragment.type_condition) else {
+            return;
+        };
+        if !scope.is_composite_type() {
+            self.report(
+                &fragment.type_condition,
+                format!(
+                    "Fragment {} cannot condition on non composite type {}",
+                    fragment.name.original,
+                    scope.name()
+                ),
+            );
+            return;
+        }
+
+        self.validate_field_list(&fragment.field_list, scope);
+    }
+
+    fn validate_field_list(&mut self, field_list: &ast::FieldList, scope: &Type) {
+        for field in &field_list.fields {
+            match field {
+                Field::Concrete(field) => self.validate_concrete_field(field, scope),
+                Field::Union(field) => self.validate_inline_fragment(field, scope),
+                Field::FragmentSpread(field) => self.validate_fragment_spread(field, scope),
+            }
+        }
+    }
+
+    fn validate_concrete_field(&mut self, field: &ast::ConcreteField, scope: &Type) {
+        let name = &field.name.original;
+        if name.is_empty() {
+            return;
+        }
+
+        let Some(field_definition) = self.schema.field_definition(scope, name) else {
+            let message = match scope {
+                Type::Union(_) => format!(
+                    "Field {} cannot be selected directly on union {}, use an inline fragment",
+                    name,
+                    scope.name()
+                ),
+                _ => format!("Unknown field {} on type {}", name, scope.name()),
+            };
+            self.report(&field.name, message);
+            return;
+        };
+        self.warn_deprecated(
+            &field.name,
+            format!("Field {}.{}", scope.name(), name),
+            &field_definition.deprecation_reason,
+        );
+
+        self.validate_arglist(
+            field.arglist.as_ref(),
+            &field_definition.args,
+            &field.name,
+            &format!("field {}", name),
+            "argument",
+        );
+
+        let Some(field_type) = field_definition
+            .field_type
+            .underlying_type_name()
+            .and_then(|type_name| self.schema.type_definition(type_name))
+        else {
+            return;
+        };
+
+        let has_selection = field
+            .field_list
+            .as_ref()
+            .is_some_and(|field_list| !field_list.fields.is_empty());
+        if field_type.is_composite_type() {
+            if !has_selection {
+                self.report(
+                    &field.name,
+                    format!(
+                        "Field {} of type {} must have a selection of subfields",
+                        name,
+                        field_definition.field_type.signature()
+                    ),
+                );
+            }
+        } else if field.field_list.is_some() {
+            self.report(
+                &field.name,
+                format!(
+                    "Field {} of leaf type {} must not have a selection",
+                    name,
+                    field_definition.field_type.signature()
+                ),
+            );
+        }
+
+        if let Some(field_list) = &field.field_list {
+            if field_type.is_composite_type() {
+                self.validate_field_list(field_list, field_type);
+            }
+        }
+    }
+
+    fn validate_inline_fragment(&mut self, field: &ast::UnionField, scope: &Type) {
+        let Some(condition_type) = self.named_type(&field.type_name) else {
+            return;
+        };
+
+        if !condition_type.is_composite_type() {
+            self.report(
+                &field.type_name,
+                format!(
+                    "Inline fragment cannot condition on non composite type {}",
+                    condition_type.name()
+                ),
+            );
+            return;
+        }
+        if !self
+            .schema
+            .type_condition_applies(scope, &field.type_name.original)
+        {
+            self.report(
+                &field.type_name,
+                format!(
+                    "Inline fragment on {} cannot be spread in {}",
+                    condition_type.name(),
+                    scope.name()
+                ),
+            );
+        }
+
+        self.validate_field_list(&field.field_list, condition_type);
+    }
+
+    fn validate_fragment_spread(&mut self, field: &ast::FragmentSpread, scope: &Type) {
+        let name = &field.name.original;
+        if name.is_empty() {
+            return;
+        }
+
+        let Some(fragment) = self.document.fragment(name) else {
+            self.report(&field.name, format!("Unknown fragment {}", name));
+            return;
+        };
+
+        let condition_exists = self
+            .schema
+            .type_definition(&fragment.type_condition.original)
+            .is_some();
+        if condition_exists
+            && !self
+                .schema
+                .type_condition_applies(scope, &fragment.type_condition.original)
+        {
+            self.report(
+                &field.name,
+                format!(
+                    "Fragment {} on {} cannot be spread in {}",
+                    name,
+                    fragment.type_condition.original,
+                    scope.name()
+                ),
+            );
+        }
+    }
+
+    // Unknown directives, directives on locations they are not defined for and their arguments.
+    fn validate_directives(&mut self) {
+        for (directive, location) in self.document.directives() {
+            let name = &directive.name.original;
+            if name.is_empty() {
+                continue;
+            }
+
+            let Some(directive_definition) = self.schema.directive(name) else {
+                self.report(&directive.name, format!("Unknown directive @{}", name));
+                continue;
+            };
+
+            if !directive_definition
+                .locations
+                .iter()
+                .any(|defined_location| defined_location == location.as_str())
+            {
+                self.report(
+                    &directive.name,
+                    format!(
+                        "Directive @{} is not allowed on {}",
+                        name,
+                        location.as_str()
+                    ),
+                );
+            }
+
+            self.validate_arglist(
+                directive.arglist.as_ref(),
+                &directive_definition.args,
+                &directive.name,
+                &format!("directive @{}", name),
+                "argument",
+            );
+        }
+    }
+
+    // Unknown, duplicate and missing required arguments (or input object fields) and their values.
+    fn validate_arglist(
+        &mut self,
+        arglist: Option<&ast::ArgList>,
+        definitions: &schema::ArgList,
+        owner_token: &Token,
+        owner: &str,
+        item: &str,
+    ) {
+        let params = arglist
+            .map(|arglist| arglist.params.as_slice())
+            .unwrap_or_default();
+
+        for (i, param) in params.iter().enumerate() {
+            let name = &param.key.original;
+            if name.is_empty() {
+                continue;
+            }
+
+            if params[..i]
+                .iter()
+                .any(|previous| &previous.key.original == name)
+            {
+                self.report(&param.key, format!("Duplicate {} {}", item, name));
+                continue;
+            }
+
+            match definitions.arg(name) {
+                Some(definition) => {
+                    self.warn_deprecated(
+                        &param.key,
+                        format!("{} {} on {}", capitalize(item), name, owner),
+                        &definition.deprecation_reason,
+                    );
+                    self.validate_value(&param.value, &definition.arg_type, &param.key);
+                }
+                None => self.report(
+                    &param.key,
+                    format!("Unknown {} {} on {}", item, name, owner),
+                ),
+            }
+        }
+
+        for required_arg in definitions.required_args() {
+            if !params
+                .iter()
+                .any(|param| *param.key.original == *required_arg.name)
+            {
+                self.report(
+                    owner_token,
+                    format!(
+                        "Missing required {} {}: {} on {}",
+                        item,
+                        required_arg.name,
+                        required_arg.arg_type.signature(),
+                        owner
+                    ),
+                );
+            }
+        }
+    }
+
+    // Literal values against the expected type. Variables and magic tokens are only known at execution.
+    fn validate_value(&mut self, value: &ParamValue, expected: &TypeClass, key: &Token) {
+        match (expected, value) {
+            (_, ParamValue::Variable(_) | ParamValue::Magic(_) | ParamValue::Missing(_)) => {}
+            (TypeClass::NonNull(_), ParamValue::Null(token)) => self.report(
+                token,
+                format!(
+                    "Expected value of type {}, found null",
+                    expected.signature()
+                ),
+            ),
+            (TypeClass::NonNull(inner), _) => self.validate_value(value, inner, key),
+            (_, ParamValue::Null(_)) => {}
+            (TypeClass::List(inner), ParamValue::List(list)) => {
+                for elem in &list.elems {
+                    self.validate_value(elem, inner, key);
+                }
+            }
+            // A single value is coerced to a list of one.
+            (TypeClass::List(inner), _) => self.validate_value(value, inner, key),
+            (TypeClass::Scalar(scalar_name), _) if scalar_accepts(scalar_name, value) => {}
+            (TypeClass::Enum(enum_name), ParamValue::Enum(token)) => {
+                if let Some(Type::Enum(enum_type)) = self.schema.type_definition(enum_name) {
+                    match enum_type.value(&token.original) {
+                        Some(enum_value) => self.warn_deprecated(
+                            token,
+                            format!("Enum value {}.{}", enum_name, token.original),
+                            &enum_value.deprecation_reason,
+                        ),
+                        None => self.report(
+                            token,
+                            format!(
+                                "Value {} does not exist in enum {}",
+                                token.original, enum_name
+                            ),
+                        ),
+                    }
+                }
+            }
+            (TypeClass::Input(input_name), ParamValue::Object(object)) => {
+                if let Some(Type::InputObject(input_type)) = self.schema.type_definition(input_name)
+                {
+                    self.validate_arglist(
+                        Some(object),
+                        &input_type.args,
+                        key,
+                        &format!("input {}", input_name),
+                        "field",
+                    );
+                }
+            }
+            // Output types are not valid for arguments, the schema would not have them.
+            (TypeClass::Object(_) | TypeClass::Interface(_) | TypeClass::Union(_), _) => {}
+            _ => {
+                let (token, found) = match value {
+                    ParamValue::List(_) => (key, "a list".to_string()),
+                    ParamValue::Object(_) => (key, "an object".to_string()),
+                    _ => (value.as_scalar(), value.as_scalar().original.clone()),
+                };
+                self.report(
+                    token,
+                    format!(
+                        "Expected value of type {}, found {}",
+                        expected.signature(),
+                        found
+                    ),
+                );
+            }
+        }
+    }
+
+    // The type definition of a referenced type name, reporting it when unknown.
+    fn named_type(&mut self, token: &Token) -> Option<&'a Type> {
+        if token.original.is_empty() {
+            return None;
+        }
+
+        let ty = self.schema.type_definition(&token.original);
+        if ty.is_none() {
+            self.report(token, format!("Unknown type {}", token.original));
+        }
+        ty
+    }
+
+    fn report(&mut self, token: &Token, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            span: token.span,
+            message,
+        });
+    }
+
+    fn warn_deprecated(
+        &mut self,
+        token: &Token,
+        item: String,
+        deprecation_reason: &Option<String>,
+    ) {
+        if let Some(deprecation_reason) = deprecation_reason {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                span: token.span,
+                message: format!("{} is deprecated: {}", item, deprecation_reason),
+            });
+        }
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Built-in scalars check the literal kind. Custom scalars define their own literal format.
+fn scalar_accepts(scalar_name: &str, value: &ParamValue) -> bool {
+    match scalar_name {
+        "Int" => matches!(value, ParamValue::Int(token) if token.original.parse::<i32>().is_ok()),
+        "Float" => matches!(value, ParamValue::Int(_) | ParamValue::Float(_)),
+        "String" => matches!(value, ParamValue::String(_) | ParamValue::BlockString(_)),
+        "Boolean" => matches!(value, ParamValue::Boolean(_)),
+        "ID" => matches!(
+            value,
+            ParamValue::Int(_) | ParamValue::String(_) | ParamValue::BlockString(_)
+        ),
+        _ => true,
+    }
+}
+
+// Location of the name the field is referred by.
+fn field_span(field: &Field) -> Span {
+    match field {
+        Field::Concrete(field) => field.alias.as_ref().unwrap_or(&field.name).span,
+        Field::Union(field) => field.type_name.span,
+        Field::FragmentSpread(field) => field.name.span,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        parser::Parser,
+        schema::fixture,
+        span::Span,
+        tokenizer::Tokenizer,
+        validator::{Diagnostic, Severity},
+    };
+
+    use super::Validator;
+
+    fn validate(raw: &str) -> Vec<String> {
+        let (document, errors) = Parser::new(Tokenizer::tokenize(raw, false)).parse();
+        assert!(errors.is_empty());
+
+        Validator::validate(&fixture::schema(), &document)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let diagnostics = validate(
+            r#"
+            query Q($id: ID!) {
+                user(id: $id) { id name posts(first: 2) { title } }
+                search(term: "a") { ... on User { name } ...PostFields }
+                version
+            }
+            fragment PostFields on Post { title author { __typename } }
+            "#,
+        );
+
+        assert_eq!(Vec::<String>::new(), diagnostics);
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        assert_eq!(
+            vec![
+                "Unknown type Identifier",
+                "Unknown field email on type User",
+                "Unknown argument limit on field posts",
+                "Unknown fragment Missing",
+            ],
+            validate(
+                "query($id: Identifier) { user(id: 1) { email posts(limit: 1) { id } ...Missing } }"
+            )
+        );
+    }
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!(
+            vec![
+                "Missing required argument id: ID! on field user",
+                "Duplicate argument first",
+                "Missing required argument if: Boolean! on directive @include",
+            ],
+            validate("{ user { id } users(first: 1, first: 2) { id @include } }")
+        );
+    }
+
+    #[test]
+    fn test_argument_values() {
+        assert_eq!(
+            vec![
+                "Expected value of type ID, found true",
+                "Expected value of type Int, found \"2\"",
+                "Value OWNER does not exist in enum Role",
+                "Expected value of type Int, found 3000000000",
+                "Expected value of type String!, found null",
+            ],
+            validate(
+                r#"{
+                    user(id: true) { id }
+                    users(first: "2", filter: { role: OWNER }) { posts(first: 3000000000) { id } }
+                    search(term: null) { __typename }
+                }"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_input_object_values() {
+        assert_eq!(
+            vec![
+                "Missing required field name: String! on input CreateUserInput",
+                "Unknown field email on input CreateUserInput",
+                "Expected value of type String, found 1",
+                "Expected value of type CreateUserInput, found a list",
+            ],
+            validate(
+                r#"mutation {
+                    a: createUser(input: { email: "a" }) { id }
+                    b: createUser(input: { name: "b", tags: ["x", 1], role: ADMIN }) { id }
+                    c: createUser(input: [{ name: "c" }]) { id }
+                    d: createUser(input: { name: "d", tags: "single" }) { id }
+                }"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_deprecation_warnings() {
+        let (document, _) = Parser::new(Tokenizer::tokenize(
+            "{ users(offset: 1, filter: { role: GUEST }) { username } }",
+            false,
+        ))
+        .parse();
+        let diagnostics = Validator::validate(&fixture::schema(), &document);
+
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+        assert_eq!(
+            vec![
+                "Argument offset on field users is deprecated: Use cursors.",
+                "Enum value Role.GUEST is deprecated: Sign up instead.",
+                "Field User.username is deprecated: Use name.",
+            ],
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_selections() {
+        assert_eq!(
+            vec![
+                "Field user of type User must have a selection of subfields",
+                "Field version of leaf type String! must not have a selection",
+                "Field posts of type [Post] must have a selection of subfields",
+            ],
+            validate("{ user(id: 1) version { length } users { posts {} } }")
+        );
+    }
+
+    #[test]
+    fn test_type_conditions() {
+        assert_eq!(
+            vec![
+                "Inline fragment on Post cannot be spread in User",
+                "Fragment F on Post cannot be spread in User",
+                "Fragment S cannot condition on non composite type String",
+            ],
+            validate(
+                "{ user(id: 1) { ... on Post { id } ...F } } fragment F on Post { id } fragment S on String { length }"
+            )
+        );
+    }
+
+    #[test]
+    fn test_meta_fields() {
+        assert_eq!(
+            Vec::<String>::new(),
+            validate(
+                r#"{
+                    __typename
+                    __schema { queryType { name } }
+                    __type(name: "User") { name }
+                    search(term: "a") { __typename ... on Node { id } }
+                }"#
+            )
+        );
+        assert_eq!(
+            vec![
+                "Unknown field __schema on type Mutation",
+                "Field id cannot be selected directly on union SearchResult, use an inline fragment",
+                "Missing required argument name: String! on field __type",
+            ],
+            validate(
+                "mutation { __schema { queryType { name } } } query { search(term: \"a\") { id } __type { name } }"
+            )
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_location() {
+        let (document, _) = Parser::new(Tokenizer::tokenize(
+            "{\n  user(id: 1) {\n    email\n  }\n}",
+            false,
+        ))
+        .parse();
+
+        assert_eq!(
+            vec![Diagnostic {
+                severity: Severity::Error,
+                span: Span::new(22, 22, 3, 5),
+                message: "Unknown field email on type User".to_string(),
+            }],
+            Validator::validate(&fixture::schema(), &document)
+        );
+    }
+}